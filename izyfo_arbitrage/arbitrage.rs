@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use chrono::{TimeZone, Utc};
@@ -8,12 +9,31 @@ use chrono::{NaiveDate, NaiveDateTime};
 use chrono::prelude::*;
 use chrono::prelude::DateTime;
 use log::{debug, error, info, trace, warn};
+use opentelemetry::Context;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::izyfo_arbitrage::arbitrage_transaction::{ArbitrageTransaction, ArbitrageTransactionResult};
+use crate::izyfo_arbitrage::arbitrage_clock::{Clock, SystemClock};
+use crate::izyfo_arbitrage::arbitrage_exchange::strip_prefix;
+use crate::izyfo_arbitrage::arbitrage_transaction::{ArbitrageTransaction, ArbitrageTransactionConfig, ArbitrageTransactionResult, OrderingConstraint, OrderType, TimeInForce};
 use crate::izyfo_events::exchange::market_bbo::MarketBBO;
 
+// Arbitrage Ordering Failure
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArbitrageOrderingFailure {
+    pub leg: usize,
+    pub name: String,
+    pub constraints: Vec<OrderingConstraint>,
+}
+
+// Validation Warning
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidationWarning {
+    pub name: String,
+    pub message: String,
+    pub create_at: DateTime<Utc>,
+}
+
 // Arbitrage Profit
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ArbitrageProfit {
@@ -23,6 +43,8 @@ pub struct ArbitrageProfit {
     transaction_result_list: Vec<ArbitrageTransactionResult>,
     create_at: DateTime<Utc>,
     uuid: Uuid,
+    #[serde(skip)]
+    trace_context: Context,
 }
 
 impl fmt::Display for ArbitrageProfit {
@@ -33,14 +55,107 @@ impl fmt::Display for ArbitrageProfit {
 }
 
 impl ArbitrageProfit {
-    // return profit
-    pub fn get_profit(&self) -> f32 {
-        return self.transaction_result_list[2].get_qty_out() - self.transaction_result_list[0].get_qty_in();
+    // return profit, net of fees as each leg qty out has its fee deducted
+    pub fn get_profit(&self) -> f64 {
+        return self.get_qty_out() - self.get_qty_in();
+    }
+
+    // return qty out before fees, each leg fee is converted through the following legs
+    fn get_gross_qty_out(&self) -> f64 {
+        let mut qty_out_ratio: f64 = 1.0;
+        for t in &self.transaction_result_list {
+            if t.get_qty_out() > 0.0 {
+                qty_out_ratio *= (t.get_qty_out() + t.get_fee()) / t.get_qty_out();
+            }
+        }
+        return self.get_qty_out() * qty_out_ratio;
+    }
+
+    // return profit before fees, in the start asset
+    pub fn get_gross_profit(&self) -> f64 {
+        return self.get_gross_qty_out() - self.get_qty_in();
+    }
+
+    // return fees of all legs, in the start asset
+    pub fn get_total_fee(&self) -> f64 {
+        return self.get_gross_qty_out() - self.get_qty_out();
+    }
+
+    // return profit after the fees of all legs, in the start asset
+    pub fn get_net_profit(&self) -> f64 {
+        return self.get_gross_profit() - self.get_total_fee();
+    }
+
+    // return net profit as a percentage of qty in
+    pub fn get_profit_pct(&self) -> f64 {
+        let qty_in = self.get_qty_in();
+        if qty_in <= 0.0 {
+            return 0.0;
+        }
+        return self.get_net_profit() / qty_in * 100.0;
+    }
+
+    // return minimum start asset input meeting every leg min qty and min notional, legs scale linearly with qty in
+    // a notional minimum keeps one step of margin for the qty floor, none if a leg has no qty to infer its scale
+    pub fn get_min_capital(&self) -> Option<f64> {
+        let qty_in = self.get_qty_in();
+        if qty_in <= 0.0 {
+            return None;
+        }
+
+        let mut min_capital: f64 = 0.0;
+        for transaction in &self.transaction_result_list {
+            if (transaction.get_qty_to_execute() <= 0.0) | (transaction.get_price() <= 0.0) {
+                return None;
+            }
+
+            // leg base qty per unit of start asset
+            let ratio = transaction.get_qty_to_execute() / qty_in;
+
+            let mut min_qty = transaction.get_min_qty();
+            if transaction.get_min_notional() > 0.0 {
+                min_qty = min_qty.max(transaction.get_min_notional() / transaction.get_price() + transaction.get_step_size());
+            }
+            min_capital = min_capital.max(min_qty / ratio);
+        }
+        return Some(min_capital);
+    }
+
+    // return profit recomputed with the realized commission of each leg, in the leg output asset
+    pub fn recompute_with_realized_fees(&self, commissions: &Vec<f64>) -> f64 {
+        let mut qty_out_ratio: f64 = 1.0;
+        for (t, commission) in self.transaction_result_list.iter().zip(commissions.iter()) {
+            if t.get_qty_out() > 0.0 {
+                let qty_out_gross = t.get_qty_out() + t.get_fee();
+                qty_out_ratio *= (qty_out_gross - commission) / t.get_qty_out();
+            }
+        }
+        return self.get_qty_out() * qty_out_ratio - self.get_qty_in();
+    }
+
+    // return worst-case loss when every leg but the last fills and the last is unwound at market
+    pub fn get_max_loss_if_last_leg_fails(&self) -> f64 {
+        let last = &self.transaction_result_list[self.transaction_result_list.len() - 1];
+        return self.get_qty_in() - last.get_unwind_qty_out();
     }
 
     // return distance
     pub fn get_distance(&self) -> f64 {
-        return self.transaction_result_list[2].get_tick_timestamp() - self.transaction_result_list[0].get_tick_timestamp();
+        match (self.transaction_result_list.first(), self.transaction_result_list.last()) {
+            (Some(first), Some(last)) => last.get_tick_timestamp() - first.get_tick_timestamp(),
+            _ => 0.0
+        }
+    }
+
+    // return time spanned by the leg quotes, in milliseconds
+    pub fn get_leg_distance_ms(&self) -> f64 {
+        let timestamps = self.transaction_result_list.iter().map(|t| t.get_tick_timestamp());
+        let min = timestamps.clone().fold(f64::INFINITY, f64::min);
+        let max = timestamps.fold(f64::NEG_INFINITY, f64::max);
+        if min > max {
+            return 0.0;
+        }
+        return (max - min) * 1000_f64;
     }
 
     // return name
@@ -54,13 +169,13 @@ impl ArbitrageProfit {
     }
 
     // return qty in
-    pub fn get_qty_in(&self) -> f32 {
-        return self.transaction_result_list[0].get_qty_in();
+    pub fn get_qty_in(&self) -> f64 {
+        return self.transaction_result_list.first().map(|t| t.get_qty_in()).unwrap_or(0.0);
     }
 
     // return qty out
-    pub fn get_qty_out(&self) -> f32 {
-        return self.transaction_result_list[2].get_qty_out();
+    pub fn get_qty_out(&self) -> f64 {
+        return self.transaction_result_list.last().map(|t| t.get_qty_out()).unwrap_or(0.0);
     }
 
     // return transaction result list
@@ -68,13 +183,13 @@ impl ArbitrageProfit {
         return &self.transaction_result_list;
     }
 
-    // return asset list
-    pub fn get_asset_list(&self) -> Vec<String> {
+    // return asset list, without the exchange prefix
+    pub fn get_asset_list(&self, prefix: &str) -> Vec<String> {
         let mut asset_list: Vec<String> = Vec::new();
 
         for transaction in &self.transaction_result_list {
-            let source = transaction.get_source().to_string().replace("BINANCE_", "");
-            let target = transaction.get_target().to_string().replace("BINANCE_", "");
+            let source = strip_prefix(transaction.get_source(), prefix);
+            let target = strip_prefix(transaction.get_target(), prefix);
 
 
             // insert base
@@ -101,11 +216,36 @@ impl ArbitrageProfit {
         return self.uuid;
     }
 
+    // return trace context of the profit emission, the ordering spans of the cycle are its children
+    pub fn get_trace_context(&self) -> &Context {
+        return &self.trace_context;
+    }
+
+    // set trace context
+    pub fn set_trace_context(&mut self, trace_context: Context) {
+        self.trace_context = trace_context;
+    }
+
+    // return create at
+    pub fn get_create_at(&self) -> DateTime<Utc> {
+        return self.create_at;
+    }
+
+    // check created within [start, end)
+    pub fn is_created_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        return (self.create_at >= start) & (self.create_at < end);
+    }
+
+    // return start asset, without the exchange prefix
+    pub fn get_start_asset(&self, prefix: &str) -> String {
+        return strip_prefix(self.transaction_result_list[0].get_source(), prefix);
+    }
+
     // check valid
     pub fn is_valid_ordering(&self) -> bool {
         for t in &self.transaction_result_list {
             if !t.is_valid_ordering() {
-                warn!("arbitrage_profit - invalid transaction. {:?}", t);
+                warn!("arbitrage_profit - invalid transaction. failures: {:?}, {:?}", t.get_ordering_failures(), t);
                 return false;
             } else {
                 info!("arbitrage_profit - valid transaction. {:?}", t);
@@ -113,6 +253,22 @@ impl ArbitrageProfit {
         }
         return true;
     }
+
+    // return failing legs and constraints
+    pub fn get_ordering_failures(&self) -> Vec<ArbitrageOrderingFailure> {
+        let mut failures: Vec<ArbitrageOrderingFailure> = Vec::new();
+        for (index, t) in self.transaction_result_list.iter().enumerate() {
+            let constraints = t.get_ordering_failures();
+            if !constraints.is_empty() {
+                failures.push(ArbitrageOrderingFailure {
+                    leg: index,
+                    name: t.get_name().clone(),
+                    constraints: constraints,
+                });
+            }
+        }
+        return failures;
+    }
 }
 
 // Arbitrage
@@ -121,11 +277,23 @@ pub struct Arbitrage {
     transaction_list: Vec<ArbitrageTransaction>,
     pub instrument_list: Vec<String>,
     markets: HashMap<String, MarketBBO>,
+    clock: Arc<dyn Clock>,
+    validation_sender: Option<crossbeam_channel::Sender<ValidationWarning>>,
+    readiness_max_age: f64,
+    readiness_ready_time: f64,
+    readiness_total_time: f64,
+    readiness_last: Option<(f64, bool)>,
+    profit_threshold: f64,
+    max_leg_distance_ms: Option<f64>,
+    max_fee_ratio: Option<f64>,
+    binding_leg: Option<String>,
+    binding_leg_deferral: Option<u32>,
+    deferred_ticks: u32,
 }
 
 impl Arbitrage {
     // transaction list
-    pub fn from_transaction_list(transaction_hash_list: &Vec<HashMap<String, String>>) -> Arbitrage {
+    pub fn from_transaction_list(transaction_hash_list: &Vec<HashMap<String, String>>) -> Result<Arbitrage, String> {
         let mut transaction_list: Vec<ArbitrageTransaction> = Vec::new();
 
         for transaction in transaction_hash_list {
@@ -134,15 +302,20 @@ impl Arbitrage {
             let operation = transaction.get("operation").unwrap().to_string();
             let instrument = transaction.get("instrument").unwrap().to_string();
             let exchange_code = transaction.get("exchange_code").unwrap().to_string();
-            let arbitrage_transaction = ArbitrageTransaction::new(source, target, operation, instrument, exchange_code);
+            let mut arbitrage_transaction = ArbitrageTransaction::new(source, target, operation, instrument, exchange_code);
+            if let Some(order_type) = transaction.get("order_type") {
+                arbitrage_transaction.set_order_type(OrderType::from_str(order_type));
+            }
+            if let Some(time_in_force) = transaction.get("time_in_force") {
+                arbitrage_transaction.set_time_in_force(TimeInForce::from_str(time_in_force));
+            }
             transaction_list.push(arbitrage_transaction);
         }
 
-        let name: String = format!(
-            "{}:{}:{}", transaction_list[0].get_name(),
-            transaction_list[1].get_name(),
-            transaction_list[2].get_name()
-        );
+        // check chain
+        Arbitrage::validate_chain(&transaction_list)?;
+
+        let name: String = transaction_list.iter().map(|t| t.get_name().to_string()).collect::<Vec<String>>().join(":");
 
         let mut instrument_list: Vec<String> = Vec::new();
         for transaction in &transaction_list {
@@ -152,18 +325,189 @@ impl Arbitrage {
             }
         }
 
-        Arbitrage {
+        Ok(Arbitrage {
             name: name,
             transaction_list: transaction_list,
             instrument_list: instrument_list,
             markets: HashMap::new(),
+            clock: Arc::new(SystemClock),
+            validation_sender: None,
+            readiness_max_age: 1.0,
+            readiness_ready_time: 0.0,
+            readiness_total_time: 0.0,
+            readiness_last: None,
+            profit_threshold: 0.0,
+            max_leg_distance_ms: None,
+            max_fee_ratio: None,
+            binding_leg: None,
+            binding_leg_deferral: None,
+            deferred_ticks: 0,
+        })
+    }
+
+    // check each leg target is the next leg source and the cycle closes on the start asset
+    fn validate_chain(transaction_list: &Vec<ArbitrageTransaction>) -> Result<bool, String> {
+        if transaction_list.is_empty() {
+            return Err("empty transaction list".to_string());
+        }
+
+        for (index, transaction) in transaction_list.iter().enumerate() {
+            let next = &transaction_list[(index + 1) % transaction_list.len()];
+            if transaction.get_target() != next.get_source() {
+                return Err(format!("invalid chain: '{}' target '{}' does not match '{}' source '{}'",
+                                   transaction.get_name(), transaction.get_target(), next.get_name(), next.get_source()));
+            }
+        }
+        Ok(true)
+    }
+
+    // set transaction config
+    pub fn set_transaction_config(&mut self, config: &ArbitrageTransactionConfig) {
+        for transaction in &mut self.transaction_list {
+            transaction.set_config(config.clone());
+        }
+    }
+
+    // update quotes without evaluating
+    pub fn update(&mut self, market_bbo: &MarketBBO) {
+        for transaction in &mut self.transaction_list {
+            if transaction.get_instrument().to_string() == market_bbo.get_instrument() {
+                transaction.update(market_bbo);
+            }
+        }
+    }
+
+    // update last trade price
+    pub fn update_last_price(&mut self, instrument: &String, last_price: f64) {
+        for transaction in &mut self.transaction_list {
+            if transaction.get_instrument() == instrument {
+                transaction.update_last_price(last_price);
+            }
         }
     }
 
-    // execute market bbo
-    pub fn execute(&mut self, market_bbo: &MarketBBO, qty_initial: f32, scale: bool) -> Option<ArbitrageProfit> {
+    // set validation warning sender
+    pub fn set_validation_sender(&mut self, validation_sender: Option<crossbeam_channel::Sender<ValidationWarning>>) {
+        self.validation_sender = validation_sender;
+    }
+
+    // send validation warning, the only place warnings are emitted, a full or disconnected channel drops it
+    pub fn send_validation_warning(&self, message: String) {
+        if let Some(validation_sender) = &self.validation_sender {
+            if let Err(err) = validation_sender.try_send(ValidationWarning {
+                name: self.name.clone(),
+                message: message,
+                create_at: self.clock.now(),
+            }) {
+                warn!("arbitrage - validation warning dropped. name:{}, error:{}", self.name, err);
+            }
+        }
+    }
+
+    // set maximum quote age (seconds) for a leg to count as fresh
+    pub fn set_readiness_max_age(&mut self, readiness_max_age: f64) {
+        self.readiness_max_age = readiness_max_age;
+    }
+
+    // return fraction of time every leg had a fresh quote
+    pub fn get_readiness_ratio(&self) -> f64 {
+        if self.readiness_total_time <= 0.0 {
+            return 0.0;
+        }
+        return self.readiness_ready_time / self.readiness_total_time;
+    }
+
+    // update readiness, the time since the previous tick is attributed to the previous state
+    fn update_readiness(&mut self, timestamp: f64) {
+        let max_age = self.readiness_max_age;
+        let ready = self.transaction_list.iter().all(|t| t.is_valid().is_ok() & (timestamp - t.get_tick_timestamp() <= max_age));
+
+        if let Some((last_timestamp, last_ready)) = self.readiness_last {
+            let elapsed = timestamp - last_timestamp;
+            if elapsed > 0.0 {
+                self.readiness_total_time += elapsed;
+                if last_ready {
+                    self.readiness_ready_time += elapsed;
+                }
+            }
+        }
+        self.readiness_last = Some((timestamp, ready));
+    }
+
+    // set minimum profit a scaled-down execution must exceed
+    pub fn set_profit_threshold(&mut self, profit_threshold: f64) {
+        self.profit_threshold = profit_threshold;
+    }
+
+    // set maximum time spanned by the leg quotes for a profit to be considered real
+    pub fn set_max_leg_distance_ms(&mut self, max_leg_distance_ms: Option<f64>) {
+        self.max_leg_distance_ms = max_leg_distance_ms;
+    }
+
+    // set maximum fraction of the gross profit the fees of all legs may take
+    pub fn set_max_fee_ratio(&mut self, max_fee_ratio: Option<f64>) {
+        self.max_fee_ratio = max_fee_ratio;
+    }
+
+    // set clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    // set number of consecutive ticks on non-binding legs deferred before evaluating anyway, none evaluates every tick
+    pub fn set_binding_leg_deferral(&mut self, binding_leg_deferral: Option<u32>) {
+        self.binding_leg_deferral = binding_leg_deferral;
+        self.deferred_ticks = 0;
+    }
+
+    // return instrument of the leg whose book size limited the last evaluation
+    pub fn get_binding_leg(&self) -> Option<&String> {
+        return self.binding_leg.as_ref();
+    }
+
+    // check the tick should be evaluated, ticks on non-binding legs are deferred until the deferral is exhausted
+    pub fn should_evaluate(&mut self, market_bbo: &MarketBBO) -> bool {
+        let binding_leg_deferral = match self.binding_leg_deferral {
+            Some(binding_leg_deferral) => binding_leg_deferral,
+            None => return true
+        };
+        let binding = match &self.binding_leg {
+            Some(binding_leg) => *binding_leg == market_bbo.get_instrument(),
+            None => true
+        };
+        if binding | (self.deferred_ticks >= binding_leg_deferral) {
+            self.deferred_ticks = 0;
+            return true;
+        }
+        self.deferred_ticks += 1;
+        return false;
+    }
+
+    // update binding leg, the leg executing the largest fraction of its book size
+    fn update_binding_leg(&mut self, transaction_result_list: &Vec<ArbitrageTransactionResult>) {
+        let mut max_ratio = f64::NEG_INFINITY;
+        for (transaction, t) in self.transaction_list.iter().zip(transaction_result_list.iter()) {
+            let ratio = if t.get_market_qty() > 0.0 { t.get_qty_to_execute() / t.get_market_qty() } else { f64::INFINITY };
+            if ratio > max_ratio {
+                max_ratio = ratio;
+                self.binding_leg = Some(transaction.get_instrument().to_string());
+            }
+        }
+    }
+
+    // execute market bbo, a profit failing the leg distance or fee filters is dropped
+    pub fn execute(&mut self, market_bbo: &MarketBBO, qty_initial: f64, scale: bool) -> Option<ArbitrageProfit> {
+        let arbitrage_profit = self.execute_unfiltered(market_bbo, qty_initial, scale)?;
+        if !self.is_accepted(&arbitrage_profit) {
+            return None;
+        }
+        return Some(arbitrage_profit);
+    }
+
+    // execute market bbo before the leg distance and fee filters, scaled down to the books when scale is set
+    pub fn execute_unfiltered(&mut self, market_bbo: &MarketBBO, qty_initial: f64, scale: bool) -> Option<ArbitrageProfit> {
         // initialize out
-        let mut qty_in: f32 = qty_initial;
+        let mut qty_in: f64 = qty_initial;
         let start_date = Instant::now();
 
 
@@ -173,6 +517,7 @@ impl Arbitrage {
         let mut transaction_result_list: Vec<ArbitrageTransactionResult> = Vec::new();
 
         // list over transaction
+        let mut validation_errors: Vec<String> = Vec::new();
         for transaction in &mut self.transaction_list {
 
             // update tick
@@ -185,56 +530,146 @@ impl Arbitrage {
             match valid_message {
                 Ok(s) => {
                     last_transaction_result = transaction.execute(qty_in);
+
+                    // units
+                    if transaction.is_check_units() & !last_transaction_result.is_valid_units() {
+                        error!("arbitrage - invalid units. name:{}, result:{:?}", transaction.get_name(), last_transaction_result);
+                        validation_errors.push(format!("{}: invalid units. qty_in:{}, qty_to_execute:{}, qty_out:{}", transaction.get_name(),
+                                                       last_transaction_result.get_qty_in(), last_transaction_result.get_qty_to_execute(), last_transaction_result.get_qty_out()));
+                        continue;
+                    }
                     qty_in = last_transaction_result.get_qty_out();
                     transaction_result_list.push(last_transaction_result);
                 }
-                Err(e) => {}
+                Err(e) => {
+                    validation_errors.push(format!("{}: {}", transaction.get_name(), e));
+                }
             }
         }
 
-        if transaction_result_list.len() == 3 {
-            if scale {
-                let mut ratio_list: Vec<f32> = Vec::new();
+        // validation warnings
+        for message in validation_errors {
+            self.send_validation_warning(message);
+        }
 
+        // readiness
+        self.update_readiness(market_bbo.get_marketdata_timestamp());
+
+        if transaction_result_list.len() != self.transaction_list.len() {
+            return None;
+        }
+        self.update_binding_leg(&transaction_result_list);
+
+        if scale {
+            let mut ratio_list: Vec<f64> = Vec::new();
+
+            // ratio list
+            for t in &transaction_result_list {
                 // ratio list
-                for t in transaction_result_list {
-                    // ratio list
-                    if !t.is_valid_ordering() {
-                        let ratio = t.get_qty_to_execute() / t.get_market_qty();
-                        ratio_list.push(ratio);
+                if !t.is_valid_ordering() {
+                    // empty book side, no size can be executed
+                    if t.get_market_qty() <= 0.0 {
+                        warn!("arbitrage - scaling impossible, empty market qty. name:{}, transaction:{}", self.name, t.get_name());
+                        return None;
                     }
+                    let ratio = t.get_qty_to_execute() / t.get_market_qty();
+                    ratio_list.push(ratio);
+                }
+            }
+
+            // max value
+            let max_value = ratio_list.iter().fold(0.0f64, |mut max, &val| {
+                if val > max {
+                    max = val;
                 }
+                max
+            });
+
+            if !max_value.is_finite() {
+                warn!("arbitrage - scaling impossible, invalid ratio. name:{}, ratio:{}", self.name, max_value);
+                return None;
+            }
 
-                // max value
-                let mut max_value = ratio_list.iter().fold(0.0f32, |mut max, &val| {
-                    if val > max {
-                        max = val;
+            // one rescale only, the recursive call does not scale again
+            if max_value > 1.0 {
+                let qty_initial_scaled = qty_initial / max_value;
+                let arbitrage_profit = self.execute_unfiltered(market_bbo, qty_initial_scaled, false);
+
+                // rescaled size must be executable
+                if let Some(p) = &arbitrage_profit {
+                    let executable = p.get_transaction_result_list().iter().all(|t| (t.get_qty_to_execute() > 0.0) & (t.get_qty_to_execute() <= t.get_market_qty()));
+                    if !executable {
+                        warn!("arbitrage - scaling did not produce an executable size. name:{}, qty_in:{}", self.name, qty_initial_scaled);
+                        return None;
                     }
-                    max
-                });
+                }
 
-                if max_value > 1.0 {
-                    let qty_initial_scaled = qty_initial / max_value;
-                    let arbitrage_profit = self.execute(market_bbo, qty_initial_scaled, false);
-                    return arbitrage_profit;
+                // scaled profit must still exceed threshold
+                if let Some(p) = &arbitrage_profit {
+                    if p.get_profit() <= self.profit_threshold {
+                        debug!("arbitrage - scaled profit below threshold. name:{}, qty_in:{}, profit:{}, threshold:{}", self.name, qty_initial_scaled, p.get_profit(), self.profit_threshold);
+                        return None;
+                    }
                 }
-            } else {
-                let arbitrage_profit = ArbitrageProfit {
-                    name: self.name.clone(),
-                    transaction_result_list: transaction_result_list,
-                    tick_timestamp: market_bbo.get_marketdata_timestamp(),
-                    tick_received_timestamp_ms: market_bbo.get_created_timestamp_ms(),
-                    create_at: Utc::now(),
-                    uuid: Uuid::new_v4(),
-                };
-                return Some(arbitrage_profit);
+                return arbitrage_profit;
             }
         }
 
+        // the books hold the full size, no scaling needed
+        let arbitrage_profit = ArbitrageProfit {
+            name: self.name.clone(),
+            transaction_result_list: transaction_result_list,
+            tick_timestamp: market_bbo.get_marketdata_timestamp(),
+            tick_received_timestamp_ms: market_bbo.get_created_timestamp_ms(),
+            create_at: self.clock.now(),
+            uuid: Uuid::new_v4(),
+            trace_context: Context::new(),
+        };
 
         debug!("arbitrage - executed. duration: {:?}", start_date.elapsed());
+        return Some(arbitrage_profit);
+    }
 
-        return None;
+    // check profit passes the leg distance and fee filters
+    pub fn is_accepted(&self, arbitrage_profit: &ArbitrageProfit) -> bool {
+        // leg quotes must be co-fresh
+        if let Some(max_leg_distance_ms) = self.max_leg_distance_ms {
+            let leg_distance_ms = arbitrage_profit.get_leg_distance_ms();
+            if leg_distance_ms > max_leg_distance_ms {
+                debug!("arbitrage - leg distance too large. name:{}, distance_ms:{}, max_distance_ms:{}", self.name, leg_distance_ms, max_leg_distance_ms);
+                return false;
+            }
+        }
+
+        // fees must leave most of the edge
+        if let Some(max_fee_ratio) = self.max_fee_ratio {
+            let gross_profit = arbitrage_profit.get_gross_profit();
+            let total_fee = arbitrage_profit.get_total_fee();
+            if (gross_profit <= 0.0) | (total_fee > max_fee_ratio * gross_profit) {
+                debug!("arbitrage - fees too large. name:{}, gross_profit:{}, total_fee:{}, max_fee_ratio:{}", self.name, gross_profit, total_fee, max_fee_ratio);
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // evaluate against a quote snapshot, none if a leg has no quote
+    pub fn evaluate(&mut self, snapshot: &HashMap<String, MarketBBO>, qty_initial: f64, scale: bool) -> Option<ArbitrageProfit> {
+        let mut last_market_bbo: Option<&MarketBBO> = None;
+        for transaction in &mut self.transaction_list {
+            match snapshot.get(transaction.get_instrument()) {
+                Some(market_bbo) => {
+                    transaction.update(market_bbo);
+                    last_market_bbo = Some(market_bbo);
+                }
+                None => return None
+            }
+        }
+
+        match last_market_bbo {
+            Some(market_bbo) => self.execute(market_bbo, qty_initial, scale),
+            None => None
+        }
     }
 
     // return name
@@ -257,4 +692,464 @@ impl Arbitrage {
     pub fn get_start_asset(&self) -> &String {
         return self.transaction_list[0].get_source();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::izyfo_arbitrage::arbitrage_clock::MockClock;
+    use crate::izyfo_arbitrage::arbitrage_testing::{self, QuoteFixture};
+    use crate::izyfo_arbitrage::arbitrage_transaction::PriceSource;
+    use crate::izyfo_utils::math::RoundingStrategy;
+
+    // quotes of the triangle with a thin first leg, 0.25 ETH on the ask
+    fn thin_quotes() -> Vec<QuoteFixture> {
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[0].ask_qty = 0.25;
+        quotes[0].step_size = 0.001;
+        return quotes;
+    }
+
+    // evaluate the arbitrage on the quotes, scaled
+    fn execute_scaled(arbitrage: &mut Arbitrage, quotes: &Vec<QuoteFixture>, qty_in: f64) -> Option<ArbitrageProfit> {
+        let market_bbo_list: Vec<MarketBBO> = quotes.iter().map(|q| q.build()).collect();
+        for market_bbo in &market_bbo_list {
+            arbitrage.update(market_bbo);
+        }
+        return arbitrage.execute(market_bbo_list.last().unwrap(), qty_in, true);
+    }
+
+    #[test]
+    fn scaled_cycle_below_threshold_returns_none() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_profit_threshold(0.01);
+
+        assert!(execute_scaled(&mut arbitrage, &thin_quotes(), 1.0).is_none());
+    }
+
+    #[test]
+    fn scaled_cycle_above_threshold_is_sized_to_the_book() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_profit_threshold(0.0001);
+
+        let arbitrage_profit = execute_scaled(&mut arbitrage, &thin_quotes(), 1.0).unwrap();
+
+        assert!((arbitrage_profit.get_qty_in() - 0.0125).abs() < 1e-9);
+        assert_eq!(arbitrage_profit.get_transaction_result_list()[0].get_qty_to_execute(), 0.25);
+        assert!(arbitrage_profit.get_profit() > 0.0001);
+    }
+
+    #[test]
+    fn cycle_within_the_books_is_returned_unscaled() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+
+        let arbitrage_profit = execute_scaled(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+
+        assert_eq!(arbitrage_profit.get_qty_in(), 1.0);
+        assert!(arbitrage_profit.get_profit() > 0.0);
+    }
+
+    #[test]
+    fn cycle_within_the_books_is_filtered() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_max_fee_ratio(Some(0.01));
+
+        assert!(execute_scaled(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).is_none());
+        assert!(arbitrage.execute_unfiltered(&arbitrage_testing::triangle_quotes()[2].build(), 1.0, true).is_some());
+    }
+
+    #[test]
+    fn chained_cycle_is_accepted() {
+        assert!(Arbitrage::from_transaction_list(&arbitrage_testing::triangle()).is_ok());
+    }
+
+    #[test]
+    fn mis_chained_cycle_is_rejected() {
+        let mut transactions = arbitrage_testing::triangle();
+        transactions[1] = arbitrage_testing::leg("BNB", "USDT", "SELL", "BNB", "USDT");
+
+        let err = Arbitrage::from_transaction_list(&transactions).err().unwrap();
+
+        assert!(err.contains("invalid chain"));
+        assert!(err.contains("BINANCE_BNB"));
+    }
+
+    #[test]
+    fn last_trade_pricing_ignores_a_quote_flicker() {
+        // the ETH bid flickered up, the last trades did not follow
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1].bid_price = 2100.0;
+        let last_prices = vec![("BINANCE_ETH_BTC", 0.05), ("BINANCE_ETH_USDT", 1900.0), ("BINANCE_BTC_USDT", 38000.0)];
+
+        let mut bbo_arbitrage = arbitrage_testing::triangle_arbitrage();
+        let bbo_profit = arbitrage_testing::profit_of(&mut bbo_arbitrage, &quotes, 1.0).unwrap();
+
+        let mut last_trade_arbitrage = arbitrage_testing::triangle_arbitrage();
+        let mut config = ArbitrageTransactionConfig::default();
+        config.price_source = PriceSource::LAST_TRADE;
+        last_trade_arbitrage.set_transaction_config(&config);
+        for (instrument, last_price) in &last_prices {
+            last_trade_arbitrage.update_last_price(&instrument.to_string(), *last_price);
+        }
+        let last_trade_profit = arbitrage_testing::profit_of(&mut last_trade_arbitrage, &quotes, 1.0).unwrap();
+
+        assert!(bbo_profit.get_profit() > 0.0);
+        assert!(last_trade_profit.get_profit() < 0.0);
+        assert_eq!(last_trade_profit.get_transaction_result_list()[1].get_price(), 1900.0);
+    }
+
+    #[test]
+    fn last_trade_pricing_without_trades_detects_nothing() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        let mut config = ArbitrageTransactionConfig::default();
+        config.price_source = PriceSource::LAST_TRADE;
+        arbitrage.set_transaction_config(&config);
+
+        assert!(arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).is_none());
+    }
+
+    #[test]
+    fn failure_on_leg_2_notional_is_reported() {
+        // 1 BTC sells about 40000 USDT of ETH on leg 2
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1].min_notional = 50000.0;
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+
+        let failures = arbitrage_profit.get_ordering_failures();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].leg, 1);
+        assert_eq!(&failures[0].name, arbitrage_profit.get_transaction_result_list()[1].get_name());
+        assert_eq!(failures[0].constraints, vec![OrderingConstraint::MIN_NOTIONAL]);
+    }
+
+    #[test]
+    fn cycle_within_every_constraint_reports_no_failure() {
+        assert!(arbitrage_testing::triangle_profit(1.0).get_ordering_failures().is_empty());
+    }
+
+    #[test]
+    fn realized_fees_equal_to_predicted_keep_the_profit() {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let commissions: Vec<f64> = arbitrage_profit.get_transaction_result_list().iter().map(|t| t.get_fee()).collect();
+
+        assert!((arbitrage_profit.recompute_with_realized_fees(&commissions) - arbitrage_profit.get_profit()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn realized_fees_above_predicted_reduce_the_profit() {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let commissions: Vec<f64> = arbitrage_profit.get_transaction_result_list().iter().map(|t| t.get_fee() * 2.0).collect();
+
+        let realized_profit = arbitrage_profit.recompute_with_realized_fees(&commissions);
+
+        assert!(realized_profit < arbitrage_profit.get_profit());
+    }
+
+    #[test]
+    fn latency_is_deterministic_under_mock_clock() {
+        let clock = Arc::new(MockClock::new(Utc.timestamp_millis(1250)));
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+
+        // ticks received at 1000ms
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+        assert_eq!(arbitrage_profit.get_latency_ms(), 250);
+
+        clock.advance(chrono::Duration::milliseconds(40));
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+        assert_eq!(arbitrage_profit.get_latency_ms(), 290);
+    }
+
+    #[test]
+    fn worst_case_loss_unwinds_the_last_leg_across_the_spread() {
+        // BTC_USDT quoted 37000/40000, the unwind buys at the ask plus the spread
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[2].bid_price = 37000.0;
+        quotes[2].ask_price = 40000.0;
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+
+        // 19.98 ETH sold for 39920.04 USDT, bought back at 43000 less the fee
+        let expected_loss = 1.0 - 39920.04 / 43000.0 * 0.999;
+        assert!((arbitrage_profit.get_max_loss_if_last_leg_fails() - expected_loss).abs() < 1e-9);
+    }
+
+    #[test]
+    fn validation_failure_is_delivered_on_the_channel() {
+        let (validation_sender, validation_receiver) = crossbeam_channel::unbounded();
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_validation_sender(Some(validation_sender));
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1].bid_qty = 0.0;
+
+        assert!(arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0).is_none());
+
+        let validation_warning = validation_receiver.try_recv().unwrap();
+        assert_eq!(&validation_warning.name, arbitrage.get_name());
+        assert!(validation_warning.message.contains("invalid bid qty"));
+    }
+
+    #[test]
+    fn validation_warning_is_dropped_once_the_channel_is_full() {
+        let (validation_sender, validation_receiver) = crossbeam_channel::bounded(1);
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_validation_sender(Some(validation_sender));
+
+        arbitrage.send_validation_warning("first".to_string());
+        arbitrage.send_validation_warning("second".to_string());
+
+        assert_eq!(validation_receiver.try_recv().unwrap().message, "first");
+        assert!(validation_receiver.try_recv().is_err());
+    }
+
+    // feed the triangle quotes ticking each second, the BTC_USDT leg only at the first tick when rare
+    fn readiness_ratio_of(rare_last_leg: bool) -> f64 {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_readiness_max_age(1.0);
+        for second in 0..50 {
+            for mut quote in arbitrage_testing::triangle_quotes() {
+                if rare_last_leg & (quote.instrument == "BINANCE_BTC_USDT") & (second > 0) {
+                    continue;
+                }
+                quote.marketdata_timestamp = second as f64;
+                let market_bbo = quote.build();
+                arbitrage.update(&market_bbo);
+                arbitrage.execute(&market_bbo, 1.0, false);
+            }
+        }
+        return arbitrage.get_readiness_ratio();
+    }
+
+    #[test]
+    fn rarely_quoted_leg_reports_a_low_readiness_ratio() {
+        assert!(readiness_ratio_of(true) < 0.1);
+    }
+
+    #[test]
+    fn always_quoted_legs_report_a_full_readiness_ratio() {
+        assert!(readiness_ratio_of(false) > 0.9);
+    }
+
+    // profit of the triangle on quotes off the tick and step grids, under a rounding strategy
+    fn rounded_profit(rounding_strategy: RoundingStrategy) -> f64 {
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[0].ask_price = 0.04995;
+        quotes[1].bid_price = 2000.5;
+        quotes[2].ask_price = 37990.5;
+        for (quote, tick_size) in quotes.iter_mut().zip([0.0001, 1.0, 1.0]) {
+            quote.step_size = 0.001;
+            quote.tick_size = tick_size;
+        }
+        let mut config = ArbitrageTransactionConfig::default();
+        config.rounding_strategy = rounding_strategy;
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_transaction_config(&config);
+        return arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0).unwrap().get_profit();
+    }
+
+    #[test]
+    fn conservative_rounding_never_predicts_more_than_default() {
+        let conservative_profit = rounded_profit(RoundingStrategy::CONSERVATIVE);
+
+        assert!(conservative_profit < rounded_profit(RoundingStrategy::FLOOR));
+        assert!(conservative_profit <= rounded_profit(RoundingStrategy::NEAREST));
+        assert!(conservative_profit > 0.0);
+    }
+
+    // profit of the triangle with the first leg quoted earlier than the others, under a leg distance limit
+    fn profit_with_first_leg_age(age_secs: f64, max_leg_distance_ms: f64) -> Option<ArbitrageProfit> {
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        for quote in quotes.iter_mut() {
+            quote.marketdata_timestamp = 10.0;
+        }
+        quotes[0].marketdata_timestamp = 10.0 - age_secs;
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_max_leg_distance_ms(Some(max_leg_distance_ms));
+        return execute_scaled(&mut arbitrage, &quotes, 1.0);
+    }
+
+    #[test]
+    fn legs_spanning_beyond_the_limit_are_rejected() {
+        assert!(profit_with_first_leg_age(0.5, 100.0).is_none());
+    }
+
+    #[test]
+    fn legs_within_the_limit_are_accepted() {
+        let arbitrage_profit = profit_with_first_leg_age(0.05, 100.0).unwrap();
+
+        assert!((arbitrage_profit.get_leg_distance_ms() - 50.0).abs() < 1e-6);
+    }
+
+    // return square BTC -> ETH -> BNB -> USDT -> BTC
+    fn square() -> Vec<HashMap<String, String>> {
+        return vec![
+            arbitrage_testing::leg("BTC", "ETH", "BUY", "ETH", "BTC"),
+            arbitrage_testing::leg("ETH", "BNB", "BUY", "BNB", "ETH"),
+            arbitrage_testing::leg("BNB", "USDT", "SELL", "BNB", "USDT"),
+            arbitrage_testing::leg("USDT", "BTC", "BUY", "BTC", "USDT"),
+        ];
+    }
+
+    // return quotes of the square, 1 BTC buys 20 ETH, 200 BNB, sold for 40000 USDT, buying back about 1.0526 BTC before fees
+    fn square_quotes() -> Vec<QuoteFixture> {
+        return vec![
+            QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0),
+            QuoteFixture::new("BINANCE_BNB_ETH", 0.0999, 1000.0, 0.1, 1000.0),
+            QuoteFixture::new("BINANCE_BNB_USDT", 200.0, 1000.0, 200.1, 1000.0),
+            QuoteFixture::new("BINANCE_BTC_USDT", 37990.0, 1000.0, 38000.0, 1000.0),
+        ];
+    }
+
+    #[test]
+    fn four_leg_cycle_profit_is_last_qty_out_minus_first_qty_in() {
+        let mut arbitrage = Arbitrage::from_transaction_list(&square()).unwrap();
+
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage, &square_quotes(), 1.0).unwrap();
+
+        let transaction_result_list = arbitrage_profit.get_transaction_result_list();
+        assert_eq!(transaction_result_list.len(), 4);
+        assert_eq!(arbitrage_profit.get_qty_out(), transaction_result_list[3].get_qty_out());
+        assert_eq!(arbitrage_profit.get_profit(), transaction_result_list[3].get_qty_out() - transaction_result_list[0].get_qty_in());
+        assert!(arbitrage_profit.get_profit() > 0.0);
+    }
+
+    #[test]
+    fn four_leg_cycle_name_joins_every_leg() {
+        let arbitrage = Arbitrage::from_transaction_list(&square()).unwrap();
+
+        assert_eq!(arbitrage.get_name().split(':').count(), 4);
+    }
+
+    // triangle arbitrage rejecting mids beyond the deviation, its reference seeded by the triangle quotes
+    fn deviation_arbitrage(max_price_deviation: f64) -> Arbitrage {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        let mut config = ArbitrageTransactionConfig::default();
+        config.max_price_deviation = max_price_deviation;
+        arbitrage.set_transaction_config(&config);
+        assert!(arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).is_some());
+        return arbitrage;
+    }
+
+    #[test]
+    fn cycle_with_an_outlier_leg_price_is_rejected() {
+        let mut arbitrage = deviation_arbitrage(0.2);
+
+        // eth usdt quoted 50% above its reference mid
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1] = QuoteFixture::new("BINANCE_ETH_USDT", 3000.0, 1000.0, 3001.5, 1000.0);
+
+        assert!(arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0).is_none());
+    }
+
+    #[test]
+    fn cycle_within_the_price_deviation_is_kept() {
+        let mut arbitrage = deviation_arbitrage(0.2);
+
+        // eth usdt quoted 5% above its reference mid
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1] = QuoteFixture::new("BINANCE_ETH_USDT", 2100.0, 1000.0, 2101.05, 1000.0);
+
+        assert!(arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0).is_some());
+    }
+
+    #[test]
+    fn gross_net_and_pct_profit_of_the_triangle() {
+        // 1 BTC buys 20 ETH, sold for 40000 USDT, buying 40000 / 38000 BTC, 0.1% fee on each leg
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let gross_qty_out = 40000.0 / 38000.0;
+        let net_qty_out = gross_qty_out * 0.999 * 0.999 * 0.999;
+
+        assert!((arbitrage_profit.get_gross_profit() - (gross_qty_out - 1.0)).abs() < 1e-6);
+        assert!((arbitrage_profit.get_net_profit() - (net_qty_out - 1.0)).abs() < 1e-6);
+        assert!((arbitrage_profit.get_total_fee() - (gross_qty_out - net_qty_out)).abs() < 1e-6);
+        assert!((arbitrage_profit.get_profit_pct() - (net_qty_out - 1.0) * 100.0).abs() < 1e-4);
+        assert!((arbitrage_profit.get_net_profit() - arbitrage_profit.get_profit()).abs() < 1e-9);
+    }
+
+    // triangle profit with fees limited to a fraction of the gross profit, the fees take about 6% of it
+    fn profit_with_max_fee_ratio(max_fee_ratio: f64) -> Option<ArbitrageProfit> {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_max_fee_ratio(Some(max_fee_ratio));
+        return arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0);
+    }
+
+    #[test]
+    fn cycle_with_fees_above_the_allowed_ratio_is_rejected() {
+        assert!(profit_with_max_fee_ratio(0.05).is_none());
+    }
+
+    #[test]
+    fn cycle_with_fees_within_the_allowed_ratio_is_kept() {
+        assert!(profit_with_max_fee_ratio(0.1).is_some());
+    }
+
+    #[test]
+    fn rescale_below_the_step_size_returns_none() {
+        // the 0.25 ETH book rounds down to nothing on a 1 ETH step
+        let mut quotes = thin_quotes();
+        quotes[0].step_size = 1.0;
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+
+        assert!(execute_scaled(&mut arbitrage, &quotes, 1.0).is_none());
+    }
+
+    #[test]
+    fn rescaled_cycle_fits_every_book() {
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+
+        let arbitrage_profit = execute_scaled(&mut arbitrage, &thin_quotes(), 1.0).unwrap();
+
+        assert!(arbitrage_profit.get_transaction_result_list().iter().all(|t| (t.get_qty_to_execute() > 0.0) & (t.get_qty_to_execute() <= t.get_market_qty())));
+    }
+
+    #[test]
+    fn min_capital_is_set_by_the_limiting_leg() {
+        // selling at least 40 ETH on leg 2, about 20 ETH per BTC
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1].min_qty = 40.0;
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+        let eth_per_btc = arbitrage_profit.get_transaction_result_list()[1].get_qty_to_execute();
+
+        let min_capital = arbitrage_profit.get_min_capital().unwrap();
+
+        assert!((min_capital - 40.0 / eth_per_btc).abs() < 1e-9);
+        assert!(min_capital > 2.0);
+    }
+
+    #[test]
+    fn min_notional_of_a_leg_raises_the_min_capital() {
+        // buying BTC for at least 114000 USDT on leg 3, about 3 BTC
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[2].min_notional = 114000.0;
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+        let btc_per_btc = arbitrage_profit.get_transaction_result_list()[2].get_qty_to_execute();
+
+        let min_capital = arbitrage_profit.get_min_capital().unwrap();
+
+        let step_size = arbitrage_profit.get_transaction_result_list()[2].get_step_size();
+        assert!((min_capital - (3.0 + step_size) / btc_per_btc).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tick_on_the_binding_leg_is_evaluated_while_others_are_deferred() {
+        // the 0.25 ETH ask of the first leg limits the cycle
+        let quotes = thin_quotes();
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage.set_binding_leg_deferral(Some(1));
+        arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0);
+        assert_eq!(arbitrage.get_binding_leg(), Some(&"BINANCE_ETH_BTC".to_string()));
+
+        assert!(arbitrage.should_evaluate(&quotes[0].build()));
+        assert!(!arbitrage.should_evaluate(&quotes[1].build()));
+        // a single tick is deferred, the next one is evaluated
+        assert!(arbitrage.should_evaluate(&quotes[2].build()));
+    }
+
+    #[test]
+    fn every_tick_is_evaluated_without_a_deferral() {
+        let quotes = thin_quotes();
+        let mut arbitrage = arbitrage_testing::triangle_arbitrage();
+        arbitrage_testing::profit_of(&mut arbitrage, &quotes, 1.0);
+
+        assert!(quotes.iter().all(|quote| arbitrage.should_evaluate(&quote.build())));
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+// Clock
+pub trait Clock: Send + Sync {
+    // return current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+// System Clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        return Utc::now();
+    }
+}
+
+// Mock Clock
+pub struct MockClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl MockClock {
+    // create new instance
+    pub fn new(now: DateTime<Utc>) -> MockClock {
+        MockClock {
+            now: Mutex::new(now),
+        }
+    }
+
+    // set current time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    // advance current time
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        return *self.now.lock().unwrap();
+    }
+}
@@ -1,10 +1,69 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use postgres::{Connection, TlsMode};
 
 use simplelog::*;
 use log::{info, trace, warn};
 use crate::izyfo_arbitrage::arbitrage::ArbitrageProfit;
+use uuid::Uuid;
+use crate::izyfo_arbitrage::arbitrage_sink::{BalanceSnapshot, CycleState, ExecutionRecord, LatencyRecord, ProfitSink, RunRecord};
+
 
+// tables created on connect, by name
+const SCHEMA: [(&str, &str); 6] = [
+    ("profit", "create table if not exists triangle_arbitrage_binance (
+        id serial primary key,
+        name varchar(255),
+        uuid varchar(36),
+        date timestamp(3) with time zone,
+        profit DOUBLE PRECISION,
+        latency_ms bigint)"),
+    ("executions", "create table if not exists triangle_arbitrage_binance_executions (
+        id serial primary key,
+        name varchar(255),
+        uuid varchar(36),
+        symbol varchar(32),
+        operation varchar(8),
+        order_id bigint,
+        client_order_id varchar(64),
+        transact_time bigint,
+        price DOUBLE PRECISION,
+        qty DOUBLE PRECISION,
+        fill_price DOUBLE PRECISION,
+        raw_response text,
+        run_id varchar(36),
+        date timestamp(3) with time zone)"),
+    ("runs", "create table if not exists triangle_arbitrage_binance_runs (
+        run_id varchar(36) primary key,
+        exchange varchar(32),
+        start_assets varchar(255),
+        symbols_count integer,
+        leg_count integer,
+        qty_in DOUBLE PRECISION,
+        profit_threshold DOUBLE PRECISION,
+        ordering boolean,
+        mode varchar(16),
+        date timestamp(3) with time zone)"),
+    ("cycles", "create table if not exists triangle_arbitrage_binance_cycles (
+        name varchar(255) primary key,
+        suspended boolean,
+        date timestamp(3) with time zone)"),
+    ("balances", "create table if not exists triangle_arbitrage_binance_balances (
+        id serial primary key,
+        balances text,
+        date timestamp(3) with time zone)"),
+    ("latency", "create table if not exists triangle_arbitrage_binance_latency (
+        id serial primary key,
+        bucket_start timestamp(3) with time zone,
+        bucket_secs bigint,
+        count bigint,
+        p50_ms DOUBLE PRECISION,
+        p90_ms DOUBLE PRECISION,
+        p99_ms DOUBLE PRECISION)"),
+];
 
+#[derive(Clone)]
 pub struct ArbitrageDatabase{
     address: String
 }
@@ -16,26 +75,365 @@ impl ArbitrageDatabase{
         }
     }
 
-    pub fn connect(&self){
-        let mut connection = match Connection::connect(self.address.clone(), TlsMode::None){
+    // connect and create tables, failing if the schema can't be created
+    pub fn connect(&self) -> Result<(), String>{
+        let conn = Connection::connect(self.address.clone(), TlsMode::None)
+            .map_err(|err| format!("failed connection error={}", err))?;
+        info!("arbitrage_database connected=True");
+
+        let trans = conn.transaction().map_err(|err| format!("failed transaction error={}", err))?;
+        for (table, ddl) in SCHEMA.iter() {
+            trans.execute(ddl, &[]).map_err(|err| format!("failed to create {} table error={}", table, err))?;
+        }
+        trans.commit().map_err(|err| format!("failed commit error={}", err))?;
+        Ok(())
+    }
+
+    pub fn add_profit(&self, arbitrage_profit: &ArbitrageProfit){
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                let profit = arbitrage_profit.get_profit();
+                let uuid = arbitrage_profit.get_uuid().to_string();
+                let latency_ms = arbitrage_profit.get_latency_ms();
+                match conn.execute("insert into triangle_arbitrage_binance (name, uuid, date, profit, latency_ms) values ($1, $2, $3, $4, $5)",
+                                   &[arbitrage_profit.get_name(), &uuid, &arbitrage_profit.get_create_at(), &profit, &latency_ms]){
+                    Ok(result) => trace!("{}", format!("profit added rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to add profit error={}", err))
+                }
+            },
+            Err(err) => warn!("{}", format!("failed connection error={}", err))
+        };
+    }
+
+    pub fn add_execution(&self, execution: &ExecutionRecord){
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                let uuid = execution.uuid.to_string();
+                let order_id = execution.order_id as i64;
+                let transact_time = execution.transact_time as i64;
+                let run_id = execution.run_id.map(|run_id| run_id.to_string());
+                match conn.execute("insert into triangle_arbitrage_binance_executions (name, uuid, symbol, operation, order_id, client_order_id, transact_time, price, qty, fill_price, raw_response, run_id, date) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+                                   &[&execution.name, &uuid, &execution.symbol, &execution.operation, &order_id, &execution.client_order_id, &transact_time, &execution.price, &execution.qty, &execution.fill_price, &execution.raw_response, &run_id, &execution.create_at]){
+                    Ok(result) => trace!("{}", format!("execution added rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to add execution error={}", err))
+                }
+            },
+            Err(err) => warn!("{}", format!("failed connection error={}", err))
+        };
+    }
+
+    pub fn add_run(&self, run: &RunRecord){
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                let run_id = run.run_id.to_string();
+                let start_assets = run.start_assets.join(",");
+                let symbols_count = run.symbols_count as i32;
+                let leg_count = run.leg_count as i32;
+                match conn.execute("insert into triangle_arbitrage_binance_runs (run_id, exchange, start_assets, symbols_count, leg_count, qty_in, profit_threshold, ordering, mode, date) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                                   &[&run_id, &run.exchange, &start_assets, &symbols_count, &leg_count, &run.qty_in, &run.profit_threshold, &run.ordering, &run.mode, &run.create_at]){
+                    Ok(result) => trace!("{}", format!("run added rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to add run error={}", err))
+                }
+            },
+            Err(err) => warn!("{}", format!("failed connection error={}", err))
+        };
+    }
+
+    pub fn set_cycle_state(&self, cycle_state: &CycleState){
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                match conn.execute("insert into triangle_arbitrage_binance_cycles (name, suspended, date) values ($1, $2, $3)
+                                    on conflict (name) do update set suspended = excluded.suspended, date = excluded.date",
+                                   &[&cycle_state.name, &cycle_state.suspended, &cycle_state.create_at]){
+                    Ok(result) => trace!("{}", format!("cycle state set rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to set cycle state error={}", err))
+                }
+            },
+            Err(err) => warn!("{}", format!("failed connection error={}", err))
+        };
+    }
+
+    // return names of suspended cycles
+    pub fn get_suspended_cycles(&self) -> Vec<String>{
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                match conn.query("select name from triangle_arbitrage_binance_cycles where suspended = true", &[]){
+                    Ok(rows) => rows.iter().map(|row| row.get(0)).collect(),
+                    Err(err) => {
+                        warn!("{}", format!("failed to query cycle states error={}", err));
+                        Vec::new()
+                    }
+                }
+            },
+            Err(err) => {
+                warn!("{}", format!("failed connection error={}", err));
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot){
+        let balances = match serde_json::to_string(&balance_snapshot.balances){
+            Ok(balances) => balances,
+            Err(err) => {
+                warn!("{}", format!("failed to serialize balances error={}", err));
+                return;
+            }
+        };
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                match conn.execute("insert into triangle_arbitrage_binance_balances (balances, date) values ($1, $2)",
+                                   &[&balances, &balance_snapshot.create_at]){
+                    Ok(result) => trace!("{}", format!("balance snapshot added rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to add balance snapshot error={}", err))
+                }
+            },
+            Err(err) => warn!("{}", format!("failed connection error={}", err))
+        };
+    }
+
+    // return last balance snapshot
+    pub fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot>{
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                match conn.query("select balances, date from triangle_arbitrage_binance_balances order by id desc limit 1", &[]){
+                    Ok(rows) => {
+                        let row = rows.iter().next()?;
+                        let balances: String = row.get(0);
+                        match serde_json::from_str::<HashMap<String, f64>>(&balances){
+                            Ok(balances) => Some(BalanceSnapshot { balances: balances, create_at: row.get(1) }),
+                            Err(err) => {
+                                warn!("{}", format!("failed to parse balance snapshot error={}", err));
+                                None
+                            }
+                        }
+                    },
+                    Err(err) => {
+                        warn!("{}", format!("failed to query balance snapshot error={}", err));
+                        None
+                    }
+                }
+            },
+            Err(err) => {
+                warn!("{}", format!("failed connection error={}", err));
+                None
+            }
+        }
+    }
+
+    // return executions recorded since the date
+    pub fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord>{
+        match Connection::connect(self.address.clone(), TlsMode::None){
             Ok(conn) => {
-                info!("arbitrage_database connected=True");
-                let trans = conn.transaction().unwrap();
-                match trans.execute("create table if not exists triangle_arbitrage_binance (
-                    id serial primary key,
-                    name varchar(255),
-                    date timestamp(3) with time zone,
-                    profit DOUBLE PRECISION", &[]){
-                        Ok(result) => println!("{}", result),
-                        Err(err) => trace!("{}", format!("{}", err))
+                match conn.query("select name, uuid, symbol, operation, order_id, client_order_id, transact_time, price, qty, fill_price, raw_response, run_id, date
+                                  from triangle_arbitrage_binance_executions where date >= $1 order by id", &[&since]){
+                    Ok(rows) => rows.iter().map(|row| {
+                        let uuid: String = row.get(1);
+                        let order_id: i64 = row.get(4);
+                        let transact_time: i64 = row.get(6);
+                        let run_id: Option<String> = row.get(11);
+                        ExecutionRecord {
+                            name: row.get(0),
+                            uuid: Uuid::parse_str(&uuid).unwrap_or(Uuid::nil()),
+                            symbol: row.get(2),
+                            operation: row.get(3),
+                            order_id: order_id as u64,
+                            client_order_id: row.get(5),
+                            transact_time: transact_time as u64,
+                            price: row.get(7),
+                            qty: row.get(8),
+                            fill_price: row.get(9),
+                            raw_response: row.get(10),
+                            run_id: run_id.and_then(|run_id| Uuid::parse_str(&run_id).ok()),
+                            create_at: row.get(12),
+                        }
+                    }).collect(),
+                    Err(err) => {
+                        warn!("{}", format!("failed to query executions error={}", err));
+                        Vec::new()
                     }
-                trans.commit().unwrap();
+                }
+            },
+            Err(err) => {
+                warn!("{}", format!("failed connection error={}", err));
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn add_latency(&self, latency: &LatencyRecord){
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                let count = latency.count as i64;
+                match conn.execute("insert into triangle_arbitrage_binance_latency (bucket_start, bucket_secs, count, p50_ms, p90_ms, p99_ms) values ($1, $2, $3, $4, $5, $6)",
+                                   &[&latency.bucket_start, &latency.bucket_secs, &count, &latency.p50_ms, &latency.p90_ms, &latency.p99_ms]){
+                    Ok(result) => trace!("{}", format!("latency added rows={}", result)),
+                    Err(err) => warn!("{}", format!("failed to add latency error={}", err))
+                }
             },
             Err(err) => warn!("{}", format!("failed connection error={}", err))
         };
     }
 
-    pub fn add_profit(&self, arbitrage_profit: &ArbitrageProfit){
+    // return cycle quality, mean realized edge of its executions over its standard deviation
+    pub fn get_cycle_quality(&self, name: &String) -> Option<f64>{
+        match Connection::connect(self.address.clone(), TlsMode::None){
+            Ok(conn) => {
+                match conn.query("select uuid, operation, fill_price from triangle_arbitrage_binance_executions where name = $1 order by id", &[name]){
+                    Ok(rows) => {
+                        let legs: Vec<(String, String, Option<f64>)> = rows.iter().map(|row| (row.get(0), row.get(1), row.get(2))).collect();
+                        quality_score(&realized_edges(&legs))
+                    },
+                    Err(err) => {
+                        warn!("{}", format!("failed to query cycle quality error={}", err));
+                        None
+                    }
+                }
+            },
+            Err(err) => {
+                warn!("{}", format!("failed connection error={}", err));
+                None
+            }
+        }
+    }
+}
+
+impl ProfitSink for ArbitrageDatabase{
+    fn add_profit(&self, arbitrage_profit: &ArbitrageProfit){
+        ArbitrageDatabase::add_profit(self, arbitrage_profit);
+    }
+
+    fn add_execution(&self, execution: &ExecutionRecord){
+        ArbitrageDatabase::add_execution(self, execution);
+    }
+
+    fn add_run(&self, run: &RunRecord){
+        ArbitrageDatabase::add_run(self, run);
+    }
+
+    fn set_cycle_state(&self, cycle_state: &CycleState){
+        ArbitrageDatabase::set_cycle_state(self, cycle_state);
+    }
+
+    fn get_suspended_cycles(&self) -> Vec<String>{
+        ArbitrageDatabase::get_suspended_cycles(self)
+    }
+
+    fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot){
+        ArbitrageDatabase::add_balance_snapshot(self, balance_snapshot);
+    }
+
+    fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot>{
+        ArbitrageDatabase::get_last_balance_snapshot(self)
+    }
+
+    fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord>{
+        ArbitrageDatabase::get_executions_since(self, since)
+    }
+
+    fn add_latency(&self, latency: &LatencyRecord){
+        ArbitrageDatabase::add_latency(self, latency);
+    }
+}
+
+// return realized edge of each cycle execution from its legs (uuid, operation, fill price), in execution order
+// fill prices carry the slippage from the detected prices, executions with an unfilled leg are skipped
+pub fn realized_edges(legs: &Vec<(String, String, Option<f64>)>) -> Vec<f64>{
+    let mut uuid_list: Vec<&String> = Vec::new();
+    let mut rates: HashMap<&String, Option<f64>> = HashMap::new();
+    for (uuid, operation, fill_price) in legs {
+        if !rates.contains_key(uuid) {
+            uuid_list.push(uuid);
+        }
+        let rate = rates.entry(uuid).or_insert(Some(1.0));
+        *rate = match (*rate, fill_price) {
+            (Some(r), Some(p)) if *p > 0.0 => if operation == "BUY" { Some(r / p) } else { Some(r * p) },
+            _ => None
+        };
+    }
+    return uuid_list.iter().filter_map(|uuid| rates[uuid]).map(|rate| rate - 1.0).collect();
+}
+
+// return sharpe-like quality, none if fewer than two samples or no variance
+pub fn quality_score(edge_list: &Vec<f64>) -> Option<f64>{
+    if edge_list.len() < 2 {
+        return None;
+    }
+
+    let count = edge_list.len() as f64;
+    let mean = edge_list.iter().sum::<f64>() / count;
+    let variance = edge_list.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    let std_dev = variance.sqrt();
+
+    if std_dev == 0.0 {
+        return None;
+    }
+    return Some(mean / std_dev);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // legs of a triangle execution filled at the prices
+    fn triangle_legs(uuid: &str, prices: [f64; 3]) -> Vec<(String, String, Option<f64>)> {
+        return vec![
+            (uuid.to_string(), "BUY".to_string(), Some(prices[0])),
+            (uuid.to_string(), "SELL".to_string(), Some(prices[1])),
+            (uuid.to_string(), "BUY".to_string(), Some(prices[2])),
+        ];
+    }
+
+    #[test]
+    fn equal_mean_with_lower_variance_scores_higher() {
+        let steady = quality_score(&vec![0.01, 0.01, 0.012, 0.008]).unwrap();
+        let erratic = quality_score(&vec![0.03, -0.01, 0.04, -0.02]).unwrap();
+
+        assert!(steady > erratic);
+        assert!(quality_score(&vec![0.01]).is_none());
+        assert!(quality_score(&vec![0.01, 0.01]).is_none());
+    }
+
+    #[test]
+    fn realized_edge_uses_fill_prices() {
+        let mut legs = triangle_legs("a", [0.05, 2000.0, 38000.0]);
+        legs.extend(triangle_legs("b", [0.05, 1995.0, 38000.0]));
 
+        let edges = realized_edges(&legs);
+
+        assert_eq!(edges.len(), 2);
+        assert!((edges[0] - (2000.0 / 0.05 / 38000.0 - 1.0)).abs() < 1e-12);
+        assert!(edges[1] < edges[0]);
+    }
+
+    #[test]
+    fn realized_edge_skips_unfilled_executions() {
+        let mut legs = triangle_legs("a", [0.05, 2000.0, 38000.0]);
+        legs.push(("b".to_string(), "BUY".to_string(), Some(0.05)));
+        legs.push(("b".to_string(), "SELL".to_string(), None));
+
+        assert_eq!(realized_edges(&legs).len(), 1);
+    }
+
+    #[test]
+    fn schema_statements_are_closed() {
+        for (table, ddl) in SCHEMA.iter() {
+            assert_eq!(ddl.matches('(').count(), ddl.matches(')').count(), "unbalanced {} table", table);
+            assert!(ddl.trim_end().ends_with(')'), "unclosed {} table", table);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn profit_table_stores_uuid_and_latency() {
+        let (_, ddl) = SCHEMA.iter().find(|(table, _)| *table == "profit").unwrap();
+
+        assert!(ddl.contains("uuid varchar(36)"));
+        assert!(ddl.contains("latency_ms bigint"));
+    }
+
+    #[test]
+    fn unreachable_database_fails_to_connect() {
+        let arbitrage_database = ArbitrageDatabase::new("postgres://izyfo@127.0.0.1:1/izyfo".to_string());
+
+        assert!(arbitrage_database.connect().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use binance::account::*;
+use binance::api::*;
+use binance::model::{AccountInformation, Balance, Order, OrderCanceled, Transaction};
+
+use log::warn;
+
+use crate::izyfo_arbitrage::arbitrage_ordering::OrderingError;
+
+// binance request weights
+pub const ORDER_WEIGHT: u32 = 1;
+pub const ORDER_STATUS_WEIGHT: u32 = 4;
+pub const CANCEL_ORDER_WEIGHT: u32 = 1;
+pub const ACCOUNT_WEIGHT: u32 = 20;
+
+// exchange used by the ordering, orders and account calls only
+pub trait Exchange: Send + Sync {
+    // exchange name, as used by the referencedata
+    fn get_name(&self) -> &str;
+
+    // instrument and asset id prefix, e.g. "BINANCE_"
+    fn get_prefix(&self) -> String {
+        return format!("{}_", self.get_name());
+    }
+
+    // exchange symbol of a pair, e.g. "ADAUSDT"
+    fn get_symbol(&self, base: &str, quote: &str) -> String {
+        return format!("{}{}", base, quote);
+    }
+
+    // minimum order increment of an instrument, none to use the referencedata step size
+    fn get_qty_increment(&self, _instrument_id: &str) -> Option<f64> {
+        return None;
+    }
+
+    fn limit_buy_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn limit_sell_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn limit_buy_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn limit_sell_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn limit_buy(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn limit_sell(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError>;
+    fn market_buy(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError>;
+    fn market_sell(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError>;
+    fn order_status(&self, symbol: String, order_id: u64) -> Result<Order, OrderingError>;
+    fn cancel_order(&self, symbol: String, order_id: u64) -> Result<OrderCanceled, OrderingError>;
+    fn get_account(&self) -> Result<AccountInformation, OrderingError>;
+    fn get_balance(&self, asset: String) -> Result<Balance, OrderingError>;
+}
+
+// strip the exchange prefix from an instrument or asset id
+pub fn strip_prefix(id: &str, prefix: &str) -> String {
+    return id.replace(prefix, "");
+}
+
+pub struct BinanceExchange {
+    account: Account,
+}
+
+impl BinanceExchange {
+    // new binance exchange
+    pub fn new(api_key: String, secret_key: String) -> BinanceExchange {
+        let account: Account = Binance::new(Some(api_key), Some(secret_key));
+        return BinanceExchange {
+            account: account
+        };
+    }
+}
+
+impl Exchange for BinanceExchange {
+    fn get_name(&self) -> &str {
+        return "BINANCE";
+    }
+
+    fn limit_buy_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_buy_fok(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn limit_sell_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_sell_fok(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn limit_buy_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_buy_ioc(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn limit_sell_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_sell_ioc(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn limit_buy(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_buy(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn limit_sell(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.account.limit_sell(symbol, qty, price).map_err(OrderingError::from);
+    }
+
+    fn market_buy(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        return self.account.market_buy(symbol, qty).map_err(OrderingError::from);
+    }
+
+    fn market_sell(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        return self.account.market_sell(symbol, qty).map_err(OrderingError::from);
+    }
+
+    fn order_status(&self, symbol: String, order_id: u64) -> Result<Order, OrderingError> {
+        return self.account.order_status(symbol, order_id).map_err(OrderingError::from);
+    }
+
+    fn cancel_order(&self, symbol: String, order_id: u64) -> Result<OrderCanceled, OrderingError> {
+        return self.account.cancel_order(symbol, order_id).map_err(OrderingError::from);
+    }
+
+    fn get_account(&self) -> Result<AccountInformation, OrderingError> {
+        return self.account.get_account().map_err(OrderingError::from);
+    }
+
+    fn get_balance(&self, asset: String) -> Result<Balance, OrderingError> {
+        return self.account.get_balance(asset).map_err(OrderingError::from);
+    }
+}
+
+// Rate Limiter, token bucket of request weight refilled continuously over the period
+pub struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+    max_wait: Duration,
+}
+
+impl RateLimiter {
+    // new rate limiter allowing capacity weight per period, a call waits up to max wait for its weight
+    pub fn new(capacity: u32, period: Duration, max_wait: Duration) -> RateLimiter {
+        return RateLimiter {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec: capacity as f64 / period.as_secs_f64().max(0.001),
+            last_refill: Instant::now(),
+            max_wait: max_wait,
+        };
+    }
+
+    // take weight if available, else return the wait until it is
+    fn try_acquire(&mut self, weight: u32) -> Result<(), Duration> {
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.last_refill).as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        let weight = weight as f64;
+        if self.tokens >= weight {
+            self.tokens -= weight;
+            return Ok(());
+        }
+        return Err(Duration::from_secs_f64((weight - self.tokens) / self.refill_per_sec));
+    }
+}
+
+// block until the weight is available, rate limited past the limiter max wait, the lock is not held while waiting
+pub fn acquire(rate_limiter: &Mutex<RateLimiter>, weight: u32) -> Result<(), OrderingError> {
+    let start_date = Instant::now();
+    loop {
+        let (result, max_wait) = {
+            let mut limiter = rate_limiter.lock().unwrap();
+            (limiter.try_acquire(weight), limiter.max_wait)
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(wait) => {
+                if start_date.elapsed() + wait > max_wait {
+                    warn!("arbitrage_exchange - request weight exhausted. weight: {}, wait: {:?}", weight, wait);
+                    return Err(OrderingError::RateLimited);
+                }
+                thread::sleep(wait);
+            }
+        }
+    }
+}
+
+// Rate Limited Exchange, acquires the request weight of every call from a shared limiter
+pub struct RateLimitedExchange {
+    exchange: Arc<dyn Exchange>,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
+impl RateLimitedExchange {
+    // new rate limited exchange
+    pub fn new(exchange: Arc<dyn Exchange>, rate_limiter: Arc<Mutex<RateLimiter>>) -> RateLimitedExchange {
+        return RateLimitedExchange {
+            exchange: exchange,
+            rate_limiter: rate_limiter,
+        };
+    }
+}
+
+impl Exchange for RateLimitedExchange {
+    fn get_name(&self) -> &str {
+        return self.exchange.get_name();
+    }
+
+    fn get_prefix(&self) -> String {
+        return self.exchange.get_prefix();
+    }
+
+    fn get_symbol(&self, base: &str, quote: &str) -> String {
+        return self.exchange.get_symbol(base, quote);
+    }
+
+    fn get_qty_increment(&self, instrument_id: &str) -> Option<f64> {
+        return self.exchange.get_qty_increment(instrument_id);
+    }
+
+    fn limit_buy_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_buy_fok(symbol, qty, price);
+    }
+
+    fn limit_sell_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_sell_fok(symbol, qty, price);
+    }
+
+    fn limit_buy_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_buy_ioc(symbol, qty, price);
+    }
+
+    fn limit_sell_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_sell_ioc(symbol, qty, price);
+    }
+
+    fn limit_buy(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_buy(symbol, qty, price);
+    }
+
+    fn limit_sell(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.limit_sell(symbol, qty, price);
+    }
+
+    fn market_buy(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.market_buy(symbol, qty);
+    }
+
+    fn market_sell(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_WEIGHT)?;
+        return self.exchange.market_sell(symbol, qty);
+    }
+
+    fn order_status(&self, symbol: String, order_id: u64) -> Result<Order, OrderingError> {
+        acquire(&self.rate_limiter, ORDER_STATUS_WEIGHT)?;
+        return self.exchange.order_status(symbol, order_id);
+    }
+
+    fn cancel_order(&self, symbol: String, order_id: u64) -> Result<OrderCanceled, OrderingError> {
+        acquire(&self.rate_limiter, CANCEL_ORDER_WEIGHT)?;
+        return self.exchange.cancel_order(symbol, order_id);
+    }
+
+    fn get_account(&self) -> Result<AccountInformation, OrderingError> {
+        acquire(&self.rate_limiter, ACCOUNT_WEIGHT)?;
+        return self.exchange.get_account();
+    }
+
+    // balance is read from the account endpoint
+    fn get_balance(&self, asset: String) -> Result<Balance, OrderingError> {
+        acquire(&self.rate_limiter, ACCOUNT_WEIGHT)?;
+        return self.exchange.get_balance(asset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::izyfo_arbitrage::arbitrage_testing::{self, MockExchange};
+
+    #[test]
+    fn exhausted_limiter_returns_rate_limited() {
+        let rate_limiter = Mutex::new(RateLimiter::new(ACCOUNT_WEIGHT, Duration::from_secs(60), Duration::from_secs(0)));
+
+        assert!(acquire(&rate_limiter, ACCOUNT_WEIGHT).is_ok());
+        assert!(matches!(acquire(&rate_limiter, ORDER_WEIGHT), Err(OrderingError::RateLimited)));
+    }
+
+    #[test]
+    fn limiter_blocks_until_the_weight_is_refilled() {
+        // 10 weight per 100ms, 5 weight refills in 50ms
+        let rate_limiter = Mutex::new(RateLimiter::new(10, Duration::from_millis(100), Duration::from_secs(1)));
+        assert!(acquire(&rate_limiter, 10).is_ok());
+
+        let start_date = Instant::now();
+        assert!(acquire(&rate_limiter, 5).is_ok());
+        assert!(start_date.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn rate_limited_call_does_not_reach_the_exchange() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, "FILLED", 1.0, 1.0, 0.05)));
+        let rate_limiter = Arc::new(Mutex::new(RateLimiter::new(ORDER_WEIGHT, Duration::from_secs(60), Duration::from_secs(0))));
+        let rate_limited_exchange = RateLimitedExchange::new(Arc::clone(&exchange) as Arc<dyn Exchange>, rate_limiter);
+
+        assert!(rate_limited_exchange.market_sell("ETHBTC".to_string(), 1.0).is_ok());
+        assert!(matches!(rate_limited_exchange.market_sell("ETHBTC".to_string(), 1.0), Err(OrderingError::RateLimited)));
+        assert_eq!(exchange.get_calls_of("market_sell").len(), 1);
+    }
+}
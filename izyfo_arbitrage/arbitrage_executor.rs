@@ -1,8 +1,15 @@
 use std::{thread, time};
-use std::collections::HashMap;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize};
+use std::thread::JoinHandle;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::time::{Duration, Instant};
 use std::env;
+use std::fs;
 use binance::account::*;
 use binance::api::*;
 use binance::errors::Error;
@@ -12,14 +19,24 @@ use binance::model::Transaction;
 use bus::Bus;
 use crossbeam_channel;
 use itertools::Itertools;
+use rayon::prelude::*;
+use chrono::{DateTime, TimeZone, Utc};
 use log::{debug, error, info, trace, warn};
+use opentelemetry::{Context, KeyValue};
 use serde::{Deserialize, Serialize};
+use signal_hook::consts::SIGHUP;
+use signal_hook::iterator::{Handle, Signals};
 use simplelog::*;
+use uuid::Uuid;
 
-use crate::izyfo_arbitrage::arbitrage::{Arbitrage, ArbitrageProfit};
+use crate::izyfo_arbitrage::arbitrage::{Arbitrage, ArbitrageProfit, ValidationWarning};
+use crate::izyfo_arbitrage::arbitrage_clock::{Clock, SystemClock};
 use crate::izyfo_arbitrage::arbitrage_database::ArbitrageDatabase;
-use crate::izyfo_arbitrage::arbitrage_ordering::ArbitrageOrdering;
-use crate::izyfo_arbitrage::arbitrage_transaction::ArbitrageTransactionResult;
+use crate::izyfo_arbitrage::arbitrage_ordering::{ArbitrageOrdering, SHUTDOWN_POLL_INTERVAL};
+use crate::izyfo_arbitrage::arbitrage_stats::StatsServer;
+use crate::izyfo_arbitrage::arbitrage_sink::{CycleState, LatencyRecord, ProfitSink, ProfitSinkConfig, RunRecord};
+use crate::izyfo_arbitrage::arbitrage_tracing;
+use crate::izyfo_arbitrage::arbitrage_transaction::{to_f64, ArbitrageTransactionConfig, ArbitrageTransactionResult, OrderType, PriceSource, TimeInForce};
 use crate::izyfo_connectors;
 use crate::izyfo_events::exchange::market_bbo::MarketBBO;
 
@@ -29,17 +46,244 @@ pub enum ExecutionMode {
     SEQUENTIAL,
 }
 
+// reserve for adverse fills, deducted from the predicted profit before ordering
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AdverseFillReserve {
+    ABSOLUTE(f64),
+    BPS(f64),
+}
+
+impl AdverseFillReserve {
+    // return reserve in start asset units
+    pub fn get_reserve(&self, qty_in: f64) -> f64 {
+        match self {
+            AdverseFillReserve::ABSOLUTE(reserve) => *reserve,
+            AdverseFillReserve::BPS(bps) => qty_in * bps / 10000.0,
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArbitrageLiveConfig {
+    pub blacklist: Vec<String>,
+    pub profit_threshold: f64,
+    pub ordering: bool,
+}
+
+impl ArbitrageLiveConfig {
+    // parse key=value config file, keys missing from the file keep their current value
+    pub fn from_file(path: &str, current: &ArbitrageLiveConfig) -> Result<ArbitrageLiveConfig, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("failed to read config '{}': {}", path, e))?;
+        let mut config = current.clone();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() | line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("invalid config line: '{}'", line))
+            };
+
+            match key {
+                "blacklist" => {
+                    config.blacklist = value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+                }
+                "profit_threshold" => {
+                    config.profit_threshold = value.parse::<f64>().map_err(|e| format!("invalid profit_threshold '{}': {}", value, e))?;
+                }
+                "ordering" => {
+                    config.ordering = value.parse::<bool>().map_err(|e| format!("invalid ordering '{}': {}", value, e))?;
+                }
+                _ => warn!("arbitrage_executor - unknown config key. key:{}", key)
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ShadowTotals {
+    pub opportunities: u64,
+    pub profit: f64,
+    pub sent: u64,
+    pub sent_profit: f64,
+    pub skipped: u64,
+    pub skipped_profit: f64,
+}
+
+impl ShadowTotals {
+    // record an opportunity
+    pub fn record(&mut self, profit: f64, sent: bool) {
+        self.opportunities += 1;
+        self.profit += profit;
+        if sent {
+            self.sent += 1;
+            self.sent_profit += profit;
+        } else {
+            self.skipped += 1;
+            self.skipped_profit += profit;
+        }
+    }
+}
+
+// moving average weight of the latest evaluation latency
+const EVALUATION_LATENCY_ALPHA: f64 = 0.1;
+
+// replay gives up waiting for the cycle threads after this long without progress
+const REPLAY_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// heartbeat thread checks the clock this often, so a mocked clock drives the interval
+const HEARTBEAT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Referencedata Cache, last fetched instrument list, persisted so a restart during an outage still builds cycles
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReferencedataCache {
+    pub instrument_list: Vec<String>,
+    pub cached_at: DateTime<Utc>,
+}
+
+// Heartbeat, emitted at a fixed interval even when no opportunity fires
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Heartbeat {
+    pub uptime_secs: i64,
+    pub ticks: u64,
+    pub opportunities: u64,
+    pub create_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EvaluationStats {
+    pub evaluations: u64,
+    pub latency_us: f64,
+    pub max_latency_us: f64,
+}
+
+impl EvaluationStats {
+    // record an evaluation latency into the moving average
+    pub fn record(&mut self, latency: Duration) {
+        let latency_us = latency.as_secs_f64() * 1_000_000.0;
+        if self.evaluations == 0 {
+            self.latency_us = latency_us;
+        } else {
+            self.latency_us += EVALUATION_LATENCY_ALPHA * (latency_us - self.latency_us);
+        }
+        if latency_us > self.max_latency_us {
+            self.max_latency_us = latency_us;
+        }
+        self.evaluations += 1;
+    }
+}
+
+// Latency Bucket, detection latencies of the profits of the current time bucket
+struct LatencyBucket {
+    bucket_secs: i64,
+    bucket_start: i64,
+    latencies_ms: Vec<i64>,
+}
+
+impl LatencyBucket {
+    fn new(bucket_secs: i64) -> LatencyBucket {
+        return LatencyBucket {
+            bucket_secs: bucket_secs,
+            bucket_start: 0,
+            latencies_ms: Vec::new(),
+        };
+    }
+
+    // record a profit latency, returning the percentiles of the previous bucket once the next one starts
+    fn record(&mut self, arbitrage_profit: &ArbitrageProfit) -> Option<LatencyRecord> {
+        let profit_bucket_start = arbitrage_profit.get_create_at().timestamp() / self.bucket_secs * self.bucket_secs;
+        let mut latency = None;
+        if profit_bucket_start != self.bucket_start {
+            latency = self.flush();
+        }
+        self.bucket_start = profit_bucket_start;
+        self.latencies_ms.push(arbitrage_profit.get_latency_ms());
+        return latency;
+    }
+
+    // return percentiles of the current bucket, emptying it
+    fn flush(&mut self) -> Option<LatencyRecord> {
+        let latency = LatencyRecord::new(Utc.timestamp(self.bucket_start, 0), self.bucket_secs, &self.latencies_ms);
+        self.latencies_ms.clear();
+        return latency;
+    }
+}
+
+// persist latency percentiles of a bucket
+fn add_latency(profit_sink: &Arc<dyn ProfitSink>, latency: Option<LatencyRecord>) {
+    if let Some(latency) = latency {
+        debug!("arbitrage_executor - latency percentiles. bucket_start:{}, count:{}, p50:{}, p90:{}, p99:{}", latency.bucket_start, latency.count, latency.p50_ms, latency.p90_ms, latency.p99_ms);
+        profit_sink.add_latency(&latency);
+    }
+}
 
 pub struct ArbitrageExecutor {
-    profit_thresold: f32,
-    qty_in: f32,
+    profit_thresold: f64,
+    qty_in: f64,
     exchange: String,
-    start_asset: String,
-    arbitrage_database: ArbitrageDatabase,
+    start_assets: Vec<String>,
+    profit_sink: Arc<dyn ProfitSink>,
     transactions_list: Vec<Vec<HashMap<String, String>>>,
     symbol_list: Vec<String>,
     ordering: bool,
-    market_bbo_bus: Bus<MarketBBO>,
+    market_bbo_bus: Bus<(MarketBBO, Context)>,
+    warm_up_duration: Duration,
+    instrument_priority: HashMap<String, f64>,
+    quote_cache: Arc<RwLock<HashMap<String, MarketBBO>>>,
+    quote_cache_enabled: bool,
+    arbitrage_profit_sender: crossbeam_channel::Sender<ArbitrageProfit>,
+    arbitrage_ordering: Option<ArbitrageOrdering>,
+    clock: Arc<dyn Clock>,
+    tick_deduplication: bool,
+    tick_hashes: HashMap<String, u64>,
+    referencedata_retries: u32,
+    referencedata_retry_delay: Duration,
+    referencedata_cache: Option<ReferencedataCache>,
+    referencedata_cache_path: Option<String>,
+    referencedata_size_fallback: bool,
+    transaction_config: ArbitrageTransactionConfig,
+    last_prices: Arc<RwLock<HashMap<String, f64>>>,
+    profit_persistence: bool,
+    min_cycles: usize,
+    order_type: OrderType,
+    leg_order_types: HashMap<usize, OrderType>,
+    time_in_force: TimeInForce,
+    leg_time_in_forces: HashMap<usize, TimeInForce>,
+    live_config: Arc<RwLock<ArbitrageLiveConfig>>,
+    validation_sender: Option<crossbeam_channel::Sender<ValidationWarning>>,
+    realized_pnl: Arc<Mutex<HashMap<String, f64>>>,
+    evaluation_pool: Option<rayon::ThreadPool>,
+    balance_fetch_timeout: Duration,
+    fallback_qty_initial: Option<f64>,
+    readiness: Arc<RwLock<HashMap<String, f64>>>,
+    shadow_mode: bool,
+    shadow_totals: Arc<Mutex<ShadowTotals>>,
+    max_leg_distance_ms: Option<f64>,
+    dynamic_qty_in: bool,
+    leg_count: usize,
+    min_consecutive_ticks: u32,
+    suspended_cycles: Arc<RwLock<HashSet<String>>>,
+    evaluation_stats: Arc<RwLock<HashMap<String, EvaluationStats>>>,
+    tick_coalescing_window: Option<Duration>,
+    max_fee_ratio: Option<f64>,
+    adverse_fill_reserve: Option<AdverseFillReserve>,
+    binding_leg_deferral: Option<u32>,
+    profit_tap: Arc<RwLock<Option<crossbeam_channel::Sender<ArbitrageProfit>>>>,
+    processed_ticks: Arc<AtomicU64>,
+    cycle_threads: Arc<AtomicUsize>,
+    heartbeat_interval: Option<Duration>,
+    latency_bucket: Duration,
+    heartbeat_sender: Option<crossbeam_channel::Sender<Heartbeat>>,
+    received_ticks: Arc<AtomicU64>,
+    opportunities: Arc<AtomicU64>,
+    shutdown_requested: Arc<AtomicBool>,
+    thread_handles: Vec<JoinHandle<()>>,
+    signals_handle: Option<Handle>,
 }
 
 impl ArbitrageExecutor {
@@ -50,251 +294,2205 @@ impl ArbitrageExecutor {
     }
 
     // create new instance
-    pub fn new(exchange: String, start_asset: String, symbol_list: &Vec<String>, qty_in: f32, profit_threshold: f32, ordering: bool) -> ArbitrageExecutor {
-        // create bus
-        let mut market_bbo_bus: Bus<MarketBBO> = Bus::new(1000);
-
+    pub fn new(exchange: String, start_assets: &Vec<String>, symbol_list: &Vec<String>, qty_in: f64, profit_threshold: f64, ordering: bool, bus_capacity: usize, api_key: String, secret_key: String, leg_count: usize) -> ArbitrageExecutor {
         // arbitrage database
         let arbitrage_database = ArbitrageDatabase::new("".to_string());
-        arbitrage_database.connect();
+        if let Err(err) = arbitrage_database.connect() {
+            warn!("arbitrage_executor - profit database unavailable. error:{}", err);
+        }
+
+        // arbitrage profit channel
+        let (arbitrage_profit_sender, arbitrage_profit_receiver): (crossbeam_channel::Sender<ArbitrageProfit>, crossbeam_channel::Receiver<ArbitrageProfit>) = crossbeam_channel::unbounded();
+
+        // arbitrage ordering
+        let arbitrage_ordering: ArbitrageOrdering = ArbitrageOrdering::new(
+            arbitrage_profit_receiver, ExecutionMode::PARALLEL, api_key, secret_key,
+        );
+        return ArbitrageExecutor::with_ordering(exchange, start_assets, symbol_list, qty_in, profit_threshold, ordering, bus_capacity,
+                                                Arc::new(arbitrage_database), arbitrage_ordering, arbitrage_profit_sender, leg_count);
+    }
+
+    // new arbitrage executor on a profit sink and an ordering, profits are sent to the ordering through the sender
+    pub fn with_ordering(exchange: String, start_assets: &Vec<String>, symbol_list: &Vec<String>, qty_in: f64, profit_threshold: f64, ordering: bool, bus_capacity: usize,
+                         profit_sink: Arc<dyn ProfitSink>, mut arbitrage_ordering: ArbitrageOrdering, arbitrage_profit_sender: crossbeam_channel::Sender<ArbitrageProfit>, leg_count: usize) -> ArbitrageExecutor {
+        // create bus
+        let market_bbo_bus: Bus<(MarketBBO, Context)> = Bus::new(bus_capacity);
 
+        // quote cache
+        let quote_cache: Arc<RwLock<HashMap<String, MarketBBO>>> = Arc::new(RwLock::new(HashMap::new()));
+        arbitrage_ordering.set_quote_cache(Arc::clone(&quote_cache));
+        let realized_pnl = arbitrage_ordering.get_realized_pnl_handle();
 
         // return arbitrage executor
         ArbitrageExecutor {
             profit_thresold: profit_threshold,
             qty_in: qty_in,
-            arbitrage_database: arbitrage_database,
+            profit_sink: profit_sink,
             ordering: ordering,
             transactions_list: Vec::new(),
             market_bbo_bus: market_bbo_bus,
             symbol_list: symbol_list.clone(),
             exchange: exchange,
-            start_asset: start_asset,
+            start_assets: start_assets.clone(),
+            warm_up_duration: Duration::from_secs(0),
+            instrument_priority: HashMap::new(),
+            quote_cache: quote_cache,
+            quote_cache_enabled: true,
+            arbitrage_profit_sender: arbitrage_profit_sender,
+            arbitrage_ordering: Some(arbitrage_ordering),
+            clock: Arc::new(SystemClock),
+            tick_deduplication: false,
+            tick_hashes: HashMap::new(),
+            referencedata_retries: 3,
+            referencedata_retry_delay: Duration::from_secs(1),
+            referencedata_cache: None,
+            referencedata_cache_path: None,
+            referencedata_size_fallback: false,
+            transaction_config: ArbitrageTransactionConfig::default(),
+            last_prices: Arc::new(RwLock::new(HashMap::new())),
+            profit_persistence: false,
+            min_cycles: 0,
+            order_type: OrderType::LIMIT,
+            leg_order_types: HashMap::new(),
+            time_in_force: TimeInForce::FOK,
+            leg_time_in_forces: HashMap::new(),
+            live_config: Arc::new(RwLock::new(ArbitrageLiveConfig {
+                blacklist: Vec::new(),
+                profit_threshold: profit_threshold,
+                ordering: ordering,
+            })),
+            validation_sender: None,
+            realized_pnl: realized_pnl,
+            evaluation_pool: None,
+            balance_fetch_timeout: Duration::from_secs(10),
+            fallback_qty_initial: None,
+            readiness: Arc::new(RwLock::new(HashMap::new())),
+            shadow_mode: false,
+            shadow_totals: Arc::new(Mutex::new(ShadowTotals::default())),
+            max_leg_distance_ms: None,
+            dynamic_qty_in: false,
+            leg_count: leg_count,
+            min_consecutive_ticks: 1,
+            suspended_cycles: Arc::new(RwLock::new(HashSet::new())),
+            evaluation_stats: Arc::new(RwLock::new(HashMap::new())),
+            tick_coalescing_window: None,
+            max_fee_ratio: None,
+            adverse_fill_reserve: None,
+            binding_leg_deferral: None,
+            profit_tap: Arc::new(RwLock::new(None)),
+            processed_ticks: Arc::new(AtomicU64::new(0)),
+            cycle_threads: Arc::new(AtomicUsize::new(0)),
+            heartbeat_interval: None,
+            latency_bucket: Duration::from_secs(60),
+            heartbeat_sender: None,
+            received_ticks: Arc::new(AtomicU64::new(0)),
+            opportunities: Arc::new(AtomicU64::new(0)),
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
+            thread_handles: Vec::new(),
+            signals_handle: None,
         }
     }
 
-    pub fn initialize(&mut self) {
-        // initialize
-        info!("arbitrage_executor - initializing.");
+    // set clock, shared with arbitrage and ordering
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        if let Some(arbitrage_ordering) = self.arbitrage_ordering.as_mut() {
+            arbitrage_ordering.set_clock(Arc::clone(&clock));
+        }
+        self.clock = clock;
+    }
 
-        // database instrument list
-        let database_instrument_list = izyfo_connectors::database::list();
+    // set warm up duration
+    pub fn set_warm_up_duration(&mut self, warm_up_duration: Duration) {
+        self.warm_up_duration = warm_up_duration;
+    }
 
-        // referencedata
-        let url = String::from("");
-        let referencedata_connector = izyfo_connectors::referencedata::ReferencedataConnector::from_url(url);
-        let referencedata = referencedata_connector.get_referencedata(&self.exchange);
+    // set instrument priority (e.g. referencedata volume), used to sort the transactions list
+    pub fn set_instrument_priority(&mut self, instrument_priority: HashMap<String, f64>) {
+        self.instrument_priority = instrument_priority;
+    }
 
-        // referencedata instrument list
-        let referencedata_instrument_list = referencedata.get_instrument_list();
+    // enable quote cache
+    pub fn set_quote_cache_enabled(&mut self, quote_cache_enabled: bool) {
+        self.quote_cache_enabled = quote_cache_enabled;
+    }
 
-        // symbol list
-        let combinations = self.symbol_list.iter().combinations(3);
+    // enable profit persistence, written off the hot path by a dedicated thread
+    pub fn set_profit_persistence(&mut self, profit_persistence: bool) {
+        self.profit_persistence = profit_persistence;
+    }
 
-        for c in combinations {
-            let mut permutations: Vec<Vec<String>> = vec![
-                vec![c[0].to_string(), c[1].to_string(), c[2].to_string()],
-                vec![c[0].to_string(), c[2].to_string(), c[1].to_string()],
-                vec![c[1].to_string(), c[0].to_string(), c[2].to_string()],
-                vec![c[1].to_string(), c[2].to_string(), c[0].to_string()],
-                vec![c[2].to_string().to_string(), c[0].to_string(), c[1].to_string()],
-                vec![c[2].to_string(), c[1].to_string(), c[0].to_string()]
-            ];
-
-
-            for p in &permutations {
-                let symbol_1 = &p[0];
-                let symbol_2 = &p[1];
-                let symbol_3 = &p[2];
-
-                if symbol_1.to_string() == self.start_asset {
-                    // transaction 1
-                    let mut transaction_1: HashMap<String, String> = HashMap::new();
-                    transaction_1.insert("source".to_string(), format!("{}_{}", self.exchange, symbol_1));
-                    transaction_1.insert("target".to_string(), format!("{}_{}", self.exchange, symbol_2));
-
-                    let mut instrument_a = format!("{}_{}_{}", self.exchange, symbol_1, symbol_2);
-                    let mut instrument_b = format!("{}_{}_{}", self.exchange, symbol_2, symbol_1);
-
-                    if database_instrument_list.contains(&instrument_a) {
-                        transaction_1.insert("operation".to_string(), "SELL".to_string());
-                        transaction_1.insert("instrument".to_string(), instrument_a);
-                        transaction_1.insert("exchange_code".to_string(), format!("{}{}", symbol_1, symbol_2));
-                    } else if database_instrument_list.contains(&instrument_b) {
-                        transaction_1.insert("operation".to_string(), "BUY".to_string());
-                        transaction_1.insert("instrument".to_string(), instrument_b);
-                        transaction_1.insert("exchange_code".to_string(), format!("{}{}", symbol_2, symbol_1));
-                    } else {
-                        break;
-                    }
+    // set profit sink backend (postgres, sqlite, jsonl)
+    pub fn set_profit_sink(&mut self, profit_sink_config: &ProfitSinkConfig) -> Result<(), String> {
+        self.profit_sink = profit_sink_config.create()?;
+        return Ok(());
+    }
 
-                    // transaction 2
-                    let mut transaction_2: HashMap<String, String> = HashMap::new();
-                    transaction_2.insert("source".to_string(), format!("{}_{}", self.exchange, symbol_2));
-                    transaction_2.insert("target".to_string(), format!("{}_{}", self.exchange, symbol_3));
-
-                    instrument_a = format!("{}_{}_{}", self.exchange, symbol_2, symbol_3);
-                    instrument_b = format!("{}_{}_{}", self.exchange, symbol_3, symbol_2);
-
-
-                    if database_instrument_list.contains(&instrument_a) {
-                        transaction_2.insert("operation".to_string(), "SELL".to_string());
-                        transaction_2.insert("instrument".to_string(), instrument_a);
-                        transaction_2.insert("exchange_code".to_string(), format!("{}{}", symbol_2, symbol_3));
-                    } else if database_instrument_list.contains(&instrument_b) {
-                        transaction_2.insert("operation".to_string(), "BUY".to_string());
-                        transaction_2.insert("instrument".to_string(), instrument_b);
-                        transaction_2.insert("exchange_code".to_string(), format!("{}{}", symbol_3, symbol_2));
-                    } else {
-                        break;
-                    }
+    // set transaction config
+    pub fn set_transaction_config(&mut self, transaction_config: ArbitrageTransactionConfig) {
+        self.transaction_config = transaction_config;
+    }
 
-                    // transaction 3
-                    let mut transaction_3: HashMap<String, String> = HashMap::new();
-                    transaction_3.insert("source".to_string(), format!("{}_{}", self.exchange, symbol_3));
-                    transaction_3.insert("target".to_string(), format!("{}_{}", self.exchange, symbol_1));
-
-                    instrument_a = format!("{}_{}_{}", self.exchange, symbol_3, symbol_1);
-                    instrument_b = format!("{}_{}_{}", self.exchange, symbol_1, symbol_3);
-
-                    if database_instrument_list.contains(&instrument_a) {
-                        transaction_3.insert("operation".to_string(), "SELL".to_string());
-                        transaction_3.insert("instrument".to_string(), instrument_a);
-                        transaction_3.insert("exchange_code".to_string(), format!("{}{}", symbol_3, symbol_1));
-                    } else if database_instrument_list.contains(&instrument_b) {
-                        transaction_3.insert("operation".to_string(), "BUY".to_string());
-                        transaction_3.insert("instrument".to_string(), instrument_b);
-                        transaction_3.insert("exchange_code".to_string(), format!("{}{}", symbol_1, symbol_3));
-                    } else {
-                        break;
-                    }
+    // coalesce ticks over the window, evaluating each arbitrage once per window with the latest quotes
+    pub fn set_tick_coalescing_window(&mut self, tick_coalescing_window: Option<Duration>) {
+        self.tick_coalescing_window = tick_coalescing_window;
+    }
+
+    // enable deduplication of identical consecutive ticks
+    pub fn set_tick_deduplication(&mut self, tick_deduplication: bool) {
+        self.tick_deduplication = tick_deduplication;
+    }
+
+    // return market bbo hash
+    fn get_tick_hash(market_bbo: &MarketBBO) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        market_bbo.get_instrument().hash(&mut hasher);
+        market_bbo.get_ask_price().to_bits().hash(&mut hasher);
+        market_bbo.get_bid_price().to_bits().hash(&mut hasher);
+        market_bbo.get_ask_qty().to_bits().hash(&mut hasher);
+        market_bbo.get_bid_qty().to_bits().hash(&mut hasher);
+        return hasher.finish();
+    }
+
+    // return arbitrage ordering, available for configuration until start
+    pub fn get_arbitrage_ordering(&mut self) -> Option<&mut ArbitrageOrdering> {
+        return self.arbitrage_ordering.as_mut();
+    }
+
+    // return quote cache
+    pub fn get_quote_cache(&self) -> Arc<RwLock<HashMap<String, MarketBBO>>> {
+        return Arc::clone(&self.quote_cache);
+    }
+
+    // set referencedata retries, the delay doubles after each attempt
+    pub fn set_referencedata_retry(&mut self, referencedata_retries: u32, referencedata_retry_delay: Duration) {
+        self.referencedata_retries = referencedata_retries;
+        self.referencedata_retry_delay = referencedata_retry_delay;
+    }
+
+    // use referencedata step and tick size when a tick carries zeros
+    pub fn set_referencedata_size_fallback(&mut self, referencedata_size_fallback: bool) {
+        self.referencedata_size_fallback = referencedata_size_fallback;
+    }
+
+    // set referencedata cache file, the last fetched instrument list is kept there across restarts
+    pub fn set_referencedata_cache_path(&mut self, referencedata_cache_path: Option<String>) {
+        self.referencedata_cache_path = referencedata_cache_path;
+    }
+
+    // fetch referencedata instrument list, falling back to the cached copy
+    fn fetch_referencedata_instrument_list(&mut self) -> Vec<String> {
+        let exchange = self.exchange.clone();
+        let referencedata_size_fallback = self.referencedata_size_fallback;
+        let mut referencedata_sizes: HashMap<String, (f64, f64)> = HashMap::new();
 
-                    // check instruments in referencedata
-                    if referencedata_instrument_list.contains(&transaction_1.get("instrument").unwrap())
-                        & referencedata_instrument_list.contains(&transaction_2.get("instrument").unwrap()) &
-                        &referencedata_instrument_list.contains(&transaction_3.get("instrument").unwrap()) {
-                        let transactions = vec![transaction_1, transaction_2, transaction_3];
-                        self.transactions_list.push(transactions);
+        let instrument_list = self.fetch_instrument_list_with_fallback(|| {
+            let url = String::from("");
+            let referencedata_connector = izyfo_connectors::referencedata::ReferencedataConnector::from_url(url);
+            let referencedata = referencedata_connector.get_referencedata(&exchange);
+            let instrument_list: Vec<String> = referencedata.get_instrument_list().iter().map(|i| i.to_string()).collect();
+
+            // step and tick size fallback
+            if referencedata_size_fallback {
+                for instrument_id in &instrument_list {
+                    if let Some(instrument) = referencedata.get_instrument_by_id(instrument_id.clone()) {
+                        referencedata_sizes.insert(instrument_id.clone(), (to_f64(instrument.get_step_size()), to_f64(instrument.get_tick_size())));
                     }
                 }
             }
+            instrument_list
+        });
+
+        self.transaction_config.referencedata_sizes.extend(referencedata_sizes);
+        return instrument_list;
+    }
+
+    // fetch instrument list with retries, an empty list is an outage, then fall back to the cached copy
+    fn fetch_instrument_list_with_fallback<F: FnMut() -> Vec<String>>(&mut self, mut fetch: F) -> Vec<String> {
+        let mut delay = self.referencedata_retry_delay;
+
+        for attempt in 0..(self.referencedata_retries + 1) {
+            let instrument_list = fetch();
+
+            if !instrument_list.is_empty() {
+                self.store_referencedata_cache(ReferencedataCache {
+                    instrument_list: instrument_list.clone(),
+                    cached_at: self.clock.now(),
+                });
+                return instrument_list;
+            }
+
+            warn!("arbitrage_executor - referencedata unavailable. attempt:{}", attempt + 1);
+            if attempt < self.referencedata_retries {
+                thread::sleep(delay);
+                delay = delay * 2;
+            }
         }
 
-        info!("arbitrage_executor - initialization finished. total arbitrage:{}", self.transactions_list.len());
+        // cached copy, from this run or a previous one
+        if self.referencedata_cache.is_none() {
+            self.referencedata_cache = self.load_referencedata_cache();
+        }
+        match &self.referencedata_cache {
+            Some(referencedata_cache) => {
+                let age = self.clock.now().signed_duration_since(referencedata_cache.cached_at);
+                warn!("arbitrage_executor - using stale cached referencedata. cached_at:{}, age_secs:{}, instruments:{}",
+                      referencedata_cache.cached_at, age.num_seconds(), referencedata_cache.instrument_list.len());
+                referencedata_cache.instrument_list.clone()
+            }
+            None => {
+                error!("arbitrage_executor - referencedata unavailable and no cached copy.");
+                Vec::new()
+            }
+        }
     }
 
-    // start arbitrage
-    pub fn start(&mut self) {
-        // receiver
-        info!("arbitrage_executor - starting.");
+    // keep referencedata cache, written to the cache file when set
+    fn store_referencedata_cache(&mut self, referencedata_cache: ReferencedataCache) {
+        if let Some(path) = &self.referencedata_cache_path {
+            let result = serde_json::to_string(&referencedata_cache).map_err(|e| e.to_string())
+                .and_then(|content| fs::write(path, content).map_err(|e| e.to_string()));
+            if let Err(err) = result {
+                error!("arbitrage_executor - referencedata cache write failed. path:{}, error:{}", path, err);
+            }
+        }
+        self.referencedata_cache = Some(referencedata_cache);
+    }
+
+    // load referencedata cache file
+    fn load_referencedata_cache(&self) -> Option<ReferencedataCache> {
+        let path = self.referencedata_cache_path.as_ref()?;
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!("arbitrage_executor - referencedata cache unreadable. path:{}, error:{}", path, err);
+                return None;
+            }
+        };
+        match serde_json::from_str::<ReferencedataCache>(&content) {
+            Ok(referencedata_cache) => Some(referencedata_cache),
+            Err(err) => {
+                error!("arbitrage_executor - referencedata cache invalid. path:{}, error:{}", path, err);
+                None
+            }
+        }
+    }
 
-        // qty initial
-        let mut qty_initial: f32 = self.qty_in.clone();
+    // return live config
+    pub fn get_live_config(&self) -> ArbitrageLiveConfig {
+        return self.live_config.read().unwrap().clone();
+    }
 
-        // mode
-        let mode = ExecutionMode::PARALLEL;
+    // reload live config (blacklist, threshold, ordering), to be called on SIGHUP
+    pub fn reload_config(&self, path: &str) -> Result<ArbitrageLiveConfig, String> {
+        ArbitrageExecutor::reload_live_config(&self.live_config, path)
+    }
 
-        // arbitrage profit channel
-        let (arbitrage_profit_sender, arbitrage_profit_receiver): (crossbeam_channel::Sender<ArbitrageProfit>, crossbeam_channel::Receiver<ArbitrageProfit>) = crossbeam_channel::unbounded();
+    fn reload_live_config(live_config: &Arc<RwLock<ArbitrageLiveConfig>>, path: &str) -> Result<ArbitrageLiveConfig, String> {
+        let current = live_config.read().unwrap().clone();
+        let config = ArbitrageLiveConfig::from_file(path, &current)?;
+        *live_config.write().unwrap() = config.clone();
+        info!("arbitrage_executor - config reloaded. config:{:?}", config);
+        Ok(config)
+    }
 
-        // arbitrage ordering
-        let mut arbitrage_ordering: ArbitrageOrdering = ArbitrageOrdering::new(
-            arbitrage_profit_receiver.clone(), mode.clone(),
-        );
+    // reload live config from the file on every SIGHUP, until shutdown
+    pub fn start_config_reload_on_sighup(&mut self, path: String) -> Result<(), String> {
+        let mut signals = Signals::new(&[SIGHUP]).map_err(|e| format!("failed to register SIGHUP: {}", e))?;
+        self.signals_handle = Some(signals.handle());
 
-        // arbitrage ordering
-        if self.ordering {
-            // update balances
-            arbitrage_ordering.update_balances();
+        let c_live_config = Arc::clone(&self.live_config);
+        self.thread_handles.push(thread::spawn(move || {
+            info!("arbitrage_executor - config reload on SIGHUP. path:{}", path);
+            for _ in signals.forever() {
+                if let Err(e) = ArbitrageExecutor::reload_live_config(&c_live_config, &path) {
+                    error!("arbitrage_executor - config reload failed. error:{}", e);
+                }
+            }
+        }));
+        Ok(())
+    }
 
-            // balance
-            let balance = arbitrage_ordering.get_balance(&self.start_asset);
-            match balance {
-                Some(b) => {
-                    if b > &0.0 {
-                        qty_initial = b.clone() / 3.0;
+    // set time bucket of the persisted latency percentiles
+    pub fn set_latency_bucket(&mut self, latency_bucket: Duration) {
+        self.latency_bucket = latency_bucket;
+    }
+
+    // set heartbeat interval, none disables the heartbeat
+    pub fn set_heartbeat_interval(&mut self, heartbeat_interval: Option<Duration>) {
+        self.heartbeat_interval = heartbeat_interval;
+    }
+
+    // set heartbeat sender, used to export the heartbeat as a metric
+    pub fn set_heartbeat_sender(&mut self, heartbeat_sender: Option<crossbeam_channel::Sender<Heartbeat>>) {
+        self.heartbeat_sender = heartbeat_sender;
+    }
+
+    // return heartbeat of the run started at the start date
+    pub fn get_heartbeat(&self, start_date: DateTime<Utc>) -> Heartbeat {
+        return ArbitrageExecutor::build_heartbeat(&self.clock, start_date, &self.received_ticks, &self.opportunities);
+    }
+
+    // build heartbeat
+    fn build_heartbeat(clock: &Arc<dyn Clock>, start_date: DateTime<Utc>, received_ticks: &Arc<AtomicU64>, opportunities: &Arc<AtomicU64>) -> Heartbeat {
+        let now = clock.now();
+        return Heartbeat {
+            uptime_secs: now.signed_duration_since(start_date).num_seconds(),
+            ticks: received_ticks.load(atomic::Ordering::SeqCst),
+            opportunities: opportunities.load(atomic::Ordering::SeqCst),
+            create_at: now,
+        };
+    }
+
+    // start heartbeat thread, firing when the interval elapsed on the clock
+    fn start_heartbeat(&self, start_date: DateTime<Utc>) -> Option<JoinHandle<()>> {
+        let heartbeat_interval = self.heartbeat_interval.and_then(|interval| chrono::Duration::from_std(interval).ok())?;
+        let c_clock = Arc::clone(&self.clock);
+        let c_received_ticks = Arc::clone(&self.received_ticks);
+        let c_opportunities = Arc::clone(&self.opportunities);
+        let c_heartbeat_sender = self.heartbeat_sender.clone();
+        let c_shutdown_requested = Arc::clone(&self.shutdown_requested);
+        let heartbeat_handle = thread::spawn(move || {
+            let mut last_heartbeat = c_clock.now();
+            while !c_shutdown_requested.load(atomic::Ordering::SeqCst) {
+                thread::sleep(HEARTBEAT_POLL_INTERVAL);
+                if c_clock.now().signed_duration_since(last_heartbeat) < heartbeat_interval {
+                    continue;
+                }
+                let heartbeat = ArbitrageExecutor::build_heartbeat(&c_clock, start_date, &c_received_ticks, &c_opportunities);
+                info!("arbitrage_executor - heartbeat. uptime_secs:{}, ticks:{}, opportunities:{}", heartbeat.uptime_secs, heartbeat.ticks, heartbeat.opportunities);
+                last_heartbeat = heartbeat.create_at;
+                if let Some(heartbeat_sender) = &c_heartbeat_sender {
+                    if let Err(err) = heartbeat_sender.try_send(heartbeat) {
+                        warn!("arbitrage_executor - heartbeat not delivered. error:{}", err);
                     }
                 }
-                None => ()
+            }
+        });
+        return Some(heartbeat_handle);
+    }
+
+    // stop cycle threads and ordering, waiting for the ordering to clean balances one last time
+    pub fn shutdown(&mut self) {
+        info!("arbitrage_executor - shutting down. threads:{}", self.thread_handles.len());
+        self.shutdown_requested.store(true, atomic::Ordering::SeqCst);
+        if let Some(signals_handle) = self.signals_handle.take() {
+            signals_handle.close();
+        }
+        for thread_handle in self.thread_handles.drain(..) {
+            if let Err(err) = thread_handle.join() {
+                error!("arbitrage_executor - thread panicked. error:{:?}", err);
             }
         }
+        info!("arbitrage_executor - shut down.");
+    }
 
-        // start arbitrage ordering
-        let c_arbitrage_profit_receiver = arbitrage_profit_receiver.clone();
-        let c_mode = mode.clone();
-        thread::spawn(move || {
-            let mut arbitrage_ordering = ArbitrageOrdering::new(
-                c_arbitrage_profit_receiver, c_mode,
-            );
-            arbitrage_ordering.start();
-        });
+    // set validation warning sender, used to surface validation failures to operators
+    pub fn set_validation_sender(&mut self, validation_sender: Option<crossbeam_channel::Sender<ValidationWarning>>) {
+        self.validation_sender = validation_sender;
+    }
 
+    // return cumulative realized pnl per start asset
+    pub fn get_realized_pnl(&self) -> HashMap<String, f64> {
+        return self.realized_pnl.lock().unwrap().clone();
+    }
 
-        // arbitrage executor
-        info!("arbitrage_executor - initial balance. balance:{}", qty_initial);
+    // start stats server serving realized pnl on /stats, until shutdown
+    pub fn start_stats_server(&mut self, address: &str) -> Result<String, String> {
+        let stats_server = StatsServer::bind(address)?;
+        let stats_address = stats_server.get_address();
+        self.thread_handles.push(stats_server.start(Arc::clone(&self.realized_pnl), Arc::clone(&self.shutdown_requested)));
+        info!("arbitrage_executor - stats server listening. address:{}", stats_address);
+        Ok(stats_address)
+    }
 
-        // transactions
-        for transactions in &self.transactions_list {
+    // set default order type
+    pub fn set_order_type(&mut self, order_type: OrderType) {
+        self.order_type = order_type;
+    }
 
-            // qty in
-            let c_qty_in: f32 = qty_initial.clone();
+    // set order type of a leg, overriding the default
+    pub fn set_leg_order_type(&mut self, leg: usize, order_type: OrderType) {
+        self.leg_order_types.insert(leg, order_type);
+    }
 
-            // arbitrage profit sender clone
-            let c_arbitrage_profit_sender = arbitrage_profit_sender.clone();
+    // set default time in force of limit orders
+    pub fn set_time_in_force(&mut self, time_in_force: TimeInForce) {
+        self.time_in_force = time_in_force;
+    }
 
-            // scale
-            let scale = true;
+    // set time in force of a leg, overriding the default
+    pub fn set_leg_time_in_force(&mut self, leg: usize, time_in_force: TimeInForce) {
+        self.leg_time_in_forces.insert(leg, time_in_force);
+    }
 
-            // transactions clone
-            let c_transactions = transactions.clone();
+    // set minimum number of cycles required by initialize
+    pub fn set_min_cycles(&mut self, min_cycles: usize) {
+        self.min_cycles = min_cycles;
+    }
 
-            // market bbo bus receiver
-            let mut market_bbo_receiver = self.market_bbo_bus.add_rx();
+    // suspend or resume a cycle by name, persisted so it survives a restart
+    pub fn set_cycle_suspended(&self, name: &String, suspended: bool) {
+        if suspended {
+            self.suspended_cycles.write().unwrap().insert(name.clone());
+        } else {
+            self.suspended_cycles.write().unwrap().remove(name);
+        }
+        self.profit_sink.set_cycle_state(&CycleState {
+            name: name.clone(),
+            suspended: suspended,
+            create_at: self.clock.now(),
+        });
+        info!("arbitrage_executor - cycle state changed. name:{}, suspended:{}", name, suspended);
+    }
 
-            // ordering
-            let c_ordering = self.ordering.clone();
+    // return suspended cycles
+    pub fn get_suspended_cycles(&self) -> Vec<String> {
+        let mut suspended_cycles: Vec<String> = self.suspended_cycles.read().unwrap().iter().cloned().collect();
+        suspended_cycles.sort();
+        return suspended_cycles;
+    }
 
-            // arbitrage profit thread
-            thread::spawn(move || {
+    pub fn initialize(&mut self) -> Result<usize, String> {
+        // initialize
+        info!("arbitrage_executor - initializing. leg_count:{}", self.leg_count);
 
-                // arbitrage
-                let mut arbitrage = Arbitrage::from_transaction_list(&c_transactions);
-                info!("arbitrage_executor - arbitrage. name:{}, scale:{}, qty_in:{}", arbitrage.get_name(), scale, c_qty_in);
+        // a cycle needs at least three legs
+        if self.leg_count < 3 {
+            return Err(format!("invalid leg count: '{}'", self.leg_count));
+        }
 
-                // loop
-                loop {
-                    // receive market bbo
-                    let market_bbo = market_bbo_receiver.recv().unwrap();
+        // a re-initialization rebuilds the cycles from scratch
+        self.transactions_list.clear();
 
-                    // get market bbo feed
-                    let feed = market_bbo.get_feed();
+        // suspended cycles
+        let suspended_cycles = self.profit_sink.get_suspended_cycles();
+        info!("arbitrage_executor - suspended cycles loaded. total:{}", suspended_cycles.len());
+        *self.suspended_cycles.write().unwrap() = suspended_cycles.into_iter().collect();
 
-                    // check arbitrage contains feed
-                    if arbitrage.instrument_list.contains(&feed) {
+        // database instrument list
+        let database_instrument_list = izyfo_connectors::database::list();
 
-                        // execute arbitrage
-                        let arbitrage_profit = arbitrage.execute(&market_bbo, c_qty_in, scale);
+        // referencedata instrument list
+        let referencedata_instrument_list = self.fetch_referencedata_instrument_list();
 
-                        match arbitrage_profit {
-                            Some(p) => {
-                                if p.get_profit() > 0.0 {
-                                    info!("arbitrage_executor - arbitrage profit. profit:{}, latency:{}(ms)", p, p.get_latency_ms());
-                                    if c_ordering & & p.is_valid_ordering(){
-                                        c_arbitrage_profit_sender.send(p);
-                                    }
-                                }
-                            }
-                            None => {}
-                        }
+        // transactions
+        self.build_transactions_list(&database_instrument_list, &referencedata_instrument_list);
+
+        info!("arbitrage_executor - initialization finished. total arbitrage:{}", self.transactions_list.len());
+
+        return self.check_min_cycles();
+    }
+
+    // check minimum cycles, returning the number of cycles
+    fn check_min_cycles(&self) -> Result<usize, String> {
+        if self.transactions_list.len() < self.min_cycles {
+            error!("arbitrage_executor - too few arbitrage. total arbitrage:{}, min_cycles:{}", self.transactions_list.len(), self.min_cycles);
+            return Err(format!("too few arbitrage: '{}' < '{}'", self.transactions_list.len(), self.min_cycles));
+        }
+        Ok(self.transactions_list.len())
+    }
+
+    // build cycles of the symbols starting on a start asset, every instrument must be in referencedata
+    fn build_transactions_list(&mut self, database_instrument_list: &Vec<String>, referencedata_instrument_list: &Vec<String>) {
+        // symbol list
+        let combinations = self.symbol_list.iter().combinations(self.leg_count);
+
+        for c in combinations {
+            for p in c.iter().permutations(self.leg_count) {
+                // cycle starts on a start asset
+                if !self.start_assets.contains(p[0]) {
+                    continue;
+                }
+
+                // one transaction per leg, the last leg closes the cycle
+                let mut transactions: Vec<HashMap<String, String>> = Vec::new();
+                for leg in 0..self.leg_count {
+                    let source = p[leg];
+                    let target = p[(leg + 1) % self.leg_count];
+                    match self.build_transaction(source, target, database_instrument_list) {
+                        Some(transaction) => transactions.push(transaction),
+                        None => break
                     }
                 }
-            });
+                if transactions.len() != self.leg_count {
+                    continue;
+                }
+
+                // check instruments in referencedata
+                if transactions.iter().all(|t| referencedata_instrument_list.contains(t.get("instrument").unwrap())) {
+                    // order type and time in force per leg
+                    for (leg, transaction) in transactions.iter_mut().enumerate() {
+                        let order_type = self.leg_order_types.get(&leg).unwrap_or(&self.order_type);
+                        transaction.insert("order_type".to_string(), order_type.to_string());
+                        let time_in_force = self.leg_time_in_forces.get(&leg).unwrap_or(&self.time_in_force);
+                        transaction.insert("time_in_force".to_string(), time_in_force.to_string());
+                    }
+
+                    self.transactions_list.push(transactions);
+                }
+            }
+        }
+
+        // sort by priority
+        if !self.instrument_priority.is_empty() {
+            self.sort_transactions_list();
         }
     }
 
+    // build transaction from source to target, none if neither instrument direction exists
+    fn build_transaction(&self, source: &String, target: &String, database_instrument_list: &Vec<String>) -> Option<HashMap<String, String>> {
+        let mut transaction: HashMap<String, String> = HashMap::new();
+        transaction.insert("source".to_string(), format!("{}_{}", self.exchange, source));
+        transaction.insert("target".to_string(), format!("{}_{}", self.exchange, target));
 
-    // execute arbitrage
-    pub fn execute(&mut self, market_bbo: MarketBBO) {
-        self.market_bbo_bus.broadcast(market_bbo);
+        let instrument_a = format!("{}_{}_{}", self.exchange, source, target);
+        let instrument_b = format!("{}_{}_{}", self.exchange, target, source);
+
+        if database_instrument_list.contains(&instrument_a) {
+            transaction.insert("operation".to_string(), "SELL".to_string());
+            transaction.insert("instrument".to_string(), instrument_a);
+            transaction.insert("exchange_code".to_string(), format!("{}{}", source, target));
+        } else if database_instrument_list.contains(&instrument_b) {
+            transaction.insert("operation".to_string(), "BUY".to_string());
+            transaction.insert("instrument".to_string(), instrument_b);
+            transaction.insert("exchange_code".to_string(), format!("{}{}", target, source));
+        } else {
+            return None;
+        }
+        return Some(transaction);
+    }
+
+    // return transactions priority, a cycle is as liquid as its weakest leg
+    fn get_transactions_priority(&self, transactions: &Vec<HashMap<String, String>>) -> f64 {
+        let mut priority = std::f64::MAX;
+        for transaction in transactions {
+            let instrument = transaction.get("instrument").unwrap();
+            let value = self.instrument_priority.get(instrument).cloned().unwrap_or(0.0);
+            if value < priority {
+                priority = value;
+            }
+        }
+        return priority;
     }
-}
\ No newline at end of file
+
+    // sort transactions list by descending priority, tie-break on instruments
+    fn sort_transactions_list(&mut self) {
+        let mut transactions_list = self.transactions_list.clone();
+        transactions_list.sort_by(|a, b| {
+            let priority_a = self.get_transactions_priority(a);
+            let priority_b = self.get_transactions_priority(b);
+            match priority_b.partial_cmp(&priority_a).unwrap_or(Ordering::Equal) {
+                Ordering::Equal => {
+                    let name_a = a.iter().map(|t| t.get("instrument").unwrap().to_string()).join(":");
+                    let name_b = b.iter().map(|t| t.get("instrument").unwrap().to_string()).join(":");
+                    name_a.cmp(&name_b)
+                }
+                ordering => ordering
+            }
+        });
+        self.transactions_list = transactions_list;
+    }
+
+    // set initial balance fetch timeout and the qty used when it fails
+    pub fn set_balance_fetch_timeout(&mut self, balance_fetch_timeout: Duration, fallback_qty_initial: Option<f64>) {
+        self.balance_fetch_timeout = balance_fetch_timeout;
+        self.fallback_qty_initial = fallback_qty_initial;
+    }
+
+    // enable shadow mode, recording the theoretical profit of every opportunity including skipped ones
+    pub fn set_shadow_mode(&mut self, shadow_mode: bool) {
+        self.shadow_mode = shadow_mode;
+    }
+
+    // set number of consecutive profitable ticks required before a cycle is sent to ordering
+    pub fn set_min_consecutive_ticks(&mut self, min_consecutive_ticks: u32) {
+        self.min_consecutive_ticks = min_consecutive_ticks.max(1);
+    }
+
+    // size each cycle from the live start asset balance rather than the startup snapshot
+    pub fn set_dynamic_qty_in(&mut self, dynamic_qty_in: bool) {
+        self.dynamic_qty_in = dynamic_qty_in;
+    }
+
+    // set maximum time spanned by the leg quotes of a profit
+    pub fn set_max_leg_distance_ms(&mut self, max_leg_distance_ms: Option<f64>) {
+        self.max_leg_distance_ms = max_leg_distance_ms;
+    }
+
+    // set maximum fraction of the gross profit the fees may take
+    pub fn set_max_fee_ratio(&mut self, max_fee_ratio: Option<f64>) {
+        self.max_fee_ratio = max_fee_ratio;
+    }
+
+    // set reserve the predicted profit must cover on top of the threshold before ordering
+    pub fn set_adverse_fill_reserve(&mut self, adverse_fill_reserve: Option<AdverseFillReserve>) {
+        self.adverse_fill_reserve = adverse_fill_reserve;
+    }
+
+    // set number of ticks on non-binding legs a cycle defers before evaluating, none evaluates every tick
+    pub fn set_binding_leg_deferral(&mut self, binding_leg_deferral: Option<u32>) {
+        self.binding_leg_deferral = binding_leg_deferral;
+    }
+
+    // return shadow totals
+    pub fn get_shadow_totals(&self) -> ShadowTotals {
+        return self.shadow_totals.lock().unwrap().clone();
+    }
+
+    // return readiness ratio per arbitrage
+    pub fn get_readiness_ratios(&self) -> HashMap<String, f64> {
+        return self.readiness.read().unwrap().clone();
+    }
+
+    // return evaluation latency stats per arbitrage, snapshot evaluations are under "snapshot"
+    pub fn get_evaluation_stats(&self) -> HashMap<String, EvaluationStats> {
+        return self.evaluation_stats.read().unwrap().clone();
+    }
+
+    // set number of threads used by evaluate_snapshot, 1 evaluates serially
+    pub fn set_evaluation_threads(&mut self, evaluation_threads: usize) -> Result<(), String> {
+        if evaluation_threads <= 1 {
+            self.evaluation_pool = None;
+            return Ok(());
+        }
+        let evaluation_pool = rayon::ThreadPoolBuilder::new().num_threads(evaluation_threads).build()
+            .map_err(|e| format!("failed to build evaluation pool: {}", e))?;
+        self.evaluation_pool = Some(evaluation_pool);
+        Ok(())
+    }
+
+    // evaluate every arbitrage against a read-only quote snapshot, returning profitable ones
+    pub fn evaluate_snapshot(&self, snapshot: &HashMap<String, MarketBBO>) -> Vec<ArbitrageProfit> {
+        let start_date = Instant::now();
+        let profits = self.evaluate_snapshot_profits(snapshot);
+
+        // evaluation latency
+        self.evaluation_stats.write().unwrap().entry("snapshot".to_string()).or_insert_with(EvaluationStats::default).record(start_date.elapsed());
+        return profits;
+    }
+
+    fn evaluate_snapshot_profits(&self, snapshot: &HashMap<String, MarketBBO>) -> Vec<ArbitrageProfit> {
+        let qty_in = self.qty_in;
+        let transaction_config = &self.transaction_config;
+        let clock = &self.clock;
+        let live_config = &self.live_config;
+        let suspended_cycles = &self.suspended_cycles;
+        let evaluate = |transactions: &Vec<HashMap<String, String>>| {
+            ArbitrageExecutor::evaluate_cycle(transactions, snapshot, qty_in, transaction_config, clock, live_config, suspended_cycles)
+        };
+
+        // cycles are independent, the snapshot is only read
+        return match &self.evaluation_pool {
+            Some(evaluation_pool) => evaluation_pool.install(|| self.transactions_list.par_iter().filter_map(evaluate).collect()),
+            None => self.transactions_list.iter().filter_map(evaluate).collect()
+        };
+    }
+
+    // evaluate a cycle against a quote snapshot, skipping blacklisted and suspended cycles
+    fn evaluate_cycle(transactions: &[HashMap<String, String>], snapshot: &HashMap<String, MarketBBO>, qty_in: f64, transaction_config: &ArbitrageTransactionConfig,
+                      clock: &Arc<dyn Clock>, live_config: &Arc<RwLock<ArbitrageLiveConfig>>, suspended_cycles: &Arc<RwLock<HashSet<String>>>) -> Option<ArbitrageProfit> {
+        let mut arbitrage = match Arbitrage::from_transaction_list(&transactions.to_vec()) {
+            Ok(a) => a,
+            Err(e) => {
+                error!("arbitrage_executor - invalid arbitrage. error:{}", e);
+                return None;
+            }
+        };
+
+        // skip blacklisted arbitrage
+        if live_config.read().unwrap().blacklist.contains(arbitrage.get_name()) {
+            return None;
+        }
+
+        // skip suspended arbitrage
+        if suspended_cycles.read().unwrap().contains(arbitrage.get_name()) {
+            return None;
+        }
+
+        arbitrage.set_transaction_config(transaction_config);
+        arbitrage.set_clock(Arc::clone(clock));
+        return arbitrage.evaluate(snapshot, qty_in, true).filter(|p| p.get_profit() > 0.0);
+    }
+
+    // return every asset the arbitrage could hold
+    pub fn all_assets(&self) -> Vec<String> {
+        let prefix = format!("{}_", self.exchange);
+        let mut assets: Vec<String> = Vec::new();
+        for transactions in &self.transactions_list {
+            for transaction in transactions {
+                for key in &["source", "target"] {
+                    let asset = transaction.get(*key).unwrap().replace(&prefix, "");
+                    if !assets.contains(&asset) {
+                        assets.push(asset);
+                    }
+                }
+            }
+        }
+        assets.sort();
+        return assets;
+    }
+
+    // export the tradable pairs graph to dot, cycle legs are highlighted
+    pub fn export_dot(&self) -> String {
+        let prefix = format!("{}_", self.exchange);
+        let mut nodes: Vec<String> = Vec::new();
+        let mut pairs: Vec<(String, String, String)> = Vec::new();
+        let mut legs: Vec<(String, String, String)> = Vec::new();
+
+        for transactions in &self.transactions_list {
+            for transaction in transactions {
+                let source = transaction.get("source").unwrap().replace(&prefix, "");
+                let target = transaction.get("target").unwrap().replace(&prefix, "");
+                let instrument = transaction.get("instrument").unwrap().to_string();
+
+                for asset in vec![source.clone(), target.clone()] {
+                    if !nodes.contains(&asset) {
+                        nodes.push(asset);
+                    }
+                }
+
+                // tradable pair
+                let pair = if source < target {
+                    (source.clone(), target.clone(), instrument.clone())
+                } else {
+                    (target.clone(), source.clone(), instrument.clone())
+                };
+                if !pairs.contains(&pair) {
+                    pairs.push(pair);
+                }
+
+                // cycle leg
+                let leg = (source, target, transaction.get("operation").unwrap().to_string());
+                if !legs.contains(&leg) {
+                    legs.push(leg);
+                }
+            }
+        }
+
+        let mut dot = String::from("digraph arbitrage {\n");
+        for node in &nodes {
+            if self.start_assets.contains(node) {
+                dot.push_str(&format!("    \"{}\" [shape=doublecircle];\n", node));
+            } else {
+                dot.push_str(&format!("    \"{}\";\n", node));
+            }
+        }
+        for (a, b, instrument) in &pairs {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [dir=none, color=gray, label=\"{}\"];\n", a, b, instrument.replace(&prefix, "")));
+        }
+        for (source, target, operation) in &legs {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [color=red, label=\"{}\"];\n", source, target, operation));
+        }
+        dot.push_str("}\n");
+        return dot;
+    }
+
+    // return qty initial per start asset, from the balances when ordering, the fallback if the balance fetch fails
+    fn get_qty_initial_map(&self, arbitrage_ordering: &mut ArbitrageOrdering) -> HashMap<String, f64> {
+        let mut qty_initial_map: HashMap<String, f64> = HashMap::new();
+        for start_asset in &self.start_assets {
+            qty_initial_map.insert(start_asset.clone(), self.qty_in);
+        }
+
+        // arbitrage ordering
+        if self.ordering {
+            // update balances
+            if !arbitrage_ordering.update_balances_with_timeout(self.balance_fetch_timeout) {
+                if let Some(fallback_qty_initial) = self.fallback_qty_initial {
+                    warn!("arbitrage_executor - balance fetch failed, using fallback. qty_initial:{}", fallback_qty_initial);
+                    for start_asset in &self.start_assets {
+                        qty_initial_map.insert(start_asset.clone(), fallback_qty_initial);
+                    }
+                }
+            }
+
+            // balance
+            for start_asset in &self.start_assets {
+                let balance = arbitrage_ordering.get_balance(start_asset);
+                match balance {
+                    Some(b) => {
+                        if b > 0.0 {
+                            qty_initial_map.insert(start_asset.clone(), qty_from_balance(b, self.leg_count));
+                        }
+                    }
+                    None => ()
+                }
+            }
+        }
+        return qty_initial_map;
+    }
+
+    // start arbitrage
+    pub fn start(&mut self) {
+        // receiver
+        info!("arbitrage_executor - starting.");
+
+        // arbitrage profit sender
+        let arbitrage_profit_sender = self.arbitrage_profit_sender.clone();
+
+        // arbitrage ordering
+        let mut arbitrage_ordering: ArbitrageOrdering = match self.arbitrage_ordering.take() {
+            Some(o) => o,
+            None => {
+                error!("arbitrage_executor - already started.");
+                return;
+            }
+        };
+
+        // balances of the ordering, sizing the cycles
+        let balances = arbitrage_ordering.get_balances();
+
+        // qty initial per start asset
+        let qty_initial_map = self.get_qty_initial_map(&mut arbitrage_ordering);
+
+        // executions persistence, linked to the run configuration
+        if self.profit_persistence {
+            let run = RunRecord {
+                run_id: Uuid::new_v4(),
+                exchange: self.exchange.clone(),
+                start_assets: self.start_assets.clone(),
+                symbols_count: self.symbol_list.len(),
+                leg_count: self.leg_count,
+                qty_in: self.qty_in,
+                profit_threshold: self.live_config.read().unwrap().profit_threshold,
+                ordering: self.ordering,
+                mode: format!("{:?}", arbitrage_ordering.get_mode()),
+                create_at: self.clock.now(),
+            };
+            self.profit_sink.add_run(&run);
+            info!("arbitrage_executor - run recorded. run_id:{}", run.run_id);
+            arbitrage_ordering.set_run_id(Some(run.run_id));
+            arbitrage_ordering.set_profit_sink(Some(Arc::clone(&self.profit_sink)));
+        }
+
+        // start arbitrage ordering
+        arbitrage_ordering.set_shutdown(Arc::clone(&self.shutdown_requested));
+        if self.ordering {
+            if let Some(balance_refresh_handle) = arbitrage_ordering.start_balance_refresh() {
+                self.thread_handles.push(balance_refresh_handle);
+            }
+        }
+        self.thread_handles.push(thread::spawn(move || {
+            arbitrage_ordering.start();
+        }));
+
+        // profit database writer
+        let (profit_database_sender, profit_database_receiver): (crossbeam_channel::Sender<ArbitrageProfit>, crossbeam_channel::Receiver<ArbitrageProfit>) = crossbeam_channel::unbounded();
+        // joined after the cycle threads, so the profits they sent are written before the last bucket is flushed
+        let mut profit_writer_handle: Option<JoinHandle<()>> = None;
+        if self.profit_persistence {
+            let c_profit_sink = Arc::clone(&self.profit_sink);
+            let c_shutdown = Arc::clone(&self.shutdown_requested);
+            let mut latency_bucket = LatencyBucket::new((self.latency_bucket.as_secs() as i64).max(1));
+            profit_writer_handle = Some(thread::spawn(move || {
+                info!("arbitrage_executor - profit database writer started.");
+                loop {
+                    match profit_database_receiver.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+                        Ok(p) => {
+                            c_profit_sink.add_profit(&p);
+                            add_latency(&c_profit_sink, latency_bucket.record(&p));
+                        }
+                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                            if c_shutdown.load(atomic::Ordering::SeqCst) {
+                                break;
+                            }
+                        }
+                        Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+
+                // last bucket
+                add_latency(&c_profit_sink, latency_bucket.flush());
+                info!("arbitrage_executor - profit database writer stopped.");
+            }));
+        }
+
+
+        // arbitrage executor
+        for (start_asset, qty_initial) in &qty_initial_map {
+            info!("arbitrage_executor - initial balance. asset:{}, balance:{}", start_asset, qty_initial);
+        }
+
+        // cycle threads, a thread with an invalid arbitrage leaves the count
+        self.cycle_threads.store(self.transactions_list.len(), atomic::Ordering::SeqCst);
+
+        // warm up
+        let start_date = self.clock.now();
+        info!("arbitrage_executor - warm up. duration:{:?}", self.warm_up_duration);
+
+        // heartbeat
+        if let Some(heartbeat_handle) = self.start_heartbeat(start_date) {
+            self.thread_handles.push(heartbeat_handle);
+        }
+
+        // transactions
+        for transactions in &self.transactions_list {
+
+            // qty in from the cycle start asset balance
+            let start_asset = transactions[0].get("source").unwrap().replace(&format!("{}_", self.exchange), "");
+            let c_qty_in: f64 = qty_initial_map.get(&start_asset).cloned().unwrap_or(self.qty_in);
+            let c_start_asset = start_asset.clone();
+            let c_leg_count = transactions.len();
+            let c_dynamic_qty_in = self.dynamic_qty_in;
+            let c_balances = Arc::clone(&balances);
+
+            // arbitrage profit sender clone
+            let c_arbitrage_profit_sender = arbitrage_profit_sender.clone();
+
+            // profit database sender clone
+            let c_profit_database_sender = profit_database_sender.clone();
+            let c_profit_persistence = self.profit_persistence.clone();
+
+            // scale
+            let scale = true;
+
+            // transactions clone
+            let c_transactions = transactions.clone();
+
+            // market bbo bus receiver
+            let mut market_bbo_receiver = self.market_bbo_bus.add_rx();
+
+            // live config
+            let c_live_config = Arc::clone(&self.live_config);
+
+            // suspended cycles
+            let c_suspended_cycles = Arc::clone(&self.suspended_cycles);
+
+            // validation sender
+            let c_validation_sender = self.validation_sender.clone();
+
+            // readiness
+            let c_readiness = Arc::clone(&self.readiness);
+
+            // evaluation stats
+            let c_evaluation_stats = Arc::clone(&self.evaluation_stats);
+
+            // shadow
+            let c_shadow_mode = self.shadow_mode;
+            let c_shadow_totals = Arc::clone(&self.shadow_totals);
+
+            // warm up duration
+            let c_warm_up_duration = self.warm_up_duration.clone();
+
+            // clock
+            let c_clock = Arc::clone(&self.clock);
+
+            // transaction config
+            let c_transaction_config = self.transaction_config.clone();
+
+            // max leg distance
+            let c_max_leg_distance_ms = self.max_leg_distance_ms;
+
+            // max fee ratio
+            let c_max_fee_ratio = self.max_fee_ratio;
+
+            // adverse fill reserve
+            let c_adverse_fill_reserve = self.adverse_fill_reserve.clone();
+
+            // binding leg deferral
+            let c_binding_leg_deferral = self.binding_leg_deferral;
+
+            // consecutive profitable ticks
+            let c_min_consecutive_ticks = self.min_consecutive_ticks;
+
+            // tick coalescing
+            let c_tick_coalescing_window = self.tick_coalescing_window;
+
+            // last prices
+            let c_last_prices = Arc::clone(&self.last_prices);
+
+            // replay
+            let c_profit_tap = Arc::clone(&self.profit_tap);
+            let c_processed_ticks = Arc::clone(&self.processed_ticks);
+            let c_cycle_threads = Arc::clone(&self.cycle_threads);
+
+            // shutdown
+            let c_shutdown_requested = Arc::clone(&self.shutdown_requested);
+
+            // opportunities
+            let c_opportunities = Arc::clone(&self.opportunities);
+
+            // arbitrage profit thread
+            let cycle_handle = thread::spawn(move || {
+
+                // arbitrage
+                let mut arbitrage = match Arbitrage::from_transaction_list(&c_transactions) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        error!("arbitrage_executor - invalid arbitrage. error:{}", e);
+                        c_cycle_threads.fetch_sub(1, atomic::Ordering::SeqCst);
+                        return;
+                    }
+                };
+                arbitrage.set_clock(Arc::clone(&c_clock));
+                arbitrage.set_transaction_config(&c_transaction_config);
+                arbitrage.set_validation_sender(c_validation_sender.clone());
+                arbitrage.set_max_leg_distance_ms(c_max_leg_distance_ms);
+                arbitrage.set_max_fee_ratio(c_max_fee_ratio);
+                arbitrage.set_binding_leg_deferral(c_binding_leg_deferral);
+                info!("arbitrage_executor - arbitrage. name:{}, scale:{}, qty_in:{}", arbitrage.get_name(), scale, c_qty_in);
+
+                // evaluation count
+                let mut evaluation_count: u64 = 0;
+                let mut evaluation_stats = EvaluationStats::default();
+
+                // consecutive profitable ticks
+                let mut consecutive_ticks: u32 = 0;
+
+                // loop
+                while !c_shutdown_requested.load(atomic::Ordering::SeqCst) {
+                    // receive market bbo with its tick ingestion span, waking up to check for shutdown
+                    let (mut market_bbo, mut tick_context) = match market_bbo_receiver.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+                        Ok(tick) => tick,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => break
+                    };
+                    let mut tick_counter = TickCounter::new(Arc::clone(&c_processed_ticks));
+
+                    // coalesce ticks of the arbitrage within the window, earlier ticks only update quotes
+                    if let Some(window) = c_tick_coalescing_window {
+                        if arbitrage.instrument_list.contains(&market_bbo.get_feed()) {
+                            let deadline = Instant::now() + window;
+                            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                                match market_bbo_receiver.recv_timeout(remaining) {
+                                    Ok((next_market_bbo, next_tick_context)) => {
+                                        tick_counter.count += 1;
+                                        if arbitrage.instrument_list.contains(&next_market_bbo.get_feed()) {
+                                            arbitrage.update(&market_bbo);
+                                            market_bbo = next_market_bbo;
+                                            tick_context = next_tick_context;
+                                        }
+                                    }
+                                    Err(_) => break
+                                }
+                            }
+                        }
+                    }
+
+                    // get market bbo feed
+                    let feed = market_bbo.get_feed();
+
+                    // check arbitrage contains feed
+                    if arbitrage.instrument_list.contains(&feed) {
+
+                        // skip blacklisted and suspended arbitrage, shadow mode still evaluates them
+                        let blacklisted = c_live_config.read().unwrap().blacklist.contains(arbitrage.get_name());
+                        let suspended = c_suspended_cycles.read().unwrap().contains(arbitrage.get_name());
+                        if (blacklisted | suspended) & !c_shadow_mode {
+                            continue;
+                        }
+
+                        // update last trade prices
+                        if c_transaction_config.price_source == PriceSource::LAST_TRADE {
+                            let last_prices = c_last_prices.read().unwrap();
+                            for instrument in arbitrage.instrument_list.clone() {
+                                if let Some(last_price) = last_prices.get(&instrument) {
+                                    arbitrage.update_last_price(&instrument, *last_price);
+                                }
+                            }
+                        }
+
+                        // defer ticks on non-binding legs, quotes are still updated
+                        if !arbitrage.should_evaluate(&market_bbo) {
+                            arbitrage.update(&market_bbo);
+                            continue;
+                        }
+
+                        // execute arbitrage
+                        arbitrage.set_profit_threshold(c_live_config.read().unwrap().profit_threshold);
+                        let qty_in = if c_dynamic_qty_in {
+                            match c_balances.read().unwrap().get(&c_start_asset) {
+                                Some(b) if *b > 0.0 => qty_from_balance(*b, c_leg_count),
+                                _ => c_qty_in
+                            }
+                        } else {
+                            c_qty_in
+                        };
+
+                        // shadow, theoretical profit of a skipped arbitrage
+                        if blacklisted | suspended {
+                            if let Some(p) = arbitrage.execute_unfiltered(&market_bbo, qty_in, scale) {
+                                if p.get_profit() > 0.0 {
+                                    c_shadow_totals.lock().unwrap().record(p.get_profit(), false);
+                                }
+                            }
+                            continue;
+                        }
+
+                        let evaluation_span = arbitrage_tracing::start_span(arbitrage_tracing::CYCLE_EVALUATION, &tick_context,
+                                                                            vec![KeyValue::new("cycle", arbitrage.get_name().clone())]);
+                        let evaluation_date = Instant::now();
+                        let unfiltered_profit = arbitrage.execute_unfiltered(&market_bbo, qty_in, scale);
+                        let arbitrage_profit = unfiltered_profit.clone().filter(|p| arbitrage.is_accepted(p));
+                        evaluation_stats.record(evaluation_date.elapsed());
+
+                        // consecutive profitable ticks
+                        let profitable = match &arbitrage_profit {
+                            Some(p) => p.get_profit() > c_live_config.read().unwrap().profit_threshold,
+                            None => false
+                        };
+                        consecutive_ticks = if profitable { consecutive_ticks.saturating_add(1) } else { 0 };
+
+                        // publish readiness
+                        evaluation_count += 1;
+                        if evaluation_count % 100 == 0 {
+                            let readiness_ratio = arbitrage.get_readiness_ratio();
+                            debug!("arbitrage_executor - readiness. name:{}, ratio:{:.3}", arbitrage.get_name(), readiness_ratio);
+                            c_readiness.write().unwrap().insert(arbitrage.get_name().clone(), readiness_ratio);
+                            debug!("arbitrage_executor - evaluation latency. name:{}, latency_us:{:.1}, max_latency_us:{:.1}", arbitrage.get_name(), evaluation_stats.latency_us, evaluation_stats.max_latency_us);
+                            c_evaluation_stats.write().unwrap().insert(arbitrage.get_name().clone(), evaluation_stats.clone());
+                        }
+
+                        let mut sent = false;
+                        match arbitrage_profit {
+                            Some(mut p) => {
+                                if p.get_profit() > 0.0 {
+                                    info!("arbitrage_executor - arbitrage profit. profit:{}, latency:{}(ms)", p, p.get_latency_ms());
+                                    c_opportunities.fetch_add(1, atomic::Ordering::SeqCst);
+
+                                    // persist profit
+                                    if c_profit_persistence {
+                                        if let Err(err) = c_profit_database_sender.send(p.clone()) {
+                                            error!("arbitrage_executor - profit not persisted, database writer gone. name:{}, error:{}", arbitrage.get_name(), err);
+                                        }
+                                    }
+
+                                    // ordering, a replay goes through the same checks without ordering
+                                    let c_ordering = c_live_config.read().unwrap().ordering;
+                                    let replaying = c_profit_tap.read().unwrap().is_some();
+                                    let profit = p.get_profit();
+
+                                    // profit threshold
+                                    let c_profit_threshold = c_live_config.read().unwrap().profit_threshold;
+                                    let reserve = match &c_adverse_fill_reserve {
+                                        Some(adverse_fill_reserve) => adverse_fill_reserve.get_reserve(p.get_qty_in()),
+                                        None => 0.0
+                                    };
+                                    let above_threshold = profit - reserve > c_profit_threshold;
+                                    if !above_threshold {
+                                        info!("arbitrage_executor - profit below threshold, rejected. name:{}, profit:{}, threshold:{}, reserve:{}", arbitrage.get_name(), profit, c_profit_threshold, reserve);
+                                    }
+
+                                    // ordering failures
+                                    let ordering_failures = p.get_ordering_failures();
+                                    if c_ordering & above_threshold & !ordering_failures.is_empty() {
+                                        info!("arbitrage_executor - invalid ordering. name:{}, failures:{:?}", arbitrage.get_name(), ordering_failures);
+                                        arbitrage.send_validation_warning(format!("invalid ordering: {:?}", ordering_failures));
+                                    }
+
+                                    if (c_ordering | replaying) & above_threshold & ordering_failures.is_empty() {
+                                        let elapsed = c_clock.now().signed_duration_since(start_date).to_std().unwrap_or(Duration::from_secs(0));
+                                        if elapsed < c_warm_up_duration {
+                                            info!("arbitrage_executor - warming up, ordering skipped. name:{}", arbitrage.get_name());
+                                        } else if consecutive_ticks < c_min_consecutive_ticks {
+                                            debug!("arbitrage_executor - waiting for consecutive ticks, ordering skipped. name:{}, ticks:{}, min_ticks:{}", arbitrage.get_name(), consecutive_ticks, c_min_consecutive_ticks);
+                                        } else {
+                                            // replay reports the profits the live run would have sent
+                                            if let Some(profit_tap) = c_profit_tap.read().unwrap().as_ref() {
+                                                if let Err(err) = profit_tap.send(p.clone()) {
+                                                    warn!("arbitrage_executor - replay profit not reported. name:{}, error:{}", arbitrage.get_name(), err);
+                                                }
+                                            }
+
+                                            if c_ordering {
+                                                let emission_span = arbitrage_tracing::start_span(arbitrage_tracing::PROFIT_EMISSION, &evaluation_span, arbitrage_tracing::cycle_attributes(&p));
+                                                p.set_trace_context(emission_span.clone());
+                                                if let Err(err) = c_arbitrage_profit_sender.send(p) {
+                                                    error!("arbitrage_executor - profit not sent, ordering gone. name:{}, error:{}", arbitrage.get_name(), err);
+                                                }
+                                                arbitrage_tracing::end_span(&emission_span);
+                                                sent = true;
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            None => {}
+                        }
+
+                        // shadow, theoretical profit before the leg distance and fee filters
+                        if c_shadow_mode {
+                            if let Some(p) = &unfiltered_profit {
+                                if p.get_profit() > 0.0 {
+                                    c_shadow_totals.lock().unwrap().record(p.get_profit(), sent);
+                                }
+                            }
+                        }
+                        arbitrage_tracing::end_span(&evaluation_span);
+                    }
+                }
+                info!("arbitrage_executor - arbitrage stopped. name:{}", arbitrage.get_name());
+            });
+            self.thread_handles.push(cycle_handle);
+        }
+
+        // profit database writer
+        if let Some(profit_writer_handle) = profit_writer_handle {
+            self.thread_handles.push(profit_writer_handle);
+        }
+    }
+
+
+    // update last trade price
+    pub fn execute_trade(&mut self, instrument: String, last_price: f64) {
+        self.last_prices.write().unwrap().insert(instrument, last_price);
+    }
+
+    // replay recorded ticks through the bus without ordering, returning the profits passing the threshold,
+    // reserve, ordering and warm-up checks the live run sends for ordering
+    // start must have been called, ideally with ordering off so no balances are fetched
+    pub fn replay(&mut self, ticks: impl Iterator<Item = MarketBBO>) -> Vec<ArbitrageProfit> {
+        if self.arbitrage_ordering.is_some() {
+            error!("arbitrage_executor - replay before start.");
+            return Vec::new();
+        }
+
+        // no ordering while replaying
+        let ordering = self.live_config.read().unwrap().ordering;
+        self.live_config.write().unwrap().ordering = false;
+
+        // profit tap
+        let (profit_sender, profit_receiver) = crossbeam_channel::unbounded();
+        *self.profit_tap.write().unwrap() = Some(profit_sender);
+        self.processed_ticks.store(0, atomic::Ordering::SeqCst);
+
+        // pump ticks
+        let mut tick_count: u64 = 0;
+        for market_bbo in ticks {
+            if self.broadcast(market_bbo) {
+                tick_count += 1;
+            }
+        }
+
+        // wait for every cycle thread to handle every tick, giving up without progress
+        let mut processed = self.processed_ticks.load(atomic::Ordering::SeqCst);
+        let mut progress_date = Instant::now();
+        while processed < tick_count * self.cycle_threads.load(atomic::Ordering::SeqCst) as u64 {
+            if progress_date.elapsed() > REPLAY_IDLE_TIMEOUT {
+                warn!("arbitrage_executor - replay stalled. processed:{}, ticks:{}", processed, tick_count);
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+            let current = self.processed_ticks.load(atomic::Ordering::SeqCst);
+            if current > processed {
+                processed = current;
+                progress_date = Instant::now();
+            }
+        }
+
+        // restore
+        *self.profit_tap.write().unwrap() = None;
+        self.live_config.write().unwrap().ordering = ordering;
+
+        let profits: Vec<ArbitrageProfit> = profit_receiver.try_iter().collect();
+        info!("arbitrage_executor - replay finished. ticks:{}, profits:{}", tick_count, profits.len());
+        return profits;
+    }
+
+    // execute arbitrage
+    pub fn execute(&mut self, market_bbo: MarketBBO) {
+        self.broadcast(market_bbo);
+    }
+
+    // update quote cache and broadcast tick to the cycle threads, false if skipped as duplicate
+    fn broadcast(&mut self, market_bbo: MarketBBO) -> bool {
+        // skip duplicate tick
+        if self.tick_deduplication {
+            let tick_hash = ArbitrageExecutor::get_tick_hash(&market_bbo);
+            let instrument = market_bbo.get_instrument().to_string();
+            if self.tick_hashes.get(&instrument) == Some(&tick_hash) {
+                trace!("arbitrage_executor - duplicate tick skipped. instrument:{}", instrument);
+                return false;
+            }
+            self.tick_hashes.insert(instrument, tick_hash);
+        }
+
+        self.received_ticks.fetch_add(1, atomic::Ordering::SeqCst);
+
+        // tick ingestion span, a child of the caller span and the parent of every cycle evaluation of the tick
+        let tick_span = arbitrage_tracing::start_span(arbitrage_tracing::TICK_INGESTION, &Context::current(),
+                                                      vec![KeyValue::new("instrument", market_bbo.get_instrument().to_string())]);
+
+        // update quote cache
+        if self.quote_cache_enabled {
+            match self.quote_cache.write() {
+                Ok(mut quote_cache) => {
+                    quote_cache.insert(market_bbo.get_instrument().to_string(), market_bbo.clone());
+                }
+                Err(err) => {
+                    error!("arbitrage_executor - quote cache error. error: {}", err);
+                }
+            }
+        }
+
+        // broadcast, blocking while a slow receiver keeps the bus full
+        if let Err(tick) = self.market_bbo_bus.try_broadcast((market_bbo, tick_span.clone())) {
+            warn!("arbitrage_executor - market bbo bus full, waiting for receivers.");
+            self.market_bbo_bus.broadcast(tick);
+        }
+        arbitrage_tracing::end_span(&tick_span);
+        return true;
+    }
+}
+
+// Tick Counter, adds the ticks received by a cycle thread to the processed count once their evaluation is done
+struct TickCounter {
+    processed_ticks: Arc<AtomicU64>,
+    count: u64,
+}
+
+impl TickCounter {
+    fn new(processed_ticks: Arc<AtomicU64>) -> TickCounter {
+        return TickCounter {
+            processed_ticks: processed_ticks,
+            count: 1,
+        };
+    }
+}
+
+impl Drop for TickCounter {
+    fn drop(&mut self) {
+        self.processed_ticks.fetch_add(self.count, atomic::Ordering::SeqCst);
+    }
+}
+
+// return cycle input sized from the start asset balance, split evenly over the cycle legs
+fn qty_from_balance(balance: f64, leg_count: usize) -> f64 {
+    return balance / leg_count as f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::izyfo_arbitrage::arbitrage_clock::MockClock;
+    use crate::izyfo_arbitrage::arbitrage_testing::{self, MemoryProfitSink, MockExchange};
+    use opentelemetry::global;
+    use opentelemetry::sdk::export::trace::SpanData;
+    use opentelemetry::sdk::trace::TracerProvider;
+    use opentelemetry::testing::trace::new_test_exporter;
+    use opentelemetry::trace::{SpanId, TraceContextExt};
+
+    // executor on the triangle, its profits sent to ordering arrive on the receiver
+    fn triangle_executor(ordering: bool) -> (ArbitrageExecutor, Arc<MemoryProfitSink>, crossbeam_channel::Receiver<ArbitrageProfit>) {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, profit_sink, arbitrage_profit_receiver) =
+            arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, ordering);
+        arbitrage_executor.transactions_list.push(arbitrage_testing::triangle());
+        return (arbitrage_executor, profit_sink, arbitrage_profit_receiver);
+    }
+
+    // instruments of the triangle
+    fn triangle_instruments() -> Vec<String> {
+        return vec!["BINANCE_ETH_BTC".to_string(), "BINANCE_ETH_USDT".to_string(), "BINANCE_BTC_USDT".to_string()];
+    }
+
+    // return cycle names, in list order
+    fn cycle_names(arbitrage_executor: &ArbitrageExecutor) -> Vec<String> {
+        return arbitrage_executor.transactions_list.iter().map(|transactions| Arbitrage::from_transaction_list(transactions).unwrap().get_name().clone()).collect();
+    }
+
+    // ticks quoting every leg of the triangle, the last one completes it
+    fn triangle_ticks() -> Vec<MarketBBO> {
+        return arbitrage_testing::triangle_quotes().iter().map(|quote| quote.build()).collect();
+    }
+
+    // broadcast ticks and wait until every cycle thread handled them
+    fn pump(arbitrage_executor: &mut ArbitrageExecutor, ticks: Vec<MarketBBO>) {
+        let cycle_threads = arbitrage_executor.cycle_threads.load(atomic::Ordering::SeqCst) as u64;
+        let expected = arbitrage_executor.processed_ticks.load(atomic::Ordering::SeqCst) + ticks.len() as u64 * cycle_threads;
+        for market_bbo in ticks {
+            arbitrage_executor.broadcast(market_bbo);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (arbitrage_executor.processed_ticks.load(atomic::Ordering::SeqCst) < expected) & (Instant::now() < deadline) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(arbitrage_executor.processed_ticks.load(atomic::Ordering::SeqCst) >= expected, "ticks not processed");
+    }
+
+    #[test]
+    fn profits_during_warm_up_are_not_sent() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_warm_up_duration(Duration::from_secs(3600));
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(arbitrage_executor.opportunities.load(atomic::Ordering::SeqCst), 1);
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn profits_after_warm_up_are_sent() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(arbitrage_profit_receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn cycles_are_built_for_every_start_asset() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC", "USDT"], &["BTC", "ETH", "USDT"], 1.0, 0.0, false);
+
+        arbitrage_executor.build_transactions_list(&triangle_instruments(), &triangle_instruments());
+
+        let start_assets: Vec<String> = arbitrage_executor.transactions_list.iter().map(|transactions| transactions[0].get("source").unwrap().clone()).collect();
+        assert_eq!(start_assets.len(), 4);
+        assert_eq!(start_assets.iter().filter(|source| *source == "BINANCE_BTC").count(), 2);
+        assert_eq!(start_assets.iter().filter(|source| *source == "BINANCE_USDT").count(), 2);
+        for transactions in &arbitrage_executor.transactions_list {
+            assert_eq!(transactions[0].get("source"), transactions[2].get("target"));
+        }
+    }
+
+    #[test]
+    fn cycles_are_sorted_by_instrument_priority() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT", "BNB"], 1.0, 0.0, false);
+        let mut instrument_list = triangle_instruments();
+        instrument_list.push("BINANCE_BNB_BTC".to_string());
+        instrument_list.push("BINANCE_BNB_USDT".to_string());
+        let mut instrument_priority: HashMap<String, f64> = HashMap::new();
+        instrument_priority.insert("BINANCE_BNB_BTC".to_string(), 500.0);
+        instrument_priority.insert("BINANCE_BNB_USDT".to_string(), 500.0);
+        instrument_priority.insert("BINANCE_BTC_USDT".to_string(), 500.0);
+        instrument_priority.insert("BINANCE_ETH_BTC".to_string(), 1.0);
+        arbitrage_executor.set_instrument_priority(instrument_priority);
+
+        arbitrage_executor.build_transactions_list(&instrument_list, &instrument_list);
+
+        let names = cycle_names(&arbitrage_executor);
+        assert_eq!(names.len(), 4);
+        assert!(names[0].contains("BNB") & names[1].contains("BNB"));
+        assert!(names[2].contains("ETH") & names[3].contains("ETH"));
+    }
+
+    #[test]
+    fn quote_pushed_through_executor_is_visible_to_ordering() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+
+        arbitrage_executor.execute(arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 10.0, 0.05, 12.0));
+
+        let arbitrage_ordering = arbitrage_executor.get_arbitrage_ordering().unwrap();
+        let quote = arbitrage_ordering.get_quote(&"BINANCE_ETH_BTC".to_string()).unwrap();
+        assert_eq!(quote.get_ask_price(), 0.05);
+        assert_eq!(quote.get_ask_qty(), 12.0);
+        assert!(arbitrage_ordering.get_quote(&"BINANCE_ETH_USDT".to_string()).is_none());
+    }
+
+    #[test]
+    fn quote_cache_disabled_leaves_ordering_without_quotes() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_quote_cache_enabled(false);
+
+        arbitrage_executor.execute(arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 10.0, 0.05, 12.0));
+
+        assert!(arbitrage_executor.get_arbitrage_ordering().unwrap().get_quote(&"BINANCE_ETH_BTC".to_string()).is_none());
+    }
+
+    #[test]
+    fn repeated_identical_tick_is_skipped() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_tick_deduplication(true);
+
+        assert!(arbitrage_executor.broadcast(arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 10.0, 0.05, 12.0)));
+        assert!(!arbitrage_executor.broadcast(arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 10.0, 0.05, 12.0)));
+        assert!(arbitrage_executor.broadcast(arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 10.0, 0.05, 11.0)));
+
+        assert_eq!(arbitrage_executor.received_ticks.load(atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn referencedata_outage_falls_back_to_persisted_cache() {
+        let cache_path = std::env::temp_dir().join(format!("izyfo-referencedata-{}.json", Uuid::new_v4())).to_string_lossy().to_string();
+
+        // a previous run fetched referencedata
+        let (mut previous_executor, _, _) = triangle_executor(false);
+        previous_executor.set_referencedata_cache_path(Some(cache_path.clone()));
+        assert_eq!(previous_executor.fetch_instrument_list_with_fallback(triangle_instruments), triangle_instruments());
+
+        // this run starts during an outage
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_referencedata_cache_path(Some(cache_path.clone()));
+        arbitrage_executor.set_referencedata_retry(2, Duration::from_millis(0));
+        let mut attempts = 0;
+
+        let instrument_list = arbitrage_executor.fetch_instrument_list_with_fallback(|| {
+            attempts += 1;
+            Vec::new()
+        });
+
+        assert_eq!(attempts, 3);
+        assert_eq!(instrument_list, triangle_instruments());
+        fs::remove_file(&cache_path).unwrap();
+    }
+
+    #[test]
+    fn referencedata_outage_without_cache_builds_no_cycles() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_referencedata_retry(0, Duration::from_millis(0));
+
+        assert!(arbitrage_executor.fetch_instrument_list_with_fallback(Vec::new).is_empty());
+    }
+
+    #[test]
+    fn detection_does_not_wait_for_a_slow_profit_database() {
+        let (mut arbitrage_executor, profit_sink, _) = triangle_executor(false);
+        *profit_sink.profit_delay.lock().unwrap() = Duration::from_millis(200);
+        arbitrage_executor.set_profit_persistence(true);
+        arbitrage_executor.start();
+
+        // 13 profits, 2.6s of database writes if persisted inline
+        let start_date = Instant::now();
+        for _ in 0..5 {
+            pump(&mut arbitrage_executor, triangle_ticks());
+        }
+        let elapsed = start_date.elapsed();
+
+        assert_eq!(arbitrage_executor.opportunities.load(atomic::Ordering::SeqCst), 13);
+        assert!(elapsed < Duration::from_secs(1), "detection blocked on the database. elapsed: {:?}", elapsed);
+        assert!(profit_sink.profits.lock().unwrap().len() < 13);
+        arbitrage_executor.shutdown();
+    }
+
+    #[test]
+    fn dot_export_has_triangle_nodes_and_edges() {
+        let (arbitrage_executor, _, _) = triangle_executor(false);
+
+        let dot = arbitrage_executor.export_dot();
+
+        assert!(dot.starts_with("digraph arbitrage {\n"));
+        assert!(dot.contains("    \"BTC\" [shape=doublecircle];\n"));
+        assert!(dot.contains("    \"ETH\";\n"));
+        assert!(dot.contains("    \"USDT\";\n"));
+        assert!(dot.contains("    \"BTC\" -> \"ETH\" [dir=none, color=gray, label=\"ETH_BTC\"];\n"));
+        assert!(dot.contains("    \"BTC\" -> \"ETH\" [color=red, label=\"BUY\"];\n"));
+        assert!(dot.contains("    \"ETH\" -> \"USDT\" [color=red, label=\"SELL\"];\n"));
+        assert!(dot.contains("    \"USDT\" -> \"BTC\" [color=red, label=\"BUY\"];\n"));
+        assert_eq!(dot.matches("color=gray").count(), 3);
+        assert_eq!(dot.matches("color=red").count(), 3);
+    }
+
+    #[test]
+    fn leg_order_type_overrides_the_default() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, false);
+        arbitrage_executor.set_leg_order_type(2, OrderType::MARKET);
+
+        arbitrage_executor.build_transactions_list(&triangle_instruments(), &triangle_instruments());
+
+        for transactions in &arbitrage_executor.transactions_list {
+            let order_types: Vec<&str> = transactions.iter().map(|transaction| transaction.get("order_type").unwrap().as_str()).collect();
+            assert_eq!(order_types, vec!["LIMIT", "LIMIT", "MARKET"]);
+        }
+    }
+
+    // triangle, its rotation from ETH and its reverse
+    fn snapshot_cycles() -> Vec<Vec<HashMap<String, String>>> {
+        let rotation = vec![
+            arbitrage_testing::leg("ETH", "USDT", "SELL", "ETH", "USDT"),
+            arbitrage_testing::leg("USDT", "BTC", "BUY", "BTC", "USDT"),
+            arbitrage_testing::leg("BTC", "ETH", "BUY", "ETH", "BTC"),
+        ];
+        let reverse = vec![
+            arbitrage_testing::leg("BTC", "USDT", "SELL", "BTC", "USDT"),
+            arbitrage_testing::leg("USDT", "ETH", "BUY", "ETH", "USDT"),
+            arbitrage_testing::leg("ETH", "BTC", "SELL", "ETH", "BTC"),
+        ];
+        return vec![arbitrage_testing::triangle(), rotation, reverse];
+    }
+
+    // quote snapshot of the triangle
+    fn triangle_snapshot() -> HashMap<String, MarketBBO> {
+        return arbitrage_testing::triangle_quotes().iter().map(|quote| (quote.instrument.clone(), quote.build())).collect();
+    }
+
+    // return profit names, in evaluation order
+    fn profit_names(profits: &[ArbitrageProfit]) -> Vec<String> {
+        return profits.iter().map(|p| p.get_name().clone()).collect();
+    }
+
+    #[test]
+    fn parallel_snapshot_evaluation_equals_serial() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, false);
+        for _ in 0..8 {
+            arbitrage_executor.transactions_list.extend(snapshot_cycles());
+        }
+        let snapshot = triangle_snapshot();
+
+        let serial = arbitrage_executor.evaluate_snapshot(&snapshot);
+        arbitrage_executor.set_evaluation_threads(4).unwrap();
+        let parallel = arbitrage_executor.evaluate_snapshot(&snapshot);
+
+        assert!(!serial.is_empty());
+        assert_eq!(profit_names(&parallel), profit_names(&serial));
+        for (p, s) in parallel.iter().zip(serial.iter()) {
+            assert_eq!(p.get_profit(), s.get_profit());
+        }
+    }
+
+    #[test]
+    fn snapshot_evaluation_skips_blacklisted_and_suspended_cycles() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, false);
+        arbitrage_executor.transactions_list.extend(snapshot_cycles());
+        arbitrage_executor.set_evaluation_threads(2).unwrap();
+        let names = cycle_names(&arbitrage_executor);
+        arbitrage_executor.live_config.write().unwrap().blacklist.push(names[0].clone());
+        arbitrage_executor.set_cycle_suspended(&names[1], true);
+
+        let profits = arbitrage_executor.evaluate_snapshot(&triangle_snapshot());
+
+        let evaluated = profit_names(&profits);
+        assert!(!evaluated.contains(&names[0]));
+        assert!(!evaluated.contains(&names[1]));
+    }
+
+    #[test]
+    fn hanging_balance_fetch_falls_back_after_the_timeout() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.set_account_delay(Duration::from_secs(2));
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 3.0)])));
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, true);
+        arbitrage_executor.set_balance_fetch_timeout(Duration::from_millis(50), Some(0.5));
+        let mut arbitrage_ordering = arbitrage_executor.arbitrage_ordering.take().unwrap();
+
+        let start_date = Instant::now();
+        let qty_initial_map = arbitrage_executor.get_qty_initial_map(&mut arbitrage_ordering);
+
+        assert!(start_date.elapsed() < Duration::from_secs(1));
+        assert_eq!(qty_initial_map.get("BTC"), Some(&0.5));
+    }
+
+    #[test]
+    fn fetched_balance_sizes_the_qty_initial() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 3.0)])));
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, 0.0, true);
+        arbitrage_executor.set_balance_fetch_timeout(Duration::from_secs(1), Some(0.5));
+        let mut arbitrage_ordering = arbitrage_executor.arbitrage_ordering.take().unwrap();
+
+        let qty_initial_map = arbitrage_executor.get_qty_initial_map(&mut arbitrage_ordering);
+
+        assert_eq!(qty_initial_map.get("BTC"), Some(&1.0));
+    }
+
+    #[test]
+    fn fewer_cycles_than_min_cycles_is_an_error() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_min_cycles(2);
+
+        assert_eq!(arbitrage_executor.check_min_cycles(), Err("too few arbitrage: '1' < '2'".to_string()));
+    }
+
+    #[test]
+    fn enough_cycles_pass_min_cycles() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        arbitrage_executor.set_min_cycles(1);
+
+        assert_eq!(arbitrage_executor.check_min_cycles(), Ok(1));
+    }
+
+    // write a live config file, returning its path
+    fn write_live_config(contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("izyfo-live-config-{}.conf", Uuid::new_v4())).to_string_lossy().to_string();
+        fs::write(&path, contents).unwrap();
+        return path;
+    }
+
+    #[test]
+    fn reload_applies_a_new_blacklist_entry() {
+        let (arbitrage_executor, _, _) = triangle_executor(false);
+        let path = write_live_config("# live config\nblacklist = ETH_BTC, BTC_USDT\n");
+
+        let config = arbitrage_executor.reload_config(&path).unwrap();
+
+        assert_eq!(config.blacklist, vec!["ETH_BTC".to_string(), "BTC_USDT".to_string()]);
+        assert_eq!(arbitrage_executor.live_config.read().unwrap().blacklist, config.blacklist);
+        assert_eq!(config.profit_threshold, 0.0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sighup_reloads_the_blacklist() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        let path = write_live_config("blacklist = ETH_BTC\n");
+        arbitrage_executor.start_config_reload_on_sighup(path.clone()).unwrap();
+
+        signal_hook::low_level::raise(SIGHUP).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while arbitrage_executor.live_config.read().unwrap().blacklist.is_empty() & (Instant::now() < deadline) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(arbitrage_executor.live_config.read().unwrap().blacklist, vec!["ETH_BTC".to_string()]);
+        arbitrage_executor.shutdown();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn all_assets_is_the_union_of_cycle_assets() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT", "BNB"], 1.0, 0.0, false);
+        arbitrage_executor.transactions_list.push(arbitrage_testing::triangle());
+        arbitrage_executor.transactions_list.push(vec![
+            arbitrage_testing::leg("BTC", "BNB", "BUY", "BNB", "BTC"),
+            arbitrage_testing::leg("BNB", "USDT", "SELL", "BNB", "USDT"),
+            arbitrage_testing::leg("USDT", "BTC", "BUY", "BTC", "USDT"),
+        ]);
+
+        assert_eq!(arbitrage_executor.all_assets(), vec!["BNB".to_string(), "BTC".to_string(), "ETH".to_string(), "USDT".to_string()]);
+    }
+
+    // executor without cycles on a market bbo bus of the capacity
+    fn executor_with_bus_capacity(bus_capacity: usize) -> ArbitrageExecutor {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_ordering = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL).0;
+        let (arbitrage_profit_sender, _) = crossbeam_channel::unbounded();
+        return ArbitrageExecutor::with_ordering("BINANCE".to_string(), &vec!["BTC".to_string()], &vec!["BTC".to_string(), "ETH".to_string(), "USDT".to_string()], 1.0, 0.0, false,
+                                                bus_capacity, Arc::new(MemoryProfitSink::default()) as Arc<dyn ProfitSink>, arbitrage_ordering, arbitrage_profit_sender, 3);
+    }
+
+    #[test]
+    fn configured_bus_capacity_is_applied() {
+        let mut arbitrage_executor = executor_with_bus_capacity(2);
+        let _market_bbo_receiver = arbitrage_executor.market_bbo_bus.add_rx();
+        let mut ticks = triangle_ticks();
+
+        assert!(arbitrage_executor.market_bbo_bus.try_broadcast((ticks.remove(0), Context::new())).is_ok());
+        assert!(arbitrage_executor.market_bbo_bus.try_broadcast((ticks.remove(0), Context::new())).is_ok());
+        assert!(arbitrage_executor.market_bbo_bus.try_broadcast((ticks.remove(0), Context::new())).is_err());
+    }
+
+    #[test]
+    fn broadcast_beyond_capacity_waits_for_a_slow_consumer() {
+        let mut arbitrage_executor = executor_with_bus_capacity(2);
+        let mut market_bbo_receiver = arbitrage_executor.market_bbo_bus.add_rx();
+        let consumer_handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            let mut received = Vec::new();
+            while let Ok((market_bbo, _)) = market_bbo_receiver.recv_timeout(Duration::from_millis(500)) {
+                received.push(market_bbo.get_instrument().to_string());
+            }
+            received
+        });
+
+        let start_date = Instant::now();
+        for market_bbo in triangle_ticks() {
+            assert!(arbitrage_executor.broadcast(market_bbo));
+        }
+
+        // the third tick waits until the consumer frees a slot, no tick is dropped
+        assert!(start_date.elapsed() >= Duration::from_millis(150));
+        assert_eq!(consumer_handle.join().unwrap(), triangle_instruments());
+    }
+
+    #[test]
+    fn shadow_mode_records_a_blacklisted_opportunity_as_skipped() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_shadow_mode(true);
+        let names = cycle_names(&arbitrage_executor);
+        arbitrage_executor.live_config.write().unwrap().blacklist.push(names[0].clone());
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        let shadow_totals = arbitrage_executor.get_shadow_totals();
+        assert_eq!(shadow_totals.opportunities, 1);
+        assert_eq!(shadow_totals.skipped, 1);
+        assert!(shadow_totals.skipped_profit > 0.0);
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn shadow_mode_records_a_filtered_opportunity_as_skipped() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_shadow_mode(true);
+        arbitrage_executor.set_max_fee_ratio(Some(0.0));
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        let shadow_totals = arbitrage_executor.get_shadow_totals();
+        assert_eq!(shadow_totals.opportunities, 1);
+        assert_eq!(shadow_totals.skipped, 1);
+        assert_eq!(arbitrage_executor.opportunities.load(atomic::Ordering::SeqCst), 0);
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn shadow_mode_records_a_sent_opportunity() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_shadow_mode(true);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        let shadow_totals = arbitrage_executor.get_shadow_totals();
+        assert_eq!(shadow_totals.sent, 1);
+        assert_eq!(shadow_totals.skipped, 0);
+        assert_eq!(arbitrage_profit_receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn balance_change_resizes_the_next_cycle() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_dynamic_qty_in(true);
+        let balances = arbitrage_executor.arbitrage_ordering.as_ref().unwrap().get_balances();
+        arbitrage_executor.start();
+
+        balances.write().unwrap().insert("BTC".to_string(), 1.5);
+        pump(&mut arbitrage_executor, triangle_ticks());
+        let first_qty_in = arbitrage_profit_receiver.recv_timeout(Duration::from_secs(1)).unwrap().get_qty_in();
+
+        balances.write().unwrap().insert("BTC".to_string(), 3.0);
+        pump(&mut arbitrage_executor, triangle_ticks());
+        let second_qty_in = arbitrage_profit_receiver.recv_timeout(Duration::from_secs(1)).unwrap().get_qty_in();
+        arbitrage_executor.shutdown();
+
+        assert!((first_qty_in - 0.5).abs() < 1e-9);
+        assert!((second_qty_in - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leg_count_builds_four_leg_cycles() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, _) = arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "BNB", "USDT"], 1.0, 0.0, false);
+        arbitrage_executor.leg_count = 4;
+        let instruments = vec!["BINANCE_ETH_BTC".to_string(), "BINANCE_BNB_ETH".to_string(), "BINANCE_BNB_USDT".to_string(), "BINANCE_BTC_USDT".to_string()];
+
+        arbitrage_executor.build_transactions_list(&instruments, &instruments);
+
+        // the square both ways round
+        assert_eq!(arbitrage_executor.transactions_list.len(), 2);
+        for transactions in &arbitrage_executor.transactions_list {
+            assert_eq!(transactions.len(), 4);
+            assert_eq!(transactions[0].get("source"), transactions[3].get("target"));
+            assert!(Arbitrage::from_transaction_list(transactions).is_ok());
+        }
+    }
+
+    #[test]
+    fn balance_is_split_over_the_cycle_legs() {
+        assert_eq!(qty_from_balance(3.0, 3), 1.0);
+        assert_eq!(qty_from_balance(4.0, 4), 1.0);
+    }
+
+    // executor on the triangle with the profit threshold, its sent profits on the receiver
+    fn triangle_executor_with_threshold(profit_threshold: f64) -> (ArbitrageExecutor, crossbeam_channel::Receiver<ArbitrageProfit>) {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) =
+            arbitrage_testing::mock_executor(&exchange, &["BTC"], &["BTC", "ETH", "USDT"], 1.0, profit_threshold, true);
+        arbitrage_executor.transactions_list.push(arbitrage_testing::triangle());
+        return (arbitrage_executor, arbitrage_profit_receiver);
+    }
+
+    #[test]
+    fn profit_below_the_threshold_is_not_sent() {
+        // the triangle makes about 0.05 BTC on 1 BTC
+        let (mut arbitrage_executor, arbitrage_profit_receiver) = triangle_executor_with_threshold(0.5);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn profit_above_the_threshold_is_sent() {
+        let (mut arbitrage_executor, arbitrage_profit_receiver) = triangle_executor_with_threshold(0.01);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(arbitrage_profit_receiver.try_iter().count(), 1);
+    }
+
+    // triangle profits sent above a 0.01 BTC threshold once the reserve is deducted
+    fn sent_with_reserve(adverse_fill_reserve: AdverseFillReserve) -> usize {
+        let (mut arbitrage_executor, arbitrage_profit_receiver) = triangle_executor_with_threshold(0.01);
+        arbitrage_executor.set_adverse_fill_reserve(Some(adverse_fill_reserve));
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        return arbitrage_profit_receiver.try_iter().count();
+    }
+
+    #[test]
+    fn profit_below_the_reserve_adjusted_threshold_is_not_sent() {
+        // the 0.0495 BTC profit clears the threshold alone, not once 0.045 BTC is reserved
+        assert_eq!(sent_with_reserve(AdverseFillReserve::ABSOLUTE(0.045)), 0);
+    }
+
+    #[test]
+    fn profit_above_the_reserve_adjusted_threshold_is_sent() {
+        // 300 bps of 1 BTC reserves 0.03 BTC
+        assert_eq!(sent_with_reserve(AdverseFillReserve::BPS(300.0)), 1);
+    }
+
+    // last tick of the triangle, repeating it keeps the cycle profitable
+    fn last_triangle_tick() -> Vec<MarketBBO> {
+        return vec![triangle_ticks().pop().unwrap()];
+    }
+
+    #[test]
+    fn single_profitable_tick_does_not_fire() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_min_consecutive_ticks(3);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(arbitrage_executor.opportunities.load(atomic::Ordering::SeqCst), 1);
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn consecutive_profitable_ticks_fire() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_min_consecutive_ticks(3);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        pump(&mut arbitrage_executor, last_triangle_tick());
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+        pump(&mut arbitrage_executor, last_triangle_tick());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(arbitrage_profit_receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn suspending_a_cycle_persists_its_state() {
+        let (arbitrage_executor, profit_sink, _) = triangle_executor(false);
+        let name = cycle_names(&arbitrage_executor)[0].clone();
+
+        arbitrage_executor.set_cycle_suspended(&name, true);
+
+        assert_eq!(profit_sink.get_suspended_cycles(), vec![name.clone()]);
+        arbitrage_executor.set_cycle_suspended(&name, false);
+        assert!(profit_sink.get_suspended_cycles().is_empty());
+    }
+
+    #[test]
+    fn snapshot_evaluation_latency_is_recorded_into_stats() {
+        let (arbitrage_executor, _, _) = triangle_executor(false);
+
+        arbitrage_executor.evaluate_snapshot(&triangle_snapshot());
+        arbitrage_executor.evaluate_snapshot(&triangle_snapshot());
+
+        let evaluation_stats = arbitrage_executor.get_evaluation_stats();
+        let snapshot_stats = evaluation_stats.get("snapshot").unwrap();
+        assert_eq!(snapshot_stats.evaluations, 2);
+        assert!(snapshot_stats.max_latency_us >= snapshot_stats.latency_us);
+    }
+
+    #[test]
+    fn evaluation_latency_is_a_moving_average() {
+        let mut evaluation_stats = EvaluationStats::default();
+
+        evaluation_stats.record(Duration::from_micros(100));
+        evaluation_stats.record(Duration::from_micros(200));
+
+        // the first latency seeds the average, the next moves it by alpha
+        assert!((evaluation_stats.latency_us - 110.0).abs() < 1e-6);
+        assert!((evaluation_stats.max_latency_us - 200.0).abs() < 1e-6);
+        assert_eq!(evaluation_stats.evaluations, 2);
+    }
+
+    // return parent span id of the span
+    fn parent_of(spans: &[SpanData], span_id: SpanId) -> SpanId {
+        return spans.iter().find(|span| span.span_context.span_id() == span_id).map(|span| span.parent_span_id).unwrap();
+    }
+
+    #[test]
+    fn profitable_cycle_produces_the_span_hierarchy() {
+        let (span_exporter, span_receiver, _) = new_test_exporter();
+        global::set_tracer_provider(TracerProvider::builder().with_simple_exporter(span_exporter).build());
+
+        // detection
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.start();
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+        let arbitrage_profit = arbitrage_profit_receiver.try_recv().unwrap();
+        let trace_id = arbitrage_profit.get_trace_context().span().span_context().trace_id();
+
+        // execution
+        let exchange = Arc::new(MockExchange::new());
+        arbitrage_testing::push_filled_legs(&exchange, &arbitrage_profit);
+        let (arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_testing::run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit.clone()]);
+
+        // spans of the cycle trace, until every leg ended
+        let mut spans: Vec<SpanData> = vec![];
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (spans.iter().filter(|span| span.name == arbitrage_tracing::ORDER_LEG).count() < 3) & (Instant::now() < deadline) {
+            if let Ok(span) = span_receiver.recv_timeout(Duration::from_millis(10)) {
+                if span.span_context.trace_id() == trace_id {
+                    spans.push(span);
+                }
+            }
+        }
+        let span_id_of = |name: &str| spans.iter().find(|span| span.name == name).map(|span| span.span_context.span_id()).unwrap();
+
+        // tick -> evaluation -> emission -> execution -> legs
+        let emission_span_id = arbitrage_profit.get_trace_context().span().span_context().span_id();
+        assert_eq!(span_id_of(arbitrage_tracing::PROFIT_EMISSION), emission_span_id);
+        assert_eq!(parent_of(&spans, emission_span_id), span_id_of(arbitrage_tracing::CYCLE_EVALUATION));
+        assert_eq!(parent_of(&spans, span_id_of(arbitrage_tracing::CYCLE_EVALUATION)), span_id_of(arbitrage_tracing::TICK_INGESTION));
+        assert_eq!(parent_of(&spans, span_id_of(arbitrage_tracing::CYCLE_EXECUTION)), emission_span_id);
+        let leg_parents: Vec<SpanId> = spans.iter().filter(|span| span.name == arbitrage_tracing::ORDER_LEG).map(|span| span.parent_span_id).collect();
+        assert_eq!(leg_parents, vec![span_id_of(arbitrage_tracing::CYCLE_EXECUTION); 3]);
+    }
+
+    #[test]
+    fn ticks_within_the_coalescing_window_are_evaluated_once_with_the_last_quotes() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.set_tick_coalescing_window(Some(Duration::from_millis(200)));
+        arbitrage_executor.start();
+
+        // eth usdt requoted within the window
+        let mut ticks = triangle_ticks();
+        let mut eth_usdt = arbitrage_testing::triangle_quotes()[1].clone();
+        eth_usdt.bid_price = 2100.0;
+        eth_usdt.ask_price = 2101.0;
+        ticks.push(eth_usdt.build());
+        pump(&mut arbitrage_executor, ticks);
+        arbitrage_executor.shutdown();
+
+        let profits: Vec<ArbitrageProfit> = arbitrage_profit_receiver.try_iter().collect();
+        assert_eq!(arbitrage_executor.opportunities.load(atomic::Ordering::SeqCst), 1);
+        assert_eq!(profits.len(), 1);
+        assert_eq!(profits[0].get_transaction_result_list()[1].get_price(), 2100.0);
+    }
+
+    #[test]
+    fn run_is_recorded_and_its_executions_reference_it() {
+        // the ordering executes the profits of the executor
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 3.0)])));
+        arbitrage_testing::push_filled_legs(&exchange, &arbitrage_testing::triangle_profit(1.0));
+        let (arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let profit_sink = Arc::new(MemoryProfitSink::default());
+        let start_assets = vec!["BTC".to_string()];
+        let symbol_list = vec!["BTC".to_string(), "ETH".to_string(), "USDT".to_string()];
+        let mut arbitrage_executor = ArbitrageExecutor::with_ordering("BINANCE".to_string(), &start_assets, &symbol_list, 1.0, 0.0, true, 64,
+                                                                      Arc::clone(&profit_sink) as Arc<dyn ProfitSink>, arbitrage_ordering, arbitrage_profit_sender, 3);
+        arbitrage_executor.transactions_list.push(arbitrage_testing::triangle());
+        arbitrage_executor.set_profit_persistence(true);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (profit_sink.executions.lock().unwrap().len() < 3) & (Instant::now() < deadline) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        arbitrage_executor.shutdown();
+
+        let runs = profit_sink.runs.lock().unwrap().clone();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].exchange, "BINANCE");
+        assert_eq!(runs[0].start_assets, start_assets);
+        assert_eq!(runs[0].symbols_count, 3);
+        assert_eq!(runs[0].mode, "SEQUENTIAL");
+        let executions = profit_sink.executions.lock().unwrap().clone();
+        assert_eq!(executions.len(), 3);
+        assert!(executions.iter().all(|execution| execution.run_id == Some(runs[0].run_id)));
+    }
+
+    #[test]
+    fn replay_returns_the_detected_profits_without_ordering() {
+        let (mut arbitrage_executor, _, arbitrage_profit_receiver) = triangle_executor(true);
+        arbitrage_executor.start();
+
+        let profits = arbitrage_executor.replay(triangle_ticks().into_iter());
+        arbitrage_executor.shutdown();
+
+        assert_eq!(profits.len(), 1);
+        assert_eq!(profits[0].get_name(), arbitrage_testing::triangle_profit(1.0).get_name());
+        assert!(profits[0].get_profit() > 0.0);
+        assert!(arbitrage_profit_receiver.try_recv().is_err());
+        assert!(arbitrage_executor.live_config.read().unwrap().ordering);
+    }
+
+    #[test]
+    fn replay_skips_profits_below_the_threshold() {
+        // the triangle makes about 0.05 BTC on 1 BTC
+        let (mut arbitrage_executor, _) = triangle_executor_with_threshold(0.5);
+        arbitrage_executor.start();
+
+        let profits = arbitrage_executor.replay(triangle_ticks().into_iter());
+        arbitrage_executor.shutdown();
+
+        assert!(profits.is_empty());
+    }
+
+    #[test]
+    fn replay_before_start_returns_nothing() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(true);
+
+        assert!(arbitrage_executor.replay(triangle_ticks().into_iter()).is_empty());
+    }
+
+    #[test]
+    fn heartbeat_fires_at_the_configured_interval_of_the_clock() {
+        let (mut arbitrage_executor, _, _) = triangle_executor(false);
+        let start_date = Utc.ymd(2026, 1, 1).and_hms(0, 0, 0);
+        let clock = Arc::new(MockClock::new(start_date));
+        arbitrage_executor.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        let (heartbeat_sender, heartbeat_receiver) = crossbeam_channel::unbounded();
+        arbitrage_executor.set_heartbeat_interval(Some(Duration::from_secs(60)));
+        arbitrage_executor.set_heartbeat_sender(Some(heartbeat_sender));
+        arbitrage_executor.start();
+        pump(&mut arbitrage_executor, triangle_ticks());
+
+        // not yet due
+        clock.advance(chrono::Duration::seconds(30));
+        assert!(heartbeat_receiver.recv_timeout(HEARTBEAT_POLL_INTERVAL * 5).is_err());
+
+        // due, then due again one interval later
+        clock.advance(chrono::Duration::seconds(30));
+        let first_heartbeat = heartbeat_receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        let second_heartbeat = heartbeat_receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        arbitrage_executor.shutdown();
+
+        assert_eq!(first_heartbeat.uptime_secs, 60);
+        assert_eq!(first_heartbeat.ticks, 3);
+        assert_eq!(first_heartbeat.opportunities, 1);
+        assert_eq!(second_heartbeat.uptime_secs, 120);
+        assert!(heartbeat_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn shutdown_joins_the_profit_writer_and_flushes_the_last_latency_bucket() {
+        let (mut arbitrage_executor, profit_sink, _) = triangle_executor(false);
+        arbitrage_executor.set_profit_persistence(true);
+        arbitrage_executor.start();
+
+        pump(&mut arbitrage_executor, triangle_ticks());
+        arbitrage_executor.shutdown();
+
+        // the profit stays in the current bucket, only shutdown persists its percentiles
+        assert_eq!(profit_sink.profits.lock().unwrap().len(), 1);
+        let latencies = profit_sink.latencies.lock().unwrap().clone();
+        assert_eq!(latencies.len(), 1);
+        assert_eq!(latencies[0].count, 1);
+        assert_eq!(latencies[0].bucket_secs, 60);
+    }
+}
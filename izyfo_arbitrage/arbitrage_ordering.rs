@@ -1,36 +1,143 @@
 use std::{thread, time};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
-use binance::account::*;
-use binance::api::*;
 use binance::errors::Error;
 use binance::errors::ErrorKind as BinanceLibErrorKind;
-use binance::market::*;
-use binance::model::Transaction;
+use binance::model::{AccountInformation, Order, Transaction};
+use chrono::{DateTime, Utc};
 use bus::BusReader;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use futures::future::lazy;
 use log::{debug, error, info, trace, warn};
+use opentelemetry::Context;
 use simplelog::*;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::sync::atomic::{self, AtomicBool};
+use std::thread::JoinHandle;
+use uuid::Uuid;
 
 use crate::izyfo_arbitrage::arbitrage::ArbitrageProfit;
+use crate::izyfo_arbitrage::arbitrage_clock::{Clock, SystemClock};
+use crate::izyfo_arbitrage::arbitrage_exchange::{strip_prefix, BinanceExchange, Exchange, RateLimitedExchange, RateLimiter};
 use crate::izyfo_arbitrage::arbitrage_executor::ExecutionMode;
-use crate::izyfo_arbitrage::arbitrage_transaction::ArbitrageTransactionResult;
+use crate::izyfo_arbitrage::arbitrage_sink::{BalanceSnapshot, ExecutionRecord, ProfitSink};
+use crate::izyfo_arbitrage::arbitrage_tracing;
+use crate::izyfo_arbitrage::arbitrage_transaction::{to_f64, ArbitrageTransactionResult, OrderType, TimeInForce};
 use crate::izyfo_configs::services;
 use crate::izyfo_connectors::referencedata::{ReferencedataConnector, Referencedata};
 use crate::izyfo_events::exchange::instrument::Instrument;
-use crate::izyfo_utils::math;
+use crate::izyfo_events::exchange::market_bbo::MarketBBO;
+use crate::izyfo_utils::math::{self, RoundingStrategy};
 use std::env;
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum OrderingError {
+    BinanceError(i64, String),
+    ExchangeError(String),
+    UnknownOperation(String),
+    InvalidQty,
+    Timeout,
+    InFlightLimit,
+    AlreadySubmitted(Option<String>),
+    Panicked,
+    RateLimited,
+}
+
+impl fmt::Display for OrderingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderingError::BinanceError(code, msg) => write!(f, "binance error. error code: {}, msg: {}", code, msg),
+            OrderingError::ExchangeError(msg) => write!(f, "exchange error: {}", msg),
+            OrderingError::UnknownOperation(operation) => write!(f, "unknown operation: '{}'", operation),
+            OrderingError::InvalidQty => write!(f, "invalid qty"),
+            OrderingError::Timeout => write!(f, "timeout"),
+            OrderingError::InFlightLimit => write!(f, "in-flight limit reached"),
+            OrderingError::AlreadySubmitted(Some(status)) => write!(f, "already submitted, status: {}", status),
+            OrderingError::AlreadySubmitted(None) => write!(f, "already submitted"),
+            OrderingError::Panicked => write!(f, "transaction thread panicked"),
+            OrderingError::RateLimited => write!(f, "request weight limit reached"),
+        }
+    }
+}
+
+impl error::Error for OrderingError {}
+
+impl OrderingError {
+    // rate limit (-1003), timestamp outside recv window (-1021) and server errors are worth retrying
+    pub fn is_transient(&self) -> bool {
+        match self {
+            OrderingError::BinanceError(code, _) => (*code == -1003) | (*code == -1021),
+            OrderingError::ExchangeError(msg) => {
+                msg.contains("Internal Server Error") | msg.contains("Bad Gateway") | msg.contains("Service Unavailable") | msg.contains("Gateway Timeout")
+            }
+            _ => false
+        }
+    }
+}
+
+impl From<Error> for OrderingError {
+    fn from(err: Error) -> OrderingError {
+        match err.0 {
+            BinanceLibErrorKind::BinanceError(code, msg, _response) => OrderingError::BinanceError(code as i64, msg),
+            BinanceLibErrorKind::Msg(msg) => OrderingError::ExchangeError(msg),
+            _ => OrderingError::ExchangeError(err.0.to_string()),
+        }
+    }
+}
+
+// blocking loops wake up this often to check for shutdown
+pub const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct ArbitrageOrdering {
-    exchange: Arc<Account>,
-    balances: HashMap<String, f32>,
-    referencedata: Referencedata,
+    exchange: Arc<dyn Exchange>,
+    balances: Arc<RwLock<HashMap<String, f64>>>,
+    referencedata: Option<Referencedata>,
     busy: bool,
     arbitrage_profit_receiver: Receiver<ArbitrageProfit>,
     mode: ExecutionMode,
+    quote_cache: Arc<RwLock<HashMap<String, MarketBBO>>>,
+    order_status_poll_interval: Duration,
+    order_status_max_wait: Duration,
+    requote_attempts: u32,
+    requote_budget: Duration,
+    price_tolerance_bps: f64,
+    retry_attempts: u32,
+    retry_backoff: Duration,
+    daily_order_limit: Option<u32>,
+    daily_reset_hour: u32,
+    daily_order_counter: Arc<Mutex<(DateTime<Utc>, u32)>>,
+    clock: Arc<dyn Clock>,
+    max_in_flight_per_instrument: Option<u32>,
+    in_flight_wait: Duration,
+    in_flight: Arc<(Mutex<HashMap<String, u32>>, Condvar)>,
+    open_orders: Arc<Mutex<HashMap<String, Vec<u64>>>>,
+    cycle_timeout: Option<Duration>,
+    max_concurrent_executions: Option<usize>,
+    pending_profits: VecDeque<ArbitrageProfit>,
+    max_pending_profits: usize,
+    dropped_profits: u64,
+    keep_balances: HashMap<String, f64>,
+    cleanup_priority: HashMap<String, f64>,
+    balances_updated_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+    balances_estimated: Arc<AtomicBool>,
+    balance_refresh_interval: Option<Duration>,
+    max_balance_age: Option<Duration>,
+    realized_pnl: Arc<Mutex<HashMap<String, f64>>>,
+    downstream_liquidity_factor: Option<f64>,
+    profit_sink: Option<Arc<dyn ProfitSink>>,
+    persist_raw_response: bool,
+    check_start_balance: bool,
+    size_to_current_book: bool,
+    client_order_window: Option<Duration>,
+    sleep_between_transactions: Duration,
+    client_orders: Arc<Mutex<HashMap<String, (DateTime<Utc>, Option<(String, u64)>)>>>,
+    run_id: Option<Uuid>,
+    shutdown: Arc<AtomicBool>,
+    last_arbitrage_profit: Option<ArbitrageProfit>,
 }
 
 pub struct ArbitrageOrderingTransaction {
@@ -41,469 +148,2846 @@ pub struct ArbitrageOrderingTransaction {
 
 
 impl ArbitrageOrdering {
-    // new arbitrage
-    pub fn new(arbitrage_profit: Receiver<ArbitrageProfit>, mode: ExecutionMode) -> ArbitrageOrdering {
-        let api_key = Some("".to_string());
-        let secret_key = Some("".to_string());
-        let account: Account = Binance::new(api_key, secret_key);
+    // new arbitrage, empty keys fall back to BINANCE_API_KEY and BINANCE_SECRET_KEY
+    pub fn new(arbitrage_profit: Receiver<ArbitrageProfit>, mode: ExecutionMode, api_key: String, secret_key: String) -> ArbitrageOrdering {
+        let (api_key, secret_key) = resolve_credentials(api_key, secret_key);
+        if api_key.is_empty() | secret_key.is_empty() {
+            warn!("arbitrage_ordering - missing api credentials, orders will be rejected.");
+        }
+        let exchange = BinanceExchange::new(api_key, secret_key);
 
         let url = String::from("");
         let referencedata_connector = ReferencedataConnector::from_url(url);
-        let referencedata = referencedata_connector.get_referencedata("BINANCE");
+        let referencedata = referencedata_connector.get_referencedata(exchange.get_name());
+        return ArbitrageOrdering::with_exchange(arbitrage_profit, mode, Arc::new(exchange), Some(referencedata));
+    }
+
+    // new arbitrage on an exchange, without referencedata instruments are resolved from the quote cache
+    pub fn with_exchange(arbitrage_profit: Receiver<ArbitrageProfit>, mode: ExecutionMode, exchange: Arc<dyn Exchange>, referencedata: Option<Referencedata>) -> ArbitrageOrdering {
+        // sleep between transactions, read once
+        let sleep_between_transactions = parse_sleep_between_transactions(env::var("SLEEP_BETWEEN_TRANSACTIONS").ok());
 
         let mut arbitrage_ordering = ArbitrageOrdering {
-            exchange: Arc::new(account),
-            balances: HashMap::new(),
+            exchange: exchange,
+            balances: Arc::new(RwLock::new(HashMap::new())),
             referencedata: referencedata,
             busy: false,
             arbitrage_profit_receiver: arbitrage_profit,
             mode: mode,
+            quote_cache: Arc::new(RwLock::new(HashMap::new())),
+            order_status_poll_interval: Duration::from_millis(50),
+            order_status_max_wait: Duration::from_millis(300),
+            requote_attempts: 0,
+            requote_budget: Duration::from_millis(100),
+            price_tolerance_bps: 0.0,
+            retry_attempts: 3,
+            retry_backoff: Duration::from_millis(50),
+            daily_order_limit: None,
+            daily_reset_hour: 0,
+            daily_order_counter: Arc::new(Mutex::new((Utc::now(), 0))),
+            clock: Arc::new(SystemClock),
+            max_in_flight_per_instrument: None,
+            in_flight_wait: Duration::from_millis(500),
+            in_flight: Arc::new((Mutex::new(HashMap::new()), Condvar::new())),
+            open_orders: Arc::new(Mutex::new(HashMap::new())),
+            cycle_timeout: None,
+            max_concurrent_executions: None,
+            pending_profits: VecDeque::new(),
+            max_pending_profits: 16,
+            dropped_profits: 0,
+            keep_balances: HashMap::new(),
+            cleanup_priority: HashMap::new(),
+            balances_updated_at: Arc::new(RwLock::new(None)),
+            balances_estimated: Arc::new(AtomicBool::new(false)),
+            balance_refresh_interval: None,
+            max_balance_age: None,
+            realized_pnl: Arc::new(Mutex::new(HashMap::new())),
+            downstream_liquidity_factor: None,
+            profit_sink: None,
+            persist_raw_response: false,
+            check_start_balance: false,
+            size_to_current_book: false,
+            client_order_window: None,
+            sleep_between_transactions: sleep_between_transactions,
+            client_orders: Arc::new(Mutex::new(HashMap::new())),
+            run_id: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            last_arbitrage_profit: None,
         };
         arbitrage_ordering
     }
 
-    pub fn start(&mut self) {
-        info!("arbitrage_ordering - started.");
-
-        // create thread counter
-        let thread_counter = Arc::new(Mutex::new(0));
+    // set interval of the background balance refresh, none refreshes only around executions
+    pub fn set_balance_refresh_interval(&mut self, balance_refresh_interval: Option<Duration>) {
+        self.balance_refresh_interval = balance_refresh_interval;
+    }
 
-        // display error
-        fn display_error(err: Error) {
-            match err.0 {
-                BinanceLibErrorKind::BinanceError(code, msg, response) => match code {
-                    _ => error!("arbitrage_ordering - binance error. error code: {}, msg: {}", code, msg),
-                },
-                BinanceLibErrorKind::Msg(msg) => {
-                    error!("arbitrage_ordering - lib error. error: {}", msg)
+    // start background balance refresh, stopped by the shutdown flag
+    pub fn start_balance_refresh(&self) -> Option<JoinHandle<()>> {
+        let balance_refresh_interval = self.balance_refresh_interval?;
+        let c_exchange = Arc::clone(&self.exchange);
+        let c_balance_store = self.get_balance_store();
+        let c_shutdown = Arc::clone(&self.shutdown);
+        let balance_refresh_handle = thread::spawn(move || {
+            info!("arbitrage_ordering - balance refresh started. interval: {:?}", balance_refresh_interval);
+            let mut last_refresh = Instant::now();
+            while !c_shutdown.load(atomic::Ordering::SeqCst) {
+                thread::sleep(SHUTDOWN_POLL_INTERVAL);
+                if last_refresh.elapsed() < balance_refresh_interval {
+                    continue;
+                }
+                last_refresh = Instant::now();
+                match c_exchange.get_account() {
+                    Ok(answer) => c_balance_store.set(answer),
+                    Err(err) => error!("arbitrage_ordering - balance refresh failed. error: {}", err)
                 }
-                _ => error!("arbitrage_ordering - other error. error: {}.", err.0),
-            };
-        }
-
-        loop {
-            let arbitrage_profit = self.arbitrage_profit_receiver.recv();
-
-            // continue if thread counter equal zero
-            let mut num = thread_counter.lock().unwrap();
-            if (*num != 0){
-                continue;
             }
+        });
+        return Some(balance_refresh_handle);
+    }
 
-            match arbitrage_profit {
-                Ok(p) => {
-                    info!("arbitrage_ordering - parallel execution started.");
-                    let start_date = Instant::now();
-                    let mut children = vec![];
-
-                    for t in p.get_transaction_result_list() {
-                        let transaction = t.clone();
-                        let thread_counter = Arc::clone(&thread_counter);
-
-                        children.push(thread::spawn(move || {
-                            info!("arbitrage_ordering - executing transaction. transaction: {:?}", transaction);
-
-                            // increment counter
-                            let mut num = thread_counter.lock().unwrap();
-                            *num += 1;
+    // set shutdown flag, the start loop returns once it is set
+    pub fn set_shutdown(&mut self, shutdown: Arc<AtomicBool>) {
+        self.shutdown = shutdown;
+    }
 
-                            let api_key = Some("".to_string());
-                            let secret_key = Some("".to_string());
-                            let account: Account = Binance::new(api_key, secret_key);
+    // set run referenced by persisted executions
+    pub fn set_run_id(&mut self, run_id: Option<Uuid>) {
+        self.run_id = run_id;
+    }
 
-                            let instrument_symbol = transaction.get_exchange_code().to_string();
+    // return execution mode
+    pub fn get_mode(&self) -> &ExecutionMode {
+        return &self.mode;
+    }
 
-                            // buy transaction
-                            let operation = transaction.get_operation();
+    // set sink recording placed orders
+    pub fn set_profit_sink(&mut self, profit_sink: Option<Arc<dyn ProfitSink>>) {
+        self.profit_sink = profit_sink;
+    }
 
-                            // uuid
-                            let uuid = transaction.get_uuid();
+    // keep the serialized order response in the executions record, off by default due to size
+    pub fn set_persist_raw_response(&mut self, persist_raw_response: bool) {
+        self.persist_raw_response = persist_raw_response;
+    }
 
-                            if operation == "BUY" {
+    // skip cycles whose start asset balance no longer covers qty in
+    pub fn set_check_start_balance(&mut self, check_start_balance: bool) {
+        self.check_start_balance = check_start_balance;
+    }
 
-                                // setup order parameters
-                                let price = transaction.get_price();
-                                let qty = transaction.get_qty_to_execute();
+    // check start asset balance covers qty in
+    fn has_start_balance(&self, arbitrage_profit: &ArbitrageProfit) -> bool {
+        if !self.check_start_balance {
+            return true;
+        }
+        let start_asset = arbitrage_profit.get_start_asset(&self.exchange.get_prefix());
+        let balance = self.get_balance(&start_asset).unwrap_or(0.0);
+        if balance < arbitrage_profit.get_qty_in() {
+            warn!("arbitrage_ordering - insufficient start balance, arbitrage skipped. name: {}, asset: {}, balance: {}, qty_in: {}",
+                  arbitrage_profit.get_name(), start_asset, balance, arbitrage_profit.get_qty_in());
+            return false;
+        }
+        return true;
+    }
 
-                                info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+    // size orders to the smaller of detected and order-time top-of-book qty
+    pub fn set_size_to_current_book(&mut self, size_to_current_book: bool) {
+        self.size_to_current_book = size_to_current_book;
+    }
 
-                                // run exchange ordering
-                                match account.limit_buy_fok(instrument_symbol.clone(), qty, price) {
-                                    Ok(answer) => {
-                                        info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                                        info!("arbitrage_ordering - order transaction. {:?}", answer);
-                                        *num -= 1;
-                                        Ok(answer)
-                                    }
-                                    Err(err) => {
-                                        error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                                        display_error(err);
-                                        *num -= 1;
-                                        Err("failed".to_string())
-                                    }
-                                }
-                            } else if operation == "SELL" {
+    // return qty to order, capped by the top-of-book qty at detection and at order time
+    fn get_order_qty(&self, transaction: &ArbitrageTransactionResult) -> f64 {
+        let qty = transaction.get_qty_to_execute();
+        if !self.size_to_current_book {
+            return qty;
+        }
 
-                                // setup order parameters
-                                let price = transaction.get_price();
-                                let qty = transaction.get_qty_to_execute();
+        let current_qty = match self.get_quote(transaction.get_instrument()) {
+            Some(quote) => {
+                if transaction.get_operation() == "BUY" {
+                    to_f64(quote.get_ask_qty())
+                } else {
+                    to_f64(quote.get_bid_qty())
+                }
+            }
+            None => return qty
+        };
 
-                                info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+        let available_qty = transaction.get_market_qty().min(current_qty);
+        if qty <= available_qty {
+            return qty;
+        }
 
-                                // run exchange ordering
-                                match account.limit_sell_fok(instrument_symbol.clone(), qty, price) {
-                                    Ok(answer) => {
-                                        info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                                        info!("arbitrage_ordering - order transaction. {:?}", answer);
-                                        *num -= 1;
-                                        Ok(answer)
-                                    }
-                                    Err(err) => {
-                                        error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                                        error!("arbitrage_ordering - order transaction. error: {:?}", err);
-                                        *num -= 1;
-                                        display_error(err);
-                                        Err("failed".to_string())
-                                    }
-                                }
-                            } else {
-                                error!("{} failed to recognize transaction", instrument_symbol);
-                                *num -= 1;
-                                Err("failed".to_string())
-                            }
-                        }));
+        // round down to the step
+        let step_size = transaction.get_step_size();
+        let order_qty = math::round_to_step(available_qty, step_size, &RoundingStrategy::FLOOR);
+        warn!("arbitrage_ordering - book thinned, order reduced. uuid: {}, symbol: {}, qty: {}, detected_qty: {}, current_qty: {}, order_qty: {}",
+              transaction.get_uuid().to_string(), transaction.get_exchange_code(), qty, transaction.get_market_qty(), current_qty, order_qty);
+        return order_qty;
+    }
 
-                        // sleep between transactions
-                        let mut sleep_duration = time::Duration::from_micros(10);
-                        match env::var("SLEEP_BETWEEN_TRANSACTIONS") {
-                            Ok(s) => {
-                                sleep_duration = time::Duration::from_micros(s.parse::<u64>().unwrap());
-                            }
-                            Err(e) => ()
-                        };
-                        thread::sleep(sleep_duration);
+    // refuse a second placement of the same client order id (transaction uuid) within the window
+    pub fn set_client_order_window(&mut self, client_order_window: Option<Duration>) {
+        self.client_order_window = client_order_window;
+    }
 
-                    };
+    // register client order id, returning the previous placement if it is still within the window
+    fn register_client_order(&self, client_order_id: &String) -> Option<Option<(String, u64)>> {
+        let window = match self.client_order_window {
+            Some(w) => chrono::Duration::from_std(w).unwrap_or(chrono::Duration::zero()),
+            None => return None
+        };
+        let now = self.clock.now();
 
-                    // arbitrage info
-                    info!("arbitrage_ordering - parallel executions finished. duration: {:?}", start_date.elapsed());
-                }
-                Err(err) => {
-                    error!("arbitrage_ordering - failed to recv transaction. error: {:?}", err);
-                }
-            }
+        let mut client_orders = self.client_orders.lock().unwrap();
+        client_orders.retain(|_, (placed_at, _)| now.signed_duration_since(*placed_at) < window);
+        if let Some((_, placement)) = client_orders.get(client_order_id) {
+            return Some(placement.clone());
         }
+        client_orders.insert(client_order_id.clone(), (now, None));
+        return None;
     }
 
-    // parallel execution
-    pub fn execute_parallel(&mut self, arbitrage_profit: &ArbitrageProfit) {
+    // record exchange order of a client order id
+    fn set_client_order(&self, client_order_id: &String, symbol: &String, order_id: u64) {
+        if self.client_order_window.is_none() {
+            return;
+        }
+        if let Some(entry) = self.client_orders.lock().unwrap().get_mut(client_order_id) {
+            entry.1 = Some((symbol.clone(), order_id));
+        }
     }
 
-    // sequential execution
-    pub fn execute_sequential(&mut self, arbitrage_profit: &ArbitrageProfit) {
-        info!("arbitrage_ordering - executing.");
+    // set sleep between spawning the parallel legs
+    pub fn set_sleep_between_transactions(&mut self, sleep_between_transactions: Duration) {
+        self.sleep_between_transactions = sleep_between_transactions;
+    }
 
-        self.busy = true;
-        let start_date = Instant::now();
+    // set quote cache shared with the executor
+    pub fn set_quote_cache(&mut self, quote_cache: Arc<RwLock<HashMap<String, MarketBBO>>>) {
+        self.quote_cache = quote_cache;
+    }
 
-        // results
-        let mut results: HashMap<u32, (Transaction, f32)> = HashMap::new();
-        let mut transaction_nbr: u32 = 0;
+    // set interval between order status polls
+    pub fn set_order_status_poll_interval(&mut self, order_status_poll_interval: Duration) {
+        self.order_status_poll_interval = order_status_poll_interval;
+    }
 
-        // run all transactions
-        for arbitrage_transaction in arbitrage_profit.get_transaction_result_list() {
-            let result = self.execute_transaction(arbitrage_transaction);
+    // set maximum wait for an open order before it is cancelled
+    pub fn set_order_status_max_wait(&mut self, order_status_max_wait: Duration) {
+        self.order_status_max_wait = order_status_max_wait;
+    }
 
-            // result
-            match result {
-                Ok(order_transaction) => {
-                    let symbol = &order_transaction.symbol;
-                    let order_id = &order_transaction.order_id;
+    // set requote attempts and time budget for killed FOK orders
+    pub fn set_requote(&mut self, requote_attempts: u32, requote_budget: Duration) {
+        self.requote_attempts = requote_attempts;
+        self.requote_budget = requote_budget;
+    }
 
-                    // sleep
-                    let sleep_duration = time::Duration::from_millis(300);
-                    thread::sleep(sleep_duration);
+    // set tolerance, in basis points, a FOK leg is re-priced to a book moved against its detected price, 0 disables
+    pub fn set_price_tolerance_bps(&mut self, price_tolerance_bps: f64) {
+        self.price_tolerance_bps = price_tolerance_bps.max(0.0);
+    }
 
-                    // check order status
-                    match self.exchange.order_status(symbol, *order_id) {
-                        Ok(order_status) => {
-                            if order_status.status == "NEW" {
+    // return order price, the current crossing price if the book moved against a FOK leg within the tolerance, else the detected price
+    fn get_order_price(&self, transaction: &ArbitrageTransactionResult) -> f64 {
+        let price = transaction.get_price();
+        if (self.price_tolerance_bps <= 0.0) | (transaction.get_time_in_force() != &TimeInForce::FOK) {
+            return price;
+        }
+        let book_price = match self.get_requote_price(transaction) {
+            Some(p) if p > 0.0 => p,
+            _ => return price
+        };
 
-                                // cancel order
-                                match self.exchange.cancel_order(symbol, *order_id) {
-                                    Ok(order_cancelled) => {
-                                        warn!("arbitrage_ordering - cancelling order. {:?}", order_cancelled);
-                                    }
-                                    Err(err) => {
-                                        error!("arbitrage_ordering - cancel order error. error: {}", err)
-                                    }
-                                }
-                            } else if order_status.status == "FILLED" {
-                                continue;
-                            }
-                        }
-                        Err(err_1) => {
-                            break;
-                        }
-                    }
-                }
-                Err(err) => {
-                    error!("arbitrage_ordering - order status error. error: {}", err);
-                    break;
-                }
-            }
+        // moved against the leg, a higher ask or a lower bid
+        let tolerance = self.price_tolerance_bps / 10000.0;
+        let within_tolerance = if transaction.get_operation() == "BUY" {
+            (book_price > price) & (book_price <= price * (1.0 + tolerance))
+        } else {
+            (book_price < price) & (book_price >= price * (1.0 - tolerance))
+        };
+        if within_tolerance {
+            info!("arbitrage_ordering - leg re-priced within tolerance. uuid: {}, symbol: {}, price: {}, book_price: {}, tolerance_bps: {}",
+                  transaction.get_uuid().to_string(), transaction.get_exchange_code(), price, book_price, self.price_tolerance_bps);
+            return book_price;
         }
+        return price;
+    }
 
-        info!("arbitrage_ordering - executed. duration: {:?}", start_date.elapsed());
+    // set retries of transient placement errors, backoff doubling from the base after each attempt
+    pub fn set_retry(&mut self, retry_attempts: u32, retry_backoff: Duration) {
+        self.retry_attempts = retry_attempts;
+        self.retry_backoff = retry_backoff;
+    }
 
-        // clean
-        //self.cancel_pending_transactions(results);
+    // set exchange orders are placed on, instrument ids must carry its prefix
+    pub fn set_exchange(&mut self, exchange: Arc<dyn Exchange>) {
+        self.exchange = exchange;
+    }
 
-        // revert
-        self.clean_balances(arbitrage_profit);
+    // limit request weight of every exchange call, the limiter may be shared with other orderings on the same account
+    pub fn set_rate_limiter(&mut self, rate_limiter: Arc<Mutex<RateLimiter>>) {
+        self.exchange = Arc::new(RateLimitedExchange::new(Arc::clone(&self.exchange), rate_limiter));
+    }
 
-        // update balances after transactions complete
-        self.update_balances();
+    // set clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
 
-        // remove busy
-        self.busy = false;
+    // set daily order limit, reset every day at the given utc hour
+    pub fn set_daily_order_limit(&mut self, daily_order_limit: Option<u32>, daily_reset_hour: u32) {
+        self.daily_order_limit = daily_order_limit;
+        self.daily_reset_hour = daily_reset_hour % 24;
     }
 
-    // execute transaction
-    pub fn execute_transaction(&self, transaction: &ArbitrageTransactionResult) -> Result<Transaction, String> {
-        let instrument_symbol = transaction.get_exchange_code().to_string();
+    // return daily order count
+    pub fn get_daily_order_count(&self) -> u32 {
+        return self.daily_order_counter.lock().unwrap().1;
+    }
 
-        // buy transaction
-        let operation = transaction.get_operation();
+    // return start of the current daily period
+    fn get_daily_period_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let boundary = now.date().and_hms(self.daily_reset_hour, 0, 0);
+        if now < boundary {
+            return boundary - chrono::Duration::days(1);
+        }
+        return boundary;
+    }
 
-        // uuid
-        let uuid = transaction.get_uuid();
+    // reserve daily orders, false if the limit would be exceeded
+    fn acquire_daily_orders(&self, count: u32) -> bool {
+        let limit = match self.daily_order_limit {
+            Some(l) => l,
+            None => return true
+        };
 
-        if operation == "BUY" {
+        let period_start = self.get_daily_period_start(self.clock.now());
+        let mut counter = self.daily_order_counter.lock().unwrap();
+        if counter.0 != period_start {
+            info!("arbitrage_ordering - daily order counter reset. period_start: {}", period_start);
+            *counter = (period_start, 0);
+        }
 
-            // setup order parameters
-            let price = transaction.get_price();
-            let qty = transaction.get_qty_to_execute();
+        if counter.1 + count > limit {
+            warn!("arbitrage_ordering - daily order limit reached. count: {}, limit: {}", counter.1, limit);
+            return false;
+        }
+        counter.1 += count;
+        return true;
+    }
 
-            info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+    // set maximum in-flight orders per instrument and how long to hold an order waiting for a slot
+    pub fn set_max_in_flight_per_instrument(&mut self, max_in_flight_per_instrument: Option<u32>, in_flight_wait: Duration) {
+        self.max_in_flight_per_instrument = max_in_flight_per_instrument;
+        self.in_flight_wait = in_flight_wait;
+    }
 
-            // run exchange ordering
-            match self.exchange.limit_buy_fok(instrument_symbol.clone(), qty, price) {
-                Ok(answer) => {
-                    info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                    info!("arbitrage_ordering - order transaction. {:?}", answer);
-                    Ok(answer)
-                }
-                Err(err) => {
-                    error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                    error!("arbitrage_ordering - arbitrage transaction. {:?}", transaction);
-                    self.display_error(err);
-                    Err("failed".to_string())
-                }
+    // acquire in-flight slot, holding until an open order resolves or the wait expires
+    fn acquire_in_flight(&self, symbol: &String) -> bool {
+        let limit = match self.max_in_flight_per_instrument {
+            Some(l) => l,
+            None => return true
+        };
+
+        let (lock, condvar) = &*self.in_flight;
+        let start_date = Instant::now();
+        loop {
+            // open orders resolved since the last check give their slot back
+            self.release_resolved_orders(symbol);
+
+            let mut in_flight = lock.lock().unwrap();
+            if *in_flight.get(symbol).unwrap_or(&0) < limit {
+                *in_flight.entry(symbol.clone()).or_insert(0) += 1;
+                return true;
             }
-        } else if operation == "SELL" {
+            let remaining = match self.in_flight_wait.checked_sub(start_date.elapsed()) {
+                Some(r) => r,
+                None => {
+                    warn!("arbitrage_ordering - in-flight limit reached, order skipped. symbol: {}, limit: {}", symbol, limit);
+                    return false;
+                }
+            };
+            debug!("arbitrage_ordering - in-flight limit reached, order held. symbol: {}", symbol);
+            drop(condvar.wait_timeout(in_flight, remaining.min(self.order_status_poll_interval)).unwrap());
+        }
+    }
 
-            // setup order parameters
-            let price = transaction.get_price();
-            let qty = transaction.get_qty_to_execute();
+    // keep the in-flight slot of an order still open on the exchange
+    fn track_open_order(&self, symbol: &String, order_id: u64) {
+        self.open_orders.lock().unwrap().entry(symbol.clone()).or_insert_with(Vec::new).push(order_id);
+    }
 
-            info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+    // release the in-flight slot of a tracked order once it is resolved
+    fn release_order(&self, symbol: &String, order_id: u64) {
+        let tracked = match self.open_orders.lock().unwrap().get_mut(symbol) {
+            Some(order_ids) => match order_ids.iter().position(|id| *id == order_id) {
+                Some(position) => {
+                    order_ids.remove(position);
+                    true
+                }
+                None => false
+            },
+            None => false
+        };
+        if tracked {
+            self.release_in_flight(symbol);
+        }
+    }
 
-            // run exchange ordering
-            match self.exchange.limit_sell_fok(instrument_symbol.clone(), qty, price) {
-                Ok(answer) => {
-                    info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                    info!("arbitrage_ordering - order transaction. {:?}", answer);
-                    Ok(answer)
+    // query the open orders of an instrument, an order status error keeps the order open
+    fn release_resolved_orders(&self, symbol: &String) {
+        let order_ids = match self.open_orders.lock().unwrap().get(symbol) {
+            Some(order_ids) => order_ids.clone(),
+            None => return
+        };
+        for order_id in order_ids {
+            match self.exchange.order_status(symbol.clone(), order_id) {
+                Ok(order_status) if !is_open(&order_status.status) => {
+                    debug!("arbitrage_ordering - in-flight order resolved. symbol: {}, order_id: {}, status: {}", symbol, order_id, order_status.status);
+                    self.release_order(symbol, order_id);
                 }
+                Ok(_) => (),
                 Err(err) => {
-                    error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
-                    error!("arbitrage_ordering - arbitrage transaction. {:?}", transaction);
-                    self.display_error(err);
-                    Err("failed".to_string())
+                    warn!("arbitrage_ordering - in-flight order status error. symbol: {}, order_id: {}, error: {}", symbol, order_id, err);
                 }
             }
-        } else {
-            error!("{} failed to recognize transaction", instrument_symbol);
-            Err("failed".to_string())
         }
     }
 
-    // reset arbitrage execution
-    pub fn reset(&self, arbitrage_execution: &ArbitrageTransactionResult) {}
-
-    // update balance
-    pub fn update_balances(&mut self) {
-        info!("arbitrage_ordering - updating balances ...");
+    // release in-flight slot
+    fn release_in_flight(&self, symbol: &String) {
+        if self.max_in_flight_per_instrument.is_none() {
+            return;
+        }
 
-        match self.exchange.get_account() {
-            Ok(answer) => {
-                for balance in answer.balances {
-                    debug!("balance: {:?}", balance);
-                    let mut amount = balance.free.parse::<f32>().unwrap_or_default();
-                    self.balances.insert(balance.asset, amount);
-                }
-            }
-            Err(err) => {
-                self.display_error(err);
+        let (lock, condvar) = &*self.in_flight;
+        let mut in_flight = lock.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(symbol) {
+            if *count > 0 {
+                *count -= 1;
             }
         }
+        condvar.notify_all();
     }
 
-    pub fn get_balance(&self, asset: &String) -> Option<&f32> {
-        let a = asset.replace("BINANCE_", "");
-        let balance = self.balances.get(&a);
-        match balance {
-            Some(b) => return Some(b),
-            None => {
-                warn!("arbitrage_ordering - asset {} not found!", a);
-                return None;
-            }
-        };
+    // set cycle-wide deadline for parallel execution
+    pub fn set_cycle_timeout(&mut self, cycle_timeout: Option<Duration>) {
+        self.cycle_timeout = cycle_timeout;
     }
 
-    fn display_error(&self, err: Error) {
-        match err.0 {
-            BinanceLibErrorKind::BinanceError(code, msg, response) => match code {
-                _ => error!("arbitrage_ordering - binance error. error code: {}, msg: {}", code, msg),
-            },
-            BinanceLibErrorKind::Msg(msg) => {
-                error!("arbitrage_ordering - lib error. error: {}", msg)
-            }
-            _ => error!("arbitrage_ordering - other error. error: {}.", err.0),
-        };
+    // set maximum number of profits executed per tick batch
+    pub fn set_max_concurrent_executions(&mut self, max_concurrent_executions: Option<usize>) {
+        self.max_concurrent_executions = max_concurrent_executions;
     }
 
-    fn cancel_pending_transactions(&self, results: HashMap<u32, (Transaction, f32)>) {
-        // cancelling pending transactions
-        info!("arbitrage_ordering - cancelling pending transactions ...");
-        let start_date = Instant::now();
-
-
-        for (key, v) in results.iter() {
-            let (transaction, qty) = v;
+    // set maximum number of profits kept while a cycle is in flight
+    pub fn set_max_pending_profits(&mut self, max_pending_profits: usize) {
+        self.max_pending_profits = max_pending_profits;
+    }
 
-            debug!("{:?}", transaction);
-            let symbol = &transaction.symbol;
-            let order_id = &transaction.order_id;
+    // return number of profits dropped because the pending queue was full
+    pub fn get_dropped_profits(&self) -> u64 {
+        return self.dropped_profits;
+    }
 
-            /// get order status
-            match self.exchange.order_status(symbol, *order_id) {
-                Ok(order) => {
-                    debug!("{:?}", order);
-                    let side = order.side;
+    // queue profit, dropping the least profitable when the queue is full
+    fn queue_pending_profit(&mut self, arbitrage_profit: ArbitrageProfit) {
+        self.pending_profits.push_back(arbitrage_profit);
+        if self.pending_profits.len() <= self.max_pending_profits {
+            return;
+        }
 
-                    // cancel order if it is pending
-                    if order.status == "NEW" {
-                        match self.exchange.cancel_order(symbol, *order_id) {
-                            Ok(order_canceled) => {
-                                warn!("arbitrage_ordering - {} : {:?}", key, order_canceled);
-                            }
-                            Err(e) => {
-                                error!("arbitrage_ordering - {}", e)
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("{}", e);
-                }
+        let profit_pct = |p: &ArbitrageProfit| p.get_profit() / p.get_qty_in();
+        let least = self.pending_profits.iter().enumerate()
+            .min_by(|(_, a), (_, b)| profit_pct(a).partial_cmp(&profit_pct(b)).unwrap_or(Ordering::Equal))
+            .map(|(index, _)| index);
+        if let Some(index) = least {
+            if let Some(dropped) = self.pending_profits.remove(index) {
+                self.dropped_profits += 1;
+                warn!("arbitrage_ordering - pending queue full, profit dropped. name: {}, profit: {}, dropped: {}", dropped.get_name(), dropped.get_profit(), self.dropped_profits);
             }
         }
-        info!("arbitrage_ordering - orders transactions cancelled. elapsed_time: {:?}", start_date.elapsed());
     }
 
-    // Clean balances
-    fn clean_balances(&self, arbitrage_profit: &ArbitrageProfit) {
-        info!("arbitrage_ordering - cleaning balances ...");
-        let start_date = Instant::now();
+    // return next arbitrage profit, keeping only the most profitable of each batch
+    fn next_arbitrage_profit(&mut self) -> Result<ArbitrageProfit, RecvTimeoutError> {
+        if let Some(p) = self.pending_profits.pop_front() {
+            return Ok(p);
+        }
 
-        for asset in &arbitrage_profit.get_asset_list() {
-            if asset != "BTC" {
-                info!("arbitrage_ordering - getting balance. asset: {}", asset);
-                match self.exchange.get_balance(asset) {
-                    Ok(balance) => {
-                        // balance
-                        info!("arbitrage_ordering - {:?}", balance);
+        let first = self.arbitrage_profit_receiver.recv_timeout(SHUTDOWN_POLL_INTERVAL)?;
+        let max_concurrent_executions = match self.max_concurrent_executions {
+            Some(m) => m,
+            None => return Ok(first)
+        };
 
-                        // amount to sell
-                        let amount = balance.free.parse::<f32>().unwrap_or_default();
+        // batch of pending profits
+        let mut batch: Vec<ArbitrageProfit> = vec![first];
+        batch.extend(self.arbitrage_profit_receiver.try_iter());
 
-                        // instrument to use
-                        let instrument_id_str = format!("BINANCE_{}_BTC", asset).to_string();
+        if batch.len() > max_concurrent_executions {
+            let profit_pct = |p: &ArbitrageProfit| p.get_profit() / p.get_qty_in();
+            batch.sort_by(|a, b| profit_pct(b).partial_cmp(&profit_pct(a)).unwrap_or(Ordering::Equal));
+            info!("arbitrage_ordering - batch limited. received: {}, executed: {}", batch.len(), max_concurrent_executions);
+            batch.truncate(max_concurrent_executions);
+        }
 
-                        // normalize qty
-                        let qty = self.normalize_qty(instrument_id_str, amount);
+        for p in batch {
+            self.queue_pending_profit(p);
+        }
+        return Ok(self.pending_profits.pop_front().unwrap());
+    }
 
-                        let symbol = format!("{}{}", asset, "BTC");
+    // set per-asset amount kept out of the balance clean up
+    pub fn set_keep_balances(&mut self, keep_balances: HashMap<String, f64>) {
+        self.keep_balances = keep_balances;
+    }
 
-                        // sell if qty > 0
-                        if qty > 0.0 {
-                            match self.exchange.market_sell(symbol, qty) {
-                                Ok(answer) => {
-                                    debug!("{:?}", answer);
-                                }
-                                Err(err) => {
-                                    error!("arbitrage_ordering - market sell failure. {:?}", err.1);
-                                }
+    // set per-asset clean up priority, e.g. volatility from referencedata, the highest is reverted first
+    pub fn set_cleanup_priority(&mut self, cleanup_priority: HashMap<String, f64>) {
+        self.cleanup_priority = cleanup_priority;
+    }
+
+    // return assets of the cycle in clean up order, highest priority first, unknown assets last in cycle order
+    pub fn get_cleanup_order(&self, arbitrage_profit: &ArbitrageProfit) -> Vec<String> {
+        let mut asset_list = arbitrage_profit.get_asset_list(&self.exchange.get_prefix());
+        asset_list.sort_by(|a, b| {
+            let priority_a = self.cleanup_priority.get(a).cloned().unwrap_or(std::f64::MIN);
+            let priority_b = self.cleanup_priority.get(b).cloned().unwrap_or(std::f64::MIN);
+            priority_b.partial_cmp(&priority_a).unwrap_or(Ordering::Equal)
+        });
+        return asset_list;
+    }
+
+    // set maximum balance age before ordering
+    pub fn set_max_balance_age(&mut self, max_balance_age: Option<Duration>) {
+        self.max_balance_age = max_balance_age;
+    }
+
+    // check balances are fresh, forcing a refresh when stale
+    fn ensure_fresh_balances(&mut self) -> bool {
+        let max_balance_age = match self.max_balance_age {
+            Some(m) => m,
+            None => return true
+        };
+
+        if self.is_balances_fresh(max_balance_age) {
+            return true;
+        }
+
+        warn!("arbitrage_ordering - balances stale, refreshing. updated_at: {:?}", self.balances_updated_at.read().unwrap());
+        self.update_balances();
+        if self.is_balances_fresh(max_balance_age) {
+            return true;
+        }
+
+        error!("arbitrage_ordering - balances stale, ordering skipped. updated_at: {:?}", self.balances_updated_at.read().unwrap());
+        return false;
+    }
+
+    // check balances updated within the maximum age
+    fn is_balances_fresh(&self, max_balance_age: Duration) -> bool {
+        match *self.balances_updated_at.read().unwrap() {
+            Some(updated_at) => {
+                let age = self.clock.now().signed_duration_since(updated_at).to_std().unwrap_or(Duration::from_secs(0));
+                age <= max_balance_age
+            }
+            None => false
+        }
+    }
+
+    // return cumulative realized pnl per start asset
+    pub fn get_realized_pnl(&self) -> HashMap<String, f64> {
+        return self.realized_pnl.lock().unwrap().clone();
+    }
+
+    // return cumulative realized pnl handle, readable once the ordering runs in its own thread
+    pub fn get_realized_pnl_handle(&self) -> Arc<Mutex<HashMap<String, f64>>> {
+        return Arc::clone(&self.realized_pnl);
+    }
+
+    // add start asset balance change to the cumulative realized pnl
+    fn add_realized_pnl(&self, start_asset: &String, balance_before: f64) {
+        let balance_after = self.get_balance(start_asset).unwrap_or(balance_before);
+        let pnl = balance_after - balance_before;
+
+        let mut realized_pnl = self.realized_pnl.lock().unwrap();
+        let total = realized_pnl.entry(start_asset.clone()).or_insert(0.0);
+        *total += pnl;
+        info!("arbitrage_ordering - realized pnl. asset: {}, pnl: {}, total: {}", start_asset, pnl, total);
+    }
+
+    // set downstream liquidity check, top-of-book qty of legs after the first must cover factor x qty to execute
+    pub fn set_downstream_liquidity_factor(&mut self, downstream_liquidity_factor: Option<f64>) {
+        self.downstream_liquidity_factor = downstream_liquidity_factor;
+    }
+
+    // check legs after the first still have enough top-of-book qty
+    fn has_downstream_liquidity(&self, arbitrage_profit: &ArbitrageProfit) -> bool {
+        let factor = match self.downstream_liquidity_factor {
+            Some(f) => f,
+            None => return true
+        };
+
+        for transaction in arbitrage_profit.get_transaction_result_list().iter().skip(1) {
+            let available_qty = match self.get_quote(transaction.get_instrument()) {
+                Some(quote) => {
+                    if transaction.get_operation() == "BUY" {
+                        to_f64(quote.get_ask_qty())
+                    } else {
+                        to_f64(quote.get_bid_qty())
+                    }
+                }
+                None => 0.0
+            };
+
+            if available_qty < transaction.get_qty_to_execute() * factor {
+                warn!("arbitrage_ordering - thin downstream liquidity, arbitrage skipped. name: {}, leg: {}, available: {}, required: {}",
+                      arbitrage_profit.get_name(), transaction.get_name(), available_qty, transaction.get_qty_to_execute() * factor);
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // return value of qty of asset in start asset terms from the quote cache, none if no direct market
+    pub fn get_value_in(&self, asset: &String, qty: f64, start_asset: &String) -> Option<f64> {
+        let prefix = self.exchange.get_prefix();
+        let asset = strip_prefix(asset, &prefix);
+        let start_asset = strip_prefix(start_asset, &prefix);
+        if asset == start_asset {
+            return Some(qty);
+        }
+
+        // asset is base, sell at bid
+        if let Some(quote) = self.get_quote(&format!("{}{}_{}", prefix, asset, start_asset)) {
+            if quote.get_bid_price() > 0.0 {
+                return Some(qty * to_f64(quote.get_bid_price()));
+            }
+        }
+
+        // asset is quote, buy at ask
+        if let Some(quote) = self.get_quote(&format!("{}{}_{}", prefix, start_asset, asset)) {
+            if quote.get_ask_price() > 0.0 {
+                return Some(qty / to_f64(quote.get_ask_price()));
+            }
+        }
+        return None;
+    }
+
+    // return total exposure in start asset terms, inventory without a direct market is left out
+    pub fn get_exposure(&self, start_asset: &String) -> f64 {
+        let mut exposure: f64 = 0.0;
+        let balances = self.balances.read().unwrap().clone();
+        for (asset, qty) in &balances {
+            if *qty <= 0.0 {
+                continue;
+            }
+            match self.get_value_in(asset, *qty, start_asset) {
+                Some(value) => exposure += value,
+                None => warn!("arbitrage_ordering - exposure, asset not valued. asset: {}, qty: {}", asset, qty)
+            }
+        }
+        info!("arbitrage_ordering - exposure. start_asset: {}, exposure: {}", start_asset, exposure);
+        return exposure;
+    }
+
+    // return latest quote
+    pub fn get_quote(&self, instrument: &String) -> Option<MarketBBO> {
+        match self.quote_cache.read() {
+            Ok(quote_cache) => quote_cache.get(instrument).cloned(),
+            Err(err) => {
+                error!("arbitrage_ordering - quote cache error. error: {}", err);
+                None
+            }
+        }
+    }
+
+    pub fn start(&mut self) {
+        info!("arbitrage_ordering - started.");
+
+        loop {
+            if self.shutdown.load(atomic::Ordering::SeqCst) {
+                break;
+            }
+
+            match self.next_arbitrage_profit() {
+                Ok(p) => {
+                    // stale balances
+                    if !self.ensure_fresh_balances() {
+                        continue;
+                    }
+
+                    // start balance
+                    if !self.has_start_balance(&p) {
+                        continue;
+                    }
+
+                    // downstream liquidity
+                    if !self.has_downstream_liquidity(&p) {
+                        continue;
+                    }
+
+                    // daily order limit
+                    if !self.acquire_daily_orders(p.get_transaction_result_list().len() as u32) {
+                        continue;
+                    }
+
+                    // execute, a child of the profit emission span, leg spans are children of the cycle span
+                    let execution_span = arbitrage_tracing::start_span(arbitrage_tracing::CYCLE_EXECUTION, p.get_trace_context(), arbitrage_tracing::cycle_attributes(&p));
+                    {
+                        let _execution_guard = execution_span.clone().attach();
+                        match self.mode {
+                            ExecutionMode::PARALLEL => {
+                                self.execute_parallel(&p);
+                            }
+                            ExecutionMode::SEQUENTIAL => {
+                                self.execute_sequential(&p);
                             }
                         }
                     }
-                    Err(e) => {
-                        error!("failed to get balance {:?}", e);
+                    arbitrage_tracing::end_span(&execution_span);
+                    self.last_arbitrage_profit = Some(p);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => {
+                    error!("arbitrage_ordering - failed to recv transaction. error: {:?}", err);
+                    break;
+                }
+            }
+        }
+
+        // legs are joined by the executions, revert what the last cycle left
+        info!("arbitrage_ordering - stopping. pending: {}", self.pending_profits.len());
+        self.pending_profits.clear();
+        if let Some(p) = self.last_arbitrage_profit.take() {
+            self.clean_balances(&p);
+        }
+        info!("arbitrage_ordering - stopped.");
+    }
+
+    // collect every leg result, legs reporting after the deadline are cancelled and reverted as they come in
+    fn watch_parallel_cycle(&self, arbitrage_profit: &ArbitrageProfit, result_receiver: &Receiver<(usize, Result<Transaction, OrderingError>)>, leg_count: usize, deadline: Option<Instant>) -> Vec<Result<Transaction, OrderingError>> {
+        let mut results: Vec<Option<Result<Transaction, OrderingError>>> = vec![None; leg_count];
+        let mut reported: usize = 0;
+        let mut timed_out = false;
+
+        while reported < leg_count {
+            let received = match deadline {
+                Some(deadline) if !timed_out => result_receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                _ => result_receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            match received {
+                Ok((index, result)) => {
+                    // late leg, its order is still in flight
+                    if timed_out {
+                        warn!("arbitrage_ordering - late leg reported. name: {}, index: {}", arbitrage_profit.get_name(), index);
+                        self.abort_parallel_leg(arbitrage_profit, index, &result);
+                    }
+                    results[index] = Some(result);
+                    reported += 1;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!("arbitrage_ordering - cycle timeout. name: {}, resolved: {}/{}", arbitrage_profit.get_name(), reported, leg_count);
+                    timed_out = true;
+
+                    // abort legs already reported
+                    for (index, result) in results.iter().enumerate() {
+                        if let Some(result) = result {
+                            self.abort_parallel_leg(arbitrage_profit, index, result);
+                        }
                     }
                 }
+                // leg thread gone without reporting
+                Err(RecvTimeoutError::Disconnected) => break
             }
         }
-        info!("arbitrage_ordering - balances cleaned. elapsed_time: {:?}", start_date.elapsed());
+
+        return results.into_iter().map(|result| result.unwrap_or(Err(OrderingError::Panicked))).collect();
     }
 
-    fn normalize_qty(&self, instrument_id: String, qty: f32) -> f32 {
+    // cancel the resting order of a timed out leg and revert its filled quantity
+    fn abort_parallel_leg(&self, arbitrage_profit: &ArbitrageProfit, index: usize, result: &Result<Transaction, OrderingError>) {
+        if let Ok(order) = result {
+            let transaction = &arbitrage_profit.get_transaction_result_list()[index];
+
+            // cancel resting order
+            if (order.status == "NEW") | (order.status == "PARTIALLY_FILLED") {
+                match self.exchange.cancel_order(order.symbol.clone(), order.order_id) {
+                    Ok(order_cancelled) => {
+                        warn!("arbitrage_ordering - cancelling order. {:?}", order_cancelled);
+                    }
+                    Err(err) => {
+                        error!("arbitrage_ordering - cancel order error. error: {}", err)
+                    }
+                }
+                self.release_order(&order.symbol, order.order_id);
+            }
 
-        // find instrument
-        let instrument: &Instrument = self.referencedata.get_instrument_by_id(instrument_id).unwrap();
-        let step_size = instrument.get_step_size();
+            // revert filled quantity
+            let executed_qty = order.executed_qty;
+            if executed_qty > 0.0 {
+                let start_asset = arbitrage_profit.get_start_asset(&self.exchange.get_prefix());
+                self.revert_to_start_asset(order.symbol.clone(), transaction.get_operation().to_string(), executed_qty, &start_asset);
+            }
+        }
+    }
 
-        //check step size
-        if !step_size.is_nan() {
-            if step_size == 1.0 {
-                return qty.trunc();
+    // parallel execution, one thread per transaction, returning each leg result in order
+    pub fn execute_parallel(&mut self, arbitrage_profit: &ArbitrageProfit) -> Vec<Result<Transaction, OrderingError>> {
+        self.busy = true;
+
+        // start asset balance before execution
+        let start_asset = arbitrage_profit.get_start_asset(&self.exchange.get_prefix());
+        let balance_before = self.get_balance(&start_asset).unwrap_or(0.0);
+
+        info!("arbitrage_ordering - parallel execution started.");
+        let start_date = Instant::now();
+
+        // leg results
+        let (result_sender, result_receiver) = crossbeam_channel::unbounded();
+
+        // legs borrow the ordering, the scope joins them before the balances are touched again
+        let ordering: &ArbitrageOrdering = self;
+        let results = thread::scope(|scope| {
+            let mut children = vec![];
+
+            for t in arbitrage_profit.get_transaction_result_list() {
+                let transaction = t.clone();
+                let c_result_sender = result_sender.clone();
+                let index = children.len();
+                let leg_span = arbitrage_tracing::start_span(arbitrage_tracing::ORDER_LEG, &Context::current(), arbitrage_tracing::leg_attributes(arbitrage_profit, t));
+
+                children.push(scope.spawn(move || {
+                    info!("arbitrage_ordering - executing transaction. transaction: {:?}", transaction);
+
+                    // same placement path as the sequential legs
+                    let result = ordering.place_leg(arbitrage_profit, &transaction);
+
+                    // report leg, the receiver is gone only once the cycle stopped watching
+                    if let Err(err) = c_result_sender.send((index, result.clone())) {
+                        warn!("arbitrage_ordering - leg result not reported. uuid: {}, index: {}, error: {}", transaction.get_uuid().to_string(), index, err);
+                    }
+
+                    arbitrage_tracing::end_span(&leg_span);
+                    result
+                }));
+
+                // sleep between transactions
+                thread::sleep(ordering.sleep_between_transactions);
+            }
+
+            // legs report through the channel only
+            drop(result_sender);
+
+            // cycle watchdog
+            let deadline = ordering.cycle_timeout.map(|cycle_timeout| start_date + cycle_timeout);
+            let results = ordering.watch_parallel_cycle(arbitrage_profit, &result_receiver, children.len(), deadline);
+
+            // every leg reported, joining only reaps the threads
+            for child in children {
+                if let Err(err) = child.join() {
+                    error!("arbitrage_ordering - transaction thread panicked. error: {:?}", err);
+                }
+            }
+            results
+        });
+
+        // arbitrage info
+        info!("arbitrage_ordering - parallel executions finished. duration: {:?}", start_date.elapsed());
+
+        // revert
+        self.clean_balances(arbitrage_profit);
+
+        // update balances after transactions complete
+        self.update_balances();
+
+        // realized pnl
+        self.add_realized_pnl(&start_asset, balance_before);
+
+        // remove busy
+        self.busy = false;
+
+        return results;
+    }
+
+    // sequential execution
+    pub fn execute_sequential(&mut self, arbitrage_profit: &ArbitrageProfit) {
+        info!("arbitrage_ordering - executing.");
+
+        self.busy = true;
+        let start_date = Instant::now();
+
+        // start asset balance before execution
+        let start_asset = arbitrage_profit.get_start_asset(&self.exchange.get_prefix());
+        let balance_before = self.get_balance(&start_asset).unwrap_or(0.0);
+
+        // results
+        let mut results: HashMap<u32, (Transaction, f64)> = HashMap::new();
+        let mut transaction_nbr: u32 = 0;
+
+        // filled ratio of the chain, a partial fill shrinks every following leg
+        let mut fill_ratio: f64 = 1.0;
+
+        // run all transactions
+        for planned_transaction in arbitrage_profit.get_transaction_result_list() {
+            let scaled_transaction;
+            let arbitrage_transaction = if fill_ratio < 1.0 {
+                scaled_transaction = planned_transaction.scale(fill_ratio);
+                info!("arbitrage_ordering - leg scaled to previous fill. uuid: {}, fill_ratio: {}, qty: {}", planned_transaction.get_uuid().to_string(), fill_ratio, scaled_transaction.get_qty_to_execute());
+                &scaled_transaction
             } else {
-                let round_count: usize = step_size.to_string().len() - 2;
-                return math::round_down(qty, round_count);
+                planned_transaction
+            };
+            let leg_span = arbitrage_tracing::start_span(arbitrage_tracing::ORDER_LEG, &Context::current(), arbitrage_tracing::leg_attributes(arbitrage_profit, arbitrage_transaction));
+            let result = self.place_leg(arbitrage_profit, arbitrage_transaction);
+            arbitrage_tracing::end_span(&leg_span);
+
+            // result
+            match result {
+                Ok(order_transaction) => {
+                    let symbol = &order_transaction.symbol;
+                    let order_id = &order_transaction.order_id;
+
+                    // check order status
+                    match self.wait_order_status(symbol, *order_id) {
+                        Ok(order_status) => {
+                            // only a GTC can still be NEW, see TimeInForce
+                            if order_status.status == "NEW" {
+
+                                // cancel order
+                                match self.exchange.cancel_order(symbol.clone(), *order_id) {
+                                    Ok(order_cancelled) => {
+                                        warn!("arbitrage_ordering - cancelling order. {:?}", order_cancelled);
+                                    }
+                                    Err(err) => {
+                                        error!("arbitrage_ordering - cancel order error. error: {}", err)
+                                    }
+                                }
+                                self.release_order(symbol, *order_id);
+
+                                // nothing filled, the next leg has no input
+                                break;
+                            } else if (order_status.status == "PARTIALLY_FILLED") | ((order_status.status == "EXPIRED") & (order_status.executed_qty > 0.0)) {
+
+                                // cancel remaining qty, an expired IOC has nothing left
+                                let mut executed_qty = order_status.executed_qty;
+                                if order_status.status == "PARTIALLY_FILLED" {
+                                    match self.exchange.cancel_order(symbol.clone(), *order_id) {
+                                        Ok(order_cancelled) => {
+                                            warn!("arbitrage_ordering - cancelling partially filled order. {:?}", order_cancelled);
+                                        }
+                                        Err(err) => {
+                                            error!("arbitrage_ordering - cancel order error. error: {}", err)
+                                        }
+                                    }
+
+                                    // fills may have landed before the cancel
+                                    if let Ok(order_cancelled_status) = self.exchange.order_status(symbol.clone(), *order_id) {
+                                        executed_qty = order_cancelled_status.executed_qty;
+                                    }
+                                }
+                                self.release_order(symbol, *order_id);
+
+                                // propagate filled qty into the next legs
+                                if (order_status.orig_qty <= 0.0) | (executed_qty <= 0.0) {
+                                    break;
+                                }
+                                fill_ratio = fill_ratio * (executed_qty / order_status.orig_qty).min(1.0);
+                                warn!("arbitrage_ordering - partial fill. uuid: {}, symbol: {}, orig_qty: {}, executed_qty: {}, fill_ratio: {}",
+                                      arbitrage_transaction.get_uuid().to_string(), symbol, order_status.orig_qty, executed_qty, fill_ratio);
+                                continue;
+                            } else if order_status.status == "FILLED" {
+                                self.release_order(symbol, *order_id);
+
+                                // execution report
+                                if let Some(effective_spread) = arbitrage_transaction.get_effective_spread(order_status.price) {
+                                    info!("arbitrage_ordering - effective spread. uuid: {}, symbol: {}, fill_price: {}, detection_spread: {}, effective_spread: {}",
+                                          arbitrage_transaction.get_uuid().to_string(), symbol, order_status.price, arbitrage_transaction.get_spread(), effective_spread);
+                                }
+                                continue;
+                            } else {
+                                self.release_order(symbol, *order_id);
+
+                                // killed FOK (EXPIRED without fill), canceled or rejected, the next leg has no input
+                                warn!("arbitrage_ordering - order not filled, chain stopped. uuid: {}, symbol: {}, status: {}, executed_qty: {}",
+                                      arbitrage_transaction.get_uuid().to_string(), symbol, order_status.status, order_status.executed_qty);
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            error!("arbitrage_ordering - order status error. uuid: {}, symbol: {}, error: {}", arbitrage_transaction.get_uuid().to_string(), symbol, err);
+                            break;
+                        }
+                    }
+                }
+                Err(err) => {
+                    error!("arbitrage_ordering - order error. uuid: {}, error: {}", arbitrage_transaction.get_uuid().to_string(), err);
+                    break;
+                }
             }
-        } else {
-            return qty;
         }
+
+        info!("arbitrage_ordering - executed. duration: {:?}", start_date.elapsed());
+
+        // clean
+        //self.cancel_pending_transactions(results);
+
+        // revert
+        self.clean_balances(arbitrage_profit);
+
+        // update balances after transactions complete
+        self.update_balances();
+
+        // realized pnl
+        self.add_realized_pnl(&start_asset, balance_before);
+
+        // remove busy
+        self.busy = false;
     }
 
-    pub fn is_busy(&self) -> bool {
-        return self.busy;
+    // poll order status every poll interval while the order is open (NEW, PARTIALLY_FILLED), up to max wait
+    fn wait_order_status(&self, symbol: &String, order_id: u64) -> Result<Order, OrderingError> {
+        let start_date = Instant::now();
+
+        loop {
+            let elapsed = start_date.elapsed();
+            if elapsed < self.order_status_max_wait {
+                thread::sleep(self.order_status_poll_interval.min(self.order_status_max_wait - elapsed));
+            }
+            let order_status = self.exchange.order_status(symbol.clone(), order_id)?;
+            if !is_open(&order_status.status) | (start_date.elapsed() >= self.order_status_max_wait) {
+                debug!("arbitrage_ordering - order status. status: {}, elapsed: {:?}", order_status.status, start_date.elapsed());
+                return Ok(order_status);
+            }
+        }
     }
 
-    // revert to start asset
-    pub fn revert_to_start_asset(&self, symbol: String, side: String, qty: f32) {
-        let start_asset = "BTC".to_string();
+    // place a leg of the cycle and persist its execution
+    fn place_leg(&self, arbitrage_profit: &ArbitrageProfit, transaction: &ArbitrageTransactionResult) -> Result<Transaction, OrderingError> {
+        let result = self.execute_transaction(transaction);
 
-        // parameters
-        let qty_ex = qty;
-        let (base, quote) = symbol.split_at(symbol.len() - 3);
+        // persist execution
+        if let (Ok(order_transaction), Some(profit_sink)) = (&result, &self.profit_sink) {
+            profit_sink.add_execution(&ExecutionRecord::new(arbitrage_profit, transaction, order_transaction, self.persist_raw_response, self.run_id, self.clock.now()));
+        }
+        return result;
+    }
 
-        if (quote == start_asset) & (side == "SELL") {
-            // sell base to btc
-            let symbol = format!("{}{}", base, quote);
-            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
-            self.exchange.market_sell(symbol, qty_ex);
-        } else if (quote != start_asset) & (side == "BUY") {
-            // sell quote to btc
-            let symbol = format!("{}{}", quote, "BTC");
-            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
-            self.exchange.market_sell(symbol, qty_ex);
-        } else if (quote != start_asset) & (side == "SELL") {
-            // sell base to btc
-            let symbol = format!("{}{}", base, "BTC");
-            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
-            self.exchange.market_sell(symbol, qty_ex);
+    // execute transaction, requoting a killed FOK at the current book price
+    pub fn execute_transaction(&self, transaction: &ArbitrageTransactionResult) -> Result<Transaction, OrderingError> {
+        // in-flight limit
+        let symbol = transaction.get_exchange_code();
+
+        // client order id deduplication
+        let client_order_id = transaction.get_uuid().to_string();
+        if let Some(placement) = self.register_client_order(&client_order_id) {
+            warn!("arbitrage_ordering - duplicate client order id, not placing. client_order_id: {}", client_order_id);
+            return match placement {
+                Some((symbol, order_id)) => match self.exchange.order_status(symbol, order_id) {
+                    Ok(order_status) => {
+                        info!("arbitrage_ordering - already submitted. client_order_id: {}, status: {}", client_order_id, order_status.status);
+                        Err(OrderingError::AlreadySubmitted(Some(order_status.status)))
+                    }
+                    Err(err) => {
+                        warn!("arbitrage_ordering - already submitted, status unavailable. client_order_id: {}, error: {}", client_order_id, err);
+                        Err(OrderingError::AlreadySubmitted(None))
+                    }
+                },
+                None => Err(OrderingError::AlreadySubmitted(None))
+            };
+        }
+
+        if !self.acquire_in_flight(symbol) {
+            warn!("arbitrage_ordering - in-flight limit reached. symbol: {}", symbol);
+            return Err(OrderingError::InFlightLimit);
+        }
+
+        let mut result = self.place_order_with_retry(transaction, self.get_order_price(transaction));
+
+        let start_date = Instant::now();
+        let mut attempt: u32 = 0;
+        while (attempt < self.requote_attempts) & (start_date.elapsed() < self.requote_budget) {
+            // requote only killed orders, a partially filled IOC is not requoted
+            match &result {
+                Ok(answer) if (answer.status == "EXPIRED") & (answer.executed_qty == 0.0) => (),
+                _ => break
+            }
+
+            // current crossing price
+            let price = match self.get_requote_price(transaction) {
+                Some(p) => p,
+                None => break
+            };
+
+            attempt += 1;
+            warn!("arbitrage_ordering - requoting killed order. uuid: {}, attempt: {}, price: {}", transaction.get_uuid().to_string(), attempt, price);
+            result = self.place_order_with_retry(transaction, price);
+        }
+
+        // an order still open holds its slot until it resolves
+        match &result {
+            Ok(answer) if self.max_in_flight_per_instrument.is_some() & is_open(&answer.status) => self.track_open_order(symbol, answer.order_id),
+            _ => self.release_in_flight(symbol)
+        }
+
+        // exchange order of the client order id
+        if let Ok(answer) = &result {
+            self.set_client_order(&client_order_id, &answer.symbol, answer.order_id);
         }
+        return result;
     }
-}
\ No newline at end of file
+
+    // place order, retrying transient errors with exponential backoff
+    fn place_order_with_retry(&self, transaction: &ArbitrageTransactionResult, price: f64) -> Result<Transaction, OrderingError> {
+        let mut delay = self.retry_backoff;
+        let mut attempt: u32 = 0;
+
+        loop {
+            let result = self.place_order(transaction, price);
+            match &result {
+                Err(err) if err.is_transient() & (attempt < self.retry_attempts) => {
+                    attempt += 1;
+                    warn!("arbitrage_ordering - retrying transient error. uuid: {}, attempt: {}, delay: {:?}, error: {}", transaction.get_uuid().to_string(), attempt, delay, err);
+                    thread::sleep(delay);
+                    delay = delay * 2;
+                }
+                _ => return result
+            }
+        }
+    }
+
+    // return current crossing price from the quote cache
+    fn get_requote_price(&self, transaction: &ArbitrageTransactionResult) -> Option<f64> {
+        let quote = self.get_quote(transaction.get_instrument())?;
+        if transaction.get_operation() == "BUY" {
+            return Some(to_f64(quote.get_ask_price()));
+        } else if transaction.get_operation() == "SELL" {
+            return Some(to_f64(quote.get_bid_price()));
+        }
+        return None;
+    }
+
+    // estimate probability a FOK at price fully fills, from the top-of-book depth at or better than price
+    pub fn estimate_fok_fill_probability(&self, transaction: &ArbitrageTransactionResult, price: f64) -> f64 {
+        let quote = match self.get_quote(transaction.get_instrument()) {
+            Some(q) => q,
+            None => return 0.0
+        };
+
+        let depth = if transaction.get_operation() == "BUY" {
+            if to_f64(quote.get_ask_price()) <= price { to_f64(quote.get_ask_qty()) } else { 0.0 }
+        } else if transaction.get_operation() == "SELL" {
+            if to_f64(quote.get_bid_price()) >= price { to_f64(quote.get_bid_qty()) } else { 0.0 }
+        } else {
+            0.0
+        };
+        return fok_fill_probability(transaction.get_qty_to_execute(), depth);
+    }
+
+    // place order
+    fn place_order(&self, transaction: &ArbitrageTransactionResult, price: f64) -> Result<Transaction, OrderingError> {
+        if transaction.get_order_type() == &OrderType::MARKET {
+            return self.place_market_order(transaction);
+        }
+
+        // fill probability at decision time
+        let fill_probability = self.estimate_fok_fill_probability(transaction, price);
+        info!("arbitrage_ordering - fok fill probability. uuid: {}, symbol: {}, qty: {}, probability: {:.3}",
+              transaction.get_uuid().to_string(), transaction.get_exchange_code(), transaction.get_qty_to_execute(), fill_probability);
+
+        let instrument_symbol = transaction.get_exchange_code().to_string();
+
+        // buy transaction
+        let operation = transaction.get_operation();
+
+        // uuid
+        let uuid = transaction.get_uuid();
+
+        // setup order parameters
+        let qty = self.get_order_qty(transaction);
+        if qty <= 0.0 {
+            error!("arbitrage_ordering - invalid qty. uuid: {}, symbol: {}, qty: {}", uuid.to_string(), instrument_symbol, qty);
+            return Err(OrderingError::InvalidQty);
+        }
+
+        if operation == "BUY" {
+
+            info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+
+            // run exchange ordering
+            match limit_buy(self.exchange.as_ref(), transaction.get_time_in_force(), instrument_symbol.clone(), qty, price) {
+                Ok(answer) => {
+                    info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+                    info!("arbitrage_ordering - order transaction. {:?}", answer);
+                    Ok(answer)
+                }
+                Err(err) => {
+                    error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}, error: {}", uuid.to_string(), operation, instrument_symbol, price, qty, err);
+                    error!("arbitrage_ordering - arbitrage transaction. {:?}", transaction);
+                    Err(err)
+                }
+            }
+        } else if operation == "SELL" {
+
+            info!("arbitrage_ordering - running. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+
+            // run exchange ordering
+            match limit_sell(self.exchange.as_ref(), transaction.get_time_in_force(), instrument_symbol.clone(), qty, price) {
+                Ok(answer) => {
+                    info!("arbitrage_ordering - executed. uuid: {}, side: {},symbol: {},price: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, price, qty);
+                    info!("arbitrage_ordering - order transaction. {:?}", answer);
+                    Ok(answer)
+                }
+                Err(err) => {
+                    error!("arbitrage_ordering - failed. uuid: {}, side: {},symbol: {},price: {}, qty:{}, error: {}", uuid.to_string(), operation, instrument_symbol, price, qty, err);
+                    error!("arbitrage_ordering - arbitrage transaction. {:?}", transaction);
+                    Err(err)
+                }
+            }
+        } else {
+            error!("{} failed to recognize transaction", instrument_symbol);
+            Err(OrderingError::UnknownOperation(operation.clone()))
+        }
+    }
+
+    // place market order
+    fn place_market_order(&self, transaction: &ArbitrageTransactionResult) -> Result<Transaction, OrderingError> {
+        let instrument_symbol = transaction.get_exchange_code().to_string();
+        let operation = transaction.get_operation();
+        let uuid = transaction.get_uuid();
+        let qty = self.get_order_qty(transaction);
+        if qty <= 0.0 {
+            error!("arbitrage_ordering - invalid qty. uuid: {}, symbol: {}, qty: {}", uuid.to_string(), instrument_symbol, qty);
+            return Err(OrderingError::InvalidQty);
+        }
+
+        info!("arbitrage_ordering - running market. uuid: {}, side: {},symbol: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, qty);
+
+        let result = if operation == "BUY" {
+            self.exchange.market_buy(instrument_symbol.clone(), qty)
+        } else if operation == "SELL" {
+            self.exchange.market_sell(instrument_symbol.clone(), qty)
+        } else {
+            error!("{} failed to recognize transaction", instrument_symbol);
+            return Err(OrderingError::UnknownOperation(operation.clone()));
+        };
+
+        match result {
+            Ok(answer) => {
+                info!("arbitrage_ordering - executed market. uuid: {}, side: {},symbol: {}, qty:{}", uuid.to_string(), operation, instrument_symbol, qty);
+                info!("arbitrage_ordering - order transaction. {:?}", answer);
+                Ok(answer)
+            }
+            Err(err) => {
+                error!("arbitrage_ordering - failed market. uuid: {}, side: {},symbol: {}, qty:{}, error: {}", uuid.to_string(), operation, instrument_symbol, qty, err);
+                Err(err)
+            }
+        }
+    }
+
+    // reset arbitrage execution
+    pub fn reset(&self, arbitrage_execution: &ArbitrageTransactionResult) {}
+
+    // update balance
+    pub fn update_balances(&mut self) {
+        info!("arbitrage_ordering - updating balances ...");
+
+        match self.exchange.get_account() {
+            Ok(answer) => {
+                self.set_balances(answer);
+            }
+            Err(err) => {
+                error!("arbitrage_ordering - balances update failed. error: {}", err);
+                self.estimate_balances();
+            }
+        }
+    }
+
+    // update balance, giving up after the timeout
+    pub fn update_balances_with_timeout(&mut self, timeout: Duration) -> bool {
+        info!("arbitrage_ordering - updating balances ... timeout: {:?}", timeout);
+
+        let exchange = Arc::clone(&self.exchange);
+        let (account_sender, account_receiver) = crossbeam_channel::bounded(1);
+        thread::spawn(move || {
+            // the receiver is gone once the update timed out, the late account is dropped
+            if account_sender.send(exchange.get_account()).is_err() {
+                debug!("arbitrage_ordering - late account dropped, balances update timed out.");
+            }
+        });
+
+        match account_receiver.recv_timeout(timeout) {
+            Ok(Ok(answer)) => {
+                self.set_balances(answer);
+                true
+            }
+            Ok(Err(err)) => {
+                error!("arbitrage_ordering - balances update failed. error: {}", err);
+                self.estimate_balances();
+                false
+            }
+            Err(_) => {
+                warn!("arbitrage_ordering - balances update timed out. timeout: {:?}", timeout);
+                self.estimate_balances();
+                false
+            }
+        }
+    }
+
+    // return handles on the balances, shared with the background refresh
+    fn get_balance_store(&self) -> BalanceStore {
+        return BalanceStore {
+            balances: Arc::clone(&self.balances),
+            balances_updated_at: Arc::clone(&self.balances_updated_at),
+            balances_estimated: Arc::clone(&self.balances_estimated),
+            clock: Arc::clone(&self.clock),
+            profit_sink: self.profit_sink.clone(),
+        };
+    }
+
+    // set balances from account information
+    fn set_balances(&mut self, answer: AccountInformation) {
+        self.get_balance_store().set(answer);
+    }
+
+    // estimate balances from the last snapshot and the executions recorded since, the update time is left unchanged
+    fn estimate_balances(&mut self) -> bool {
+        let profit_sink = match &self.profit_sink {
+            Some(profit_sink) => Arc::clone(profit_sink),
+            None => return false
+        };
+        let balance_snapshot = match profit_sink.get_last_balance_snapshot() {
+            Some(balance_snapshot) => balance_snapshot,
+            None => {
+                warn!("arbitrage_ordering - no balance snapshot, balances not estimated.");
+                return false;
+            }
+        };
+        let executions = profit_sink.get_executions_since(balance_snapshot.create_at);
+        *self.balances.write().unwrap() = reconstruct_balances(&balance_snapshot, &executions, |symbol| self.get_base_quote(symbol));
+        self.balances_estimated.store(true, atomic::Ordering::SeqCst);
+        warn!("arbitrage_ordering - balances estimated. snapshot_at: {}, executions: {}", balance_snapshot.create_at, executions.len());
+        return true;
+    }
+
+    // check balances are estimated rather than reported by the exchange
+    pub fn is_balances_estimated(&self) -> bool {
+        return self.balances_estimated.load(atomic::Ordering::SeqCst);
+    }
+
+    // return balances, shared with the executor sizing the cycles
+    pub fn get_balances(&self) -> Arc<RwLock<HashMap<String, f64>>> {
+        return Arc::clone(&self.balances);
+    }
+
+    // return latest balance of the asset
+    pub fn get_balance(&self, asset: &String) -> Option<f64> {
+        let a = strip_prefix(asset, &self.exchange.get_prefix());
+        let balance = self.balances.read().unwrap().get(&a).cloned();
+        match balance {
+            Some(b) => return Some(b),
+            None => {
+                warn!("arbitrage_ordering - asset {} not found!", a);
+                return None;
+            }
+        };
+    }
+
+    fn cancel_pending_transactions(&self, results: HashMap<u32, (Transaction, f64)>) {
+        // cancelling pending transactions
+        info!("arbitrage_ordering - cancelling pending transactions ...");
+        let start_date = Instant::now();
+
+
+        for (key, v) in results.iter() {
+            let (transaction, qty) = v;
+
+            debug!("{:?}", transaction);
+            let symbol = &transaction.symbol;
+            let order_id = &transaction.order_id;
+
+            /// get order status
+            match self.exchange.order_status(symbol.clone(), *order_id) {
+                Ok(order) => {
+                    debug!("{:?}", order);
+                    let side = order.side;
+
+                    // cancel order if it is pending
+                    if order.status == "NEW" {
+                        match self.exchange.cancel_order(symbol.clone(), *order_id) {
+                            Ok(order_canceled) => {
+                                warn!("arbitrage_ordering - {} : {:?}", key, order_canceled);
+                            }
+                            Err(e) => {
+                                error!("arbitrage_ordering - {}", e)
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("{}", e);
+                }
+            }
+        }
+        info!("arbitrage_ordering - orders transactions cancelled. elapsed_time: {:?}", start_date.elapsed());
+    }
+
+    // Clean balances
+    fn clean_balances(&self, arbitrage_profit: &ArbitrageProfit) {
+        info!("arbitrage_ordering - cleaning balances ...");
+        let start_date = Instant::now();
+
+        // start asset
+        let start_asset = arbitrage_profit.get_start_asset(&self.exchange.get_prefix());
+
+        for asset in &self.get_cleanup_order(arbitrage_profit) {
+            if asset != &start_asset {
+                info!("arbitrage_ordering - getting balance. asset: {}", asset);
+                match self.exchange.get_balance(asset.clone()) {
+                    Ok(balance) => {
+                        // balance
+                        info!("arbitrage_ordering - {:?}", balance);
+
+                        // amount to sell above the kept amount
+                        let keep = self.keep_balances.get(asset).cloned().unwrap_or(0.0);
+                        let amount = (balance.free.parse::<f64>().unwrap_or_default() - keep).max(0.0);
+
+                        // instrument to use
+                        let instrument_id_str = format!("{}{}_{}", self.exchange.get_prefix(), asset, start_asset).to_string();
+
+                        // normalize qty, never send an unrounded qty
+                        let qty = match self.normalize_qty(&instrument_id_str, amount) {
+                            Some(q) => q,
+                            None => {
+                                error!("arbitrage_ordering - no step size, clean skipped. instrument: {}, amount: {}", instrument_id_str, amount);
+                                continue;
+                            }
+                        };
+
+                        let symbol = self.exchange.get_symbol(asset, &start_asset);
+
+                        // sell if qty > 0
+                        if qty > 0.0 {
+                            match self.exchange.market_sell(symbol, qty) {
+                                Ok(answer) => {
+                                    debug!("{:?}", answer);
+                                }
+                                Err(err) => {
+                                    error!("arbitrage_ordering - market sell failure. {}", err);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!("failed to get balance {:?}", e);
+                    }
+                }
+            }
+        }
+        info!("arbitrage_ordering - balances cleaned. elapsed_time: {:?}", start_date.elapsed());
+    }
+
+    // floor qty to the exchange increment, else the instrument step size, none if both are unknown
+    fn normalize_qty(&self, instrument_id: &String, qty: f64) -> Option<f64> {
+
+        // find increment
+        let step_size = match self.exchange.get_qty_increment(instrument_id) {
+            Some(qty_increment) => qty_increment,
+            None => match &self.referencedata {
+                Some(referencedata) => {
+                    let instrument: &Instrument = referencedata.get_instrument_by_id(instrument_id.clone())?;
+                    to_f64(instrument.get_step_size())
+                }
+                None => to_f64(self.quote_cache.read().unwrap().get(instrument_id)?.get_step_size())
+            }
+        };
+
+        //check step size
+        if step_size.is_nan() | (step_size <= 0.0) {
+            return None;
+        }
+        return Some(math::round_to_step(qty, step_size, &RoundingStrategy::FLOOR));
+    }
+
+    pub fn is_busy(&self) -> bool {
+        return self.busy;
+    }
+
+    // return base and quote assets of an exchange symbol from the referencedata instrument, else the quoted instruments
+    fn get_base_quote(&self, symbol: &String) -> Option<(String, String)> {
+        let instrument_list: Vec<String> = match &self.referencedata {
+            Some(referencedata) => referencedata.get_instrument_list().iter().map(|instrument_id| instrument_id.to_string()).collect(),
+            None => self.quote_cache.read().unwrap().keys().cloned().collect()
+        };
+        for instrument_id in instrument_list {
+            if let Some((base, quote)) = split_instrument_id(&instrument_id) {
+                if self.exchange.get_symbol(&base, &quote) == *symbol {
+                    return Some((base, quote));
+                }
+            }
+        }
+        return None;
+    }
+
+    // revert to the start asset of the cycle
+    pub fn revert_to_start_asset(&self, symbol: String, side: String, qty: f64, start_asset: &String) {
+        // parameters
+        let qty_ex = qty;
+        let (base, quote) = match self.get_base_quote(&symbol) {
+            Some(base_quote) => base_quote,
+            None => {
+                error!("arbitrage_ordering - revert failed, instrument not in referencedata. symbol: {}", symbol);
+                return;
+            }
+        };
+
+        if (&quote == start_asset) & (side == "SELL") {
+            // sell base to revert asset
+            let symbol = self.exchange.get_symbol(&base, &quote);
+            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
+            self.exchange.market_sell(symbol, qty_ex);
+        } else if (&quote != start_asset) & (side == "BUY") {
+            // sell quote to revert asset
+            let symbol = self.exchange.get_symbol(&quote, start_asset);
+            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
+            self.exchange.market_sell(symbol, qty_ex);
+        } else if (&quote != start_asset) & (side == "SELL") {
+            // sell base to revert asset
+            let symbol = self.exchange.get_symbol(&base, start_asset);
+            info!("arbitrage_ordering - market sell. symbol: {}, qty:{}", symbol, qty);
+            self.exchange.market_sell(symbol, qty_ex);
+        }
+    }
+}
+
+// resolve api credentials, empty keys fall back to BINANCE_API_KEY and BINANCE_SECRET_KEY
+fn resolve_credentials(api_key: String, secret_key: String) -> (String, String) {
+    let api_key = if api_key.is_empty() { env::var("BINANCE_API_KEY").unwrap_or_default() } else { api_key };
+    let secret_key = if secret_key.is_empty() { env::var("BINANCE_SECRET_KEY").unwrap_or_default() } else { secret_key };
+    return (api_key, secret_key);
+}
+
+// sleep between transactions, in microseconds, a missing or malformed value defaults to 10µs
+fn parse_sleep_between_transactions(value: Option<String>) -> Duration {
+    let micros = match value {
+        Some(s) => s.parse::<u64>().unwrap_or(10),
+        None => 10
+    };
+    return Duration::from_micros(micros);
+}
+
+// place limit buy with the time in force
+fn limit_buy(exchange: &dyn Exchange, time_in_force: &TimeInForce, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+    match time_in_force {
+        TimeInForce::FOK => exchange.limit_buy_fok(symbol, qty, price),
+        TimeInForce::IOC => exchange.limit_buy_ioc(symbol, qty, price),
+        TimeInForce::GTC => exchange.limit_buy(symbol, qty, price),
+    }
+}
+
+// place limit sell with the time in force
+fn limit_sell(exchange: &dyn Exchange, time_in_force: &TimeInForce, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+    match time_in_force {
+        TimeInForce::FOK => exchange.limit_sell_fok(symbol, qty, price),
+        TimeInForce::IOC => exchange.limit_sell_ioc(symbol, qty, price),
+        TimeInForce::GTC => exchange.limit_sell(symbol, qty, price),
+    }
+}
+
+// order still resting on the exchange
+fn is_open(status: &String) -> bool {
+    return (status == "NEW") | (status == "PARTIALLY_FILLED");
+}
+
+// return fraction of qty covered by the available depth, a fok needs all of it
+pub fn fok_fill_probability(qty: f64, depth: f64) -> f64 {
+    if qty <= 0.0 {
+        return 1.0;
+    }
+    if depth <= 0.0 {
+        return 0.0;
+    }
+    return (depth / qty).min(1.0);
+}
+
+// Balance Store, balances shared between the ordering and the background refresh
+struct BalanceStore {
+    balances: Arc<RwLock<HashMap<String, f64>>>,
+    balances_updated_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+    balances_estimated: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+    profit_sink: Option<Arc<dyn ProfitSink>>,
+}
+
+impl BalanceStore {
+    // set balances from account information
+    fn set(&self, answer: AccountInformation) {
+        {
+            let mut balances = self.balances.write().unwrap();
+            for balance in answer.balances {
+                debug!("balance: {:?}", balance);
+                let amount = balance.free.parse::<f64>().unwrap_or_default();
+                balances.insert(balance.asset, amount);
+            }
+        }
+        *self.balances_updated_at.write().unwrap() = Some(self.clock.now());
+        self.balances_estimated.store(false, atomic::Ordering::SeqCst);
+
+        // snapshot balances, base of the estimate when the account is unavailable
+        if let Some(profit_sink) = &self.profit_sink {
+            profit_sink.add_balance_snapshot(&BalanceSnapshot {
+                balances: self.balances.read().unwrap().clone(),
+                create_at: self.clock.now(),
+            });
+        }
+    }
+}
+
+// apply executions to the snapshot balances, executed qty of the raw response if kept, else the ordered qty, fees ignored
+pub fn reconstruct_balances<F>(balance_snapshot: &BalanceSnapshot, executions: &Vec<ExecutionRecord>, base_quote: F) -> HashMap<String, f64>
+    where F: Fn(&String) -> Option<(String, String)> {
+    let mut balances = balance_snapshot.balances.clone();
+    for execution in executions {
+        let (base, quote) = match base_quote(&execution.symbol) {
+            Some(base_quote) => base_quote,
+            None => {
+                warn!("arbitrage_ordering - unknown symbol, execution not applied. symbol: {}", execution.symbol);
+                continue;
+            }
+        };
+        let qty = match execution.get_raw_transaction() {
+            Some(transaction) => transaction.executed_qty,
+            None => execution.qty
+        };
+        let (base_delta, quote_delta) = if execution.operation == "BUY" {
+            (qty, -qty * execution.price)
+        } else if execution.operation == "SELL" {
+            (-qty, qty * execution.price)
+        } else {
+            continue;
+        };
+        *balances.entry(base).or_insert(0.0) += base_delta;
+        *balances.entry(quote).or_insert(0.0) += quote_delta;
+    }
+    return balances;
+}
+
+// split an instrument id EXCHANGE_BASE_QUOTE into base and quote
+pub fn split_instrument_id(instrument_id: &String) -> Option<(String, String)> {
+    let parts: Vec<&str> = instrument_id.split('_').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    return Some((parts[1].to_string(), parts[2].to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::izyfo_arbitrage::arbitrage::Arbitrage;
+    use crate::izyfo_arbitrage::arbitrage_clock::MockClock;
+    use crate::izyfo_arbitrage::arbitrage_testing::{self, push_filled_legs, run_ordering, MemoryProfitSink, MockExchange, QuoteFixture};
+
+    #[test]
+    fn execute_sequential_places_every_leg_in_order() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let placements = exchange.get_placements();
+        let methods: Vec<&str> = placements.iter().map(|call| call.method.as_str()).collect();
+        let symbols: Vec<&str> = placements.iter().map(|call| call.symbol.as_str()).collect();
+        assert_eq!(methods, vec!["limit_buy_fok", "limit_sell_fok", "limit_buy_fok"]);
+        assert_eq!(symbols, vec!["ETHBTC", "ETHUSDT", "BTCUSDT"]);
+        for (placement, transaction) in placements.iter().zip(arbitrage_profit.get_transaction_result_list()) {
+            assert_eq!(placement.qty, transaction.get_qty_to_execute());
+            assert_eq!(placement.price, transaction.get_price());
+        }
+        assert!(exchange.get_calls_of("cancel_order").is_empty());
+    }
+
+    #[test]
+    fn execute_sequential_scales_next_legs_to_partial_fill() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let transaction_list = arbitrage_profit.get_transaction_result_list();
+        let first_qty = transaction_list[0].get_qty_to_execute();
+
+        // first leg half filled then cancelled, the others filled
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, "NEW", first_qty, 0.0, 0.05)));
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "PARTIALLY_FILLED", first_qty, first_qty / 2.0, 0.05)));
+        exchange.push_cancel(Ok(arbitrage_testing::order_canceled("ETHBTC", 1)));
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "CANCELED", first_qty, first_qty / 2.0, 0.05)));
+        for (index, transaction) in transaction_list.iter().enumerate().skip(1) {
+            let qty = transaction.get_qty_to_execute() / 2.0;
+            exchange.push_filled(transaction.get_exchange_code(), index as u64 + 1, qty, transaction.get_price());
+        }
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let placements = exchange.get_placements();
+        assert_eq!(placements.len(), 3);
+        assert_eq!(exchange.get_calls_of("cancel_order").len(), 1);
+        assert_eq!(placements[0].qty, first_qty);
+        assert!((placements[1].qty - transaction_list[1].get_qty_to_execute() / 2.0).abs() < 1e-9);
+        assert!((placements[2].qty - transaction_list[2].get_qty_to_execute() / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn execute_sequential_stops_chain_on_rejected_leg() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first = &arbitrage_profit.get_transaction_result_list()[0];
+        exchange.push_filled(first.get_exchange_code(), 1, first.get_qty_to_execute(), first.get_price());
+        exchange.push_order(Err(OrderingError::BinanceError(-2010, "insufficient balance".to_string())));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let placements = exchange.get_placements();
+        assert_eq!(placements.len(), 2);
+        assert_eq!(placements[1].symbol, "ETHUSDT");
+    }
+
+    #[test]
+    fn order_status_waits_the_configured_poll_interval() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "FILLED", 1.0, 1.0, 0.05)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_order_status_poll_interval(Duration::from_millis(40));
+        arbitrage_ordering.set_order_status_max_wait(Duration::from_millis(1000));
+
+        let start_date = Instant::now();
+        let order_status = arbitrage_ordering.wait_order_status(&"ETHBTC".to_string(), 1).unwrap();
+
+        assert_eq!(order_status.status, "FILLED");
+        assert!(start_date.elapsed() >= Duration::from_millis(40));
+        assert!(start_date.elapsed() < Duration::from_millis(1000));
+        assert_eq!(exchange.get_calls_of("order_status").len(), 1);
+    }
+
+    #[test]
+    fn open_order_is_polled_until_max_wait() {
+        let exchange = Arc::new(MockExchange::new());
+        for _ in 0..100 {
+            exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "NEW", 1.0, 0.0, 0.05)));
+        }
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_order_status_poll_interval(Duration::from_millis(10));
+        arbitrage_ordering.set_order_status_max_wait(Duration::from_millis(60));
+
+        let start_date = Instant::now();
+        let order_status = arbitrage_ordering.wait_order_status(&"ETHBTC".to_string(), 1).unwrap();
+
+        assert_eq!(order_status.status, "NEW");
+        assert!(start_date.elapsed() >= Duration::from_millis(60));
+        let polls = exchange.get_calls_of("order_status").len();
+        assert!((polls > 1) & (polls <= 7), "polls: {}", polls);
+    }
+
+    #[test]
+    fn killed_fok_is_requoted_and_fills_on_second_attempt() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first = &arbitrage_profit.get_transaction_result_list()[0];
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, "EXPIRED", first.get_qty_to_execute(), 0.0, 0.05)));
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 2, "FILLED", first.get_qty_to_execute(), first.get_qty_to_execute(), 0.051)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_requote(2, Duration::from_secs(5));
+        arbitrage_ordering.quote_cache.write().unwrap().insert("BINANCE_ETH_BTC".to_string(), arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0505, 1000.0, 0.051, 1000.0));
+
+        let answer = arbitrage_ordering.execute_transaction(first).unwrap();
+
+        assert_eq!(answer.status, "FILLED");
+        let placements = exchange.get_placements();
+        assert_eq!(placements.len(), 2);
+        assert_eq!(placements[0].price, first.get_price());
+        assert!((placements[1].price - 0.051).abs() < 1e-12);
+    }
+
+    #[test]
+    fn killed_fok_gives_up_after_requote_attempts() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first = &arbitrage_profit.get_transaction_result_list()[0];
+        for order_id in 1..4 {
+            exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", order_id, "EXPIRED", first.get_qty_to_execute(), 0.0, 0.05)));
+        }
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_requote(1, Duration::from_secs(5));
+        arbitrage_ordering.quote_cache.write().unwrap().insert("BINANCE_ETH_BTC".to_string(), arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0505, 1000.0, 0.051, 1000.0));
+
+        let answer = arbitrage_ordering.execute_transaction(first).unwrap();
+
+        assert_eq!(answer.status, "EXPIRED");
+        assert_eq!(exchange.get_placements().len(), 2);
+    }
+
+    #[test]
+    fn second_order_on_instrument_is_held_while_first_in_flight() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_max_in_flight_per_instrument(Some(1), Duration::from_secs(5));
+        let symbol = "ETHBTC".to_string();
+        assert!(arbitrage_ordering.acquire_in_flight(&symbol));
+
+        // first order completes later on another thread
+        let in_flight = Arc::clone(&arbitrage_ordering.in_flight);
+        let release_handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let (lock, condvar) = &*in_flight;
+            *lock.lock().unwrap().get_mut("ETHBTC").unwrap() -= 1;
+            condvar.notify_all();
+        });
+
+        let start_date = Instant::now();
+        assert!(arbitrage_ordering.acquire_in_flight(&"BTCUSDT".to_string()));
+        assert!(start_date.elapsed() < Duration::from_millis(50));
+        assert!(arbitrage_ordering.acquire_in_flight(&symbol));
+        assert!(start_date.elapsed() >= Duration::from_millis(50));
+        release_handle.join().unwrap();
+    }
+
+    #[test]
+    fn order_is_skipped_when_in_flight_wait_expires() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first = &arbitrage_profit.get_transaction_result_list()[0];
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_max_in_flight_per_instrument(Some(1), Duration::from_millis(20));
+
+        // first order still resting, its slot is held
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, "NEW", first.get_qty_to_execute(), 0.0, 0.05)));
+        assert!(arbitrage_ordering.execute_transaction(first).is_ok());
+
+        assert!(matches!(arbitrage_ordering.execute_transaction(first), Err(OrderingError::InFlightLimit)));
+        assert_eq!(exchange.get_placements().len(), 1);
+
+        // first order filled, the slot is back
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "FILLED", first.get_qty_to_execute(), first.get_qty_to_execute(), 0.05)));
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 2, "FILLED", first.get_qty_to_execute(), first.get_qty_to_execute(), 0.05)));
+        assert!(arbitrage_ordering.execute_transaction(first).is_ok());
+        assert_eq!(exchange.get_placements().len(), 2);
+    }
+
+    #[test]
+    fn slow_leg_reporting_after_cycle_timeout_is_cancelled_and_reverted() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let legs = arbitrage_profit.get_transaction_result_list();
+        exchange.push_order_for("ETHBTC", Ok(arbitrage_testing::transaction("ETHBTC", 1, "FILLED", legs[0].get_qty_to_execute(), legs[0].get_qty_to_execute(), 0.05)));
+        exchange.push_order_for("ETHUSDT", Ok(arbitrage_testing::transaction("ETHUSDT", 2, "FILLED", legs[1].get_qty_to_execute(), legs[1].get_qty_to_execute(), 2000.0)));
+        exchange.push_order_for("BTCUSDT", Ok(arbitrage_testing::transaction("BTCUSDT", 3, "PARTIALLY_FILLED", legs[2].get_qty_to_execute(), 0.5, 38000.0)));
+        exchange.set_order_delay("BTCUSDT", Duration::from_millis(200));
+        exchange.push_cancel(Ok(arbitrage_testing::order_canceled("BTCUSDT", 3)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+        arbitrage_ordering.set_cycle_timeout(Some(Duration::from_millis(50)));
+        for quote in arbitrage_testing::triangle_quotes() {
+            arbitrage_ordering.quote_cache.write().unwrap().insert(quote.instrument.clone(), quote.build());
+        }
+
+        let results = arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        // every leg reported, the slow one included
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].as_ref().unwrap().order_id, 3);
+
+        // the slow leg order was cancelled once it reported
+        let cancels = exchange.get_calls_of("cancel_order");
+        assert_eq!(cancels.len(), 1);
+        assert_eq!(cancels[0].symbol, "BTCUSDT");
+        assert_eq!(cancels[0].order_id, 3);
+
+        // its filled qty was reverted, USDT sold back to BTC
+        let reverts = exchange.get_calls_of("market_sell");
+        assert!(reverts.iter().any(|call| (call.symbol == "USDTBTC") & (call.qty == 0.5)));
+    }
+
+    #[test]
+    fn only_the_best_profits_of_a_batch_are_executed() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_max_concurrent_executions(Some(2));
+
+        // five simultaneous profits, a higher ETH bid is a better cycle
+        for eth_bid in &[2010.0, 2040.0, 2020.0, 2050.0, 2030.0] {
+            let mut quotes = arbitrage_testing::triangle_quotes();
+            quotes[1].bid_price = *eth_bid;
+            let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+            arbitrage_profit_sender.send(arbitrage_profit).unwrap();
+        }
+
+        let best = arbitrage_ordering.next_arbitrage_profit().unwrap();
+        let second = arbitrage_ordering.next_arbitrage_profit().unwrap();
+
+        assert!(best.get_profit() > second.get_profit());
+        assert!((best.get_transaction_result_list()[1].get_price() - 2050.0).abs() < 1e-9);
+        assert!((second.get_transaction_result_list()[1].get_price() - 2040.0).abs() < 1e-9);
+        assert!(arbitrage_ordering.next_arbitrage_profit().is_err());
+    }
+
+    #[test]
+    fn clean_up_sells_only_the_excess_above_the_keep_amount() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_balance(Ok(arbitrage_testing::balance("ETH", 5.0)));
+        exchange.push_balance(Ok(arbitrage_testing::balance("USDT", 0.0)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let mut eth_btc = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        eth_btc.step_size = 0.001;
+        arbitrage_ordering.quote_cache.write().unwrap().insert(eth_btc.instrument.clone(), eth_btc.build());
+        let mut keep_balances: HashMap<String, f64> = HashMap::new();
+        keep_balances.insert("ETH".to_string(), 2.0);
+        arbitrage_ordering.set_keep_balances(keep_balances);
+
+        arbitrage_ordering.clean_balances(&arbitrage_testing::triangle_profit(1.0));
+
+        let sells = exchange.get_calls_of("market_sell");
+        assert_eq!(sells.len(), 1);
+        assert_eq!(sells[0].symbol, "ETHBTC");
+        assert_eq!(sells[0].qty, 3.0);
+    }
+
+    #[test]
+    fn clean_up_sells_nothing_below_the_keep_amount() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_balance(Ok(arbitrage_testing::balance("ETH", 1.5)));
+        exchange.push_balance(Ok(arbitrage_testing::balance("USDT", 0.0)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let mut eth_btc = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        eth_btc.step_size = 0.001;
+        arbitrage_ordering.quote_cache.write().unwrap().insert(eth_btc.instrument.clone(), eth_btc.build());
+        let mut keep_balances: HashMap<String, f64> = HashMap::new();
+        keep_balances.insert("ETH".to_string(), 2.0);
+        arbitrage_ordering.set_keep_balances(keep_balances);
+
+        arbitrage_ordering.clean_balances(&arbitrage_testing::triangle_profit(1.0));
+
+        assert!(exchange.get_calls_of("market_sell").is_empty());
+    }
+
+    #[test]
+    fn market_last_leg_dispatches_a_market_order() {
+        let exchange = Arc::new(MockExchange::new());
+        let mut transactions = arbitrage_testing::triangle();
+        transactions[2].insert("order_type".to_string(), "MARKET".to_string());
+        let mut arbitrage = Arbitrage::from_transaction_list(&transactions).unwrap();
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let methods: Vec<String> = exchange.get_placements().iter().map(|call| call.method.clone()).collect();
+        assert_eq!(methods, vec!["limit_buy_fok", "limit_sell_fok", "market_buy"]);
+        assert_eq!(exchange.get_calls_of("market_buy")[0].symbol, "BTCUSDT");
+    }
+
+    #[test]
+    fn orders_beyond_daily_cap_are_skipped_until_reset() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(23, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        arbitrage_ordering.set_daily_order_limit(Some(3), 0);
+
+        // the second cycle would exceed the cap
+        let arbitrage_ordering = run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit.clone(), arbitrage_testing::triangle_profit(1.0)]);
+        assert_eq!(exchange.get_placements().len(), 3);
+        assert_eq!(arbitrage_ordering.get_daily_order_count(), 3);
+
+        // next day
+        clock.set(Utc.ymd(2026, 1, 2).and_hms(0, 30, 0));
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let arbitrage_ordering = run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit]);
+        assert_eq!(exchange.get_placements().len(), 6);
+        assert_eq!(arbitrage_ordering.get_daily_order_count(), 3);
+    }
+
+    #[test]
+    fn daily_period_starts_at_reset_hour() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_daily_order_limit(Some(3), 8);
+
+        assert_eq!(arbitrage_ordering.get_daily_period_start(Utc.ymd(2026, 1, 2).and_hms(7, 59, 59)), Utc.ymd(2026, 1, 1).and_hms(8, 0, 0));
+        assert_eq!(arbitrage_ordering.get_daily_period_start(Utc.ymd(2026, 1, 2).and_hms(8, 0, 0)), Utc.ymd(2026, 1, 2).and_hms(8, 0, 0));
+    }
+
+    #[test]
+    fn ordering_is_blocked_when_balances_are_stale() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        arbitrage_ordering.set_max_balance_age(Some(Duration::from_secs(30)));
+        arbitrage_ordering.update_balances();
+
+        // the refresh forced by the stale balances fails
+        clock.advance(chrono::Duration::seconds(31));
+        run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_testing::triangle_profit(1.0)]);
+
+        assert!(exchange.get_placements().is_empty());
+        assert_eq!(exchange.get_calls_of("get_account").len(), 2);
+    }
+
+    #[test]
+    fn ordering_proceeds_with_balances_within_the_bound() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        arbitrage_ordering.set_max_balance_age(Some(Duration::from_secs(30)));
+        arbitrage_ordering.update_balances();
+
+        clock.advance(chrono::Duration::seconds(29));
+        run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit]);
+
+        assert_eq!(exchange.get_placements().len(), 3);
+    }
+
+    #[test]
+    fn realized_pnl_accumulates_over_executions() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.01)])));
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.03)])));
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.update_balances();
+
+        push_filled_legs(&exchange, &arbitrage_profit);
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let realized_pnl = arbitrage_ordering.get_realized_pnl();
+        assert!((realized_pnl.get("BTC").unwrap() - 0.03).abs() < 1e-9);
+    }
+
+    // run the triangle with the downstream liquidity check on, the BTC_USDT ask qty as quoted
+    fn run_with_last_leg_ask_qty(exchange: &Arc<MockExchange>, ask_qty: f32) {
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_downstream_liquidity_factor(Some(1.0));
+        arbitrage_ordering.update_balances();
+        for mut quote in arbitrage_testing::triangle_quotes() {
+            if quote.instrument == "BINANCE_BTC_USDT" {
+                quote.ask_qty = ask_qty;
+            }
+            arbitrage_ordering.quote_cache.write().unwrap().insert(quote.instrument.clone(), quote.build());
+        }
+
+        run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit]);
+    }
+
+    #[test]
+    fn thin_leg_3_liquidity_prevents_leg_1_placement() {
+        let exchange = Arc::new(MockExchange::new());
+
+        run_with_last_leg_ask_qty(&exchange, 0.0001);
+
+        assert!(exchange.get_placements().is_empty());
+    }
+
+    #[test]
+    fn deep_downstream_liquidity_places_every_leg() {
+        let exchange = Arc::new(MockExchange::new());
+
+        run_with_last_leg_ask_qty(&exchange, 1000.0);
+
+        assert_eq!(exchange.get_placements().len(), 3);
+    }
+
+    #[test]
+    fn configured_credentials_win_and_empty_ones_fall_back_to_env() {
+        env::set_var("BINANCE_API_KEY", "env-api-key");
+        env::set_var("BINANCE_SECRET_KEY", "env-secret-key");
+
+        let configured = resolve_credentials("api-key".to_string(), "secret-key".to_string());
+        let fallback = resolve_credentials(String::new(), String::new());
+
+        assert_eq!(configured, ("api-key".to_string(), "secret-key".to_string()));
+        assert_eq!(fallback, ("env-api-key".to_string(), "env-secret-key".to_string()));
+        env::remove_var("BINANCE_API_KEY");
+        env::remove_var("BINANCE_SECRET_KEY");
+    }
+
+    // script the placement of every leg on its symbol, the leg index as order id, a rejected leg fails
+    fn push_parallel_legs(exchange: &MockExchange, arbitrage_profit: &ArbitrageProfit, rejected_leg: Option<usize>) {
+        for (index, transaction) in arbitrage_profit.get_transaction_result_list().iter().enumerate() {
+            let symbol = transaction.get_exchange_code();
+            if rejected_leg == Some(index) {
+                exchange.push_order_for(symbol, Err(OrderingError::ExchangeError("rejected".to_string())));
+            } else {
+                let qty = transaction.get_qty_to_execute();
+                exchange.push_order_for(symbol, Ok(arbitrage_testing::transaction(symbol, index as u64 + 1, "FILLED", qty, qty, transaction.get_price())));
+            }
+        }
+    }
+
+    #[test]
+    fn execute_parallel_returns_every_leg_result_in_leg_order() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+
+        let results = arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        let order_ids: Vec<u64> = results.iter().map(|result| result.as_ref().unwrap().order_id).collect();
+        assert_eq!(order_ids, vec![1, 2, 3]);
+        assert_eq!(exchange.get_calls_of("get_account").len(), 1);
+    }
+
+    #[test]
+    fn execute_parallel_reports_a_rejected_leg() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, Some(1));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+
+        let results = arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        assert!(results[0].is_ok());
+        assert!(matches!(&results[1], Err(OrderingError::ExchangeError(message)) if message == "rejected"));
+        assert!(results[2].is_ok());
+        assert!(!arbitrage_ordering.busy);
+    }
+
+    #[test]
+    fn parallel_mode_dispatches_to_execute_parallel() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+        arbitrage_ordering.update_balances();
+
+        run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit]);
+
+        // no order status polling in parallel mode
+        assert_eq!(exchange.get_placements().len(), 3);
+        assert!(exchange.get_calls_of("order_status").is_empty());
+    }
+
+    #[test]
+    fn fok_fill_probability_is_the_covered_fraction() {
+        assert_eq!(fok_fill_probability(10.0, 2.0), 0.2);
+        assert_eq!(fok_fill_probability(10.0, 10.0), 1.0);
+        assert_eq!(fok_fill_probability(10.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn order_exceeding_the_book_depth_reports_a_low_fill_probability() {
+        let exchange = Arc::new(MockExchange::new());
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1.0);
+        arbitrage_ordering.quote_cache.write().unwrap().insert(quote.instrument.clone(), quote.build());
+        let first_leg = arbitrage_testing::triangle_profit(1.0).get_transaction_result_list()[0].clone();
+
+        // 20 ETH wanted, 1 ETH on the ask
+        assert!(arbitrage_ordering.estimate_fok_fill_probability(&first_leg, 0.05) < 0.1);
+
+        quote.ask_qty = 1000.0;
+        arbitrage_ordering.quote_cache.write().unwrap().insert(quote.instrument.clone(), quote.build());
+        assert_eq!(arbitrage_ordering.estimate_fok_fill_probability(&first_leg, 0.05), 1.0);
+    }
+
+    // ordering with quoted instruments, resolving symbols without referencedata
+    fn ordering_with_instruments(exchange: &Arc<MockExchange>, instrument_list: &[&str]) -> ArbitrageOrdering {
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+        for instrument in instrument_list {
+            arbitrage_ordering.quote_cache.write().unwrap().insert(instrument.to_string(), arbitrage_testing::market_bbo(instrument, 1.0, 1000.0, 1.1, 1000.0));
+        }
+        return arbitrage_ordering;
+    }
+
+    #[test]
+    fn four_letter_quote_symbol_splits_into_base_and_quote() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_ordering = ordering_with_instruments(&exchange, &["BINANCE_ADA_USDT", "BINANCE_ADA_BTC"]);
+
+        assert_eq!(arbitrage_ordering.get_base_quote(&"ADAUSDT".to_string()), Some(("ADA".to_string(), "USDT".to_string())));
+        assert_eq!(arbitrage_ordering.get_base_quote(&"ADABTC".to_string()), Some(("ADA".to_string(), "BTC".to_string())));
+        assert_eq!(arbitrage_ordering.get_base_quote(&"XRPUSDT".to_string()), None);
+    }
+
+    #[test]
+    fn revert_of_a_four_letter_quote_sells_the_base_to_the_start_asset() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_ordering = ordering_with_instruments(&exchange, &["BINANCE_ADA_USDT", "BINANCE_ADA_BTC"]);
+
+        arbitrage_ordering.revert_to_start_asset("ADAUSDT".to_string(), "SELL".to_string(), 10.0, &"BTC".to_string());
+
+        let sells = exchange.get_calls_of("market_sell");
+        assert_eq!(sells.len(), 1);
+        assert_eq!(sells[0].symbol, "ADABTC");
+    }
+
+    #[test]
+    fn revert_of_a_usdt_cycle_sells_back_into_usdt() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_ordering = ordering_with_instruments(&exchange, &["BINANCE_ADA_USDT", "BINANCE_ADA_BTC"]);
+
+        arbitrage_ordering.revert_to_start_asset("ADAUSDT".to_string(), "SELL".to_string(), 10.0, &"USDT".to_string());
+
+        let sells = exchange.get_calls_of("market_sell");
+        assert_eq!(sells.len(), 1);
+        assert_eq!(sells[0].symbol, "ADAUSDT");
+    }
+
+    // run a triangle cycle of 1 BTC checking the start balance
+    fn run_with_start_balance(exchange: &Arc<MockExchange>, balance: f64) {
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", balance)])));
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_check_start_balance(true);
+        arbitrage_ordering.update_balances();
+
+        run_ordering(arbitrage_ordering, &arbitrage_profit_sender, vec![arbitrage_profit]);
+    }
+
+    #[test]
+    fn drained_start_balance_skips_the_cycle() {
+        let exchange = Arc::new(MockExchange::new());
+
+        run_with_start_balance(&exchange, 0.2);
+
+        assert!(exchange.get_placements().is_empty());
+    }
+
+    #[test]
+    fn covered_start_balance_places_the_cycle() {
+        let exchange = Arc::new(MockExchange::new());
+
+        run_with_start_balance(&exchange, 1.0);
+
+        assert_eq!(exchange.get_placements().len(), 3);
+    }
+
+    #[test]
+    fn balance_drained_by_a_prior_cycle_skips_the_next_one() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.0)])));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_check_start_balance(true);
+        arbitrage_ordering.update_balances();
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+
+        assert!(arbitrage_ordering.has_start_balance(&arbitrage_profit));
+        arbitrage_ordering.balances.write().unwrap().insert("BTC".to_string(), 0.0);
+        assert!(!arbitrage_ordering.has_start_balance(&arbitrage_profit));
+    }
+
+    // ordering sizing to the current book, the ETHBTC ask qty thinned to the qty
+    fn ordering_with_thinned_book(exchange: &Arc<MockExchange>, ask_qty: f32, size_to_current_book: bool) -> ArbitrageOrdering {
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_size_to_current_book(size_to_current_book);
+        for mut quote in arbitrage_testing::triangle_quotes() {
+            if quote.instrument == "BINANCE_ETH_BTC" {
+                quote.ask_qty = ask_qty;
+            }
+            arbitrage_ordering.quote_cache.write().unwrap().insert(quote.instrument.clone(), quote.build());
+        }
+        return arbitrage_ordering;
+    }
+
+    #[test]
+    fn thinned_book_at_order_time_reduces_the_executed_size() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let mut arbitrage_ordering = ordering_with_thinned_book(&exchange, 5.0, true);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        // 1 BTC buys about 20 ETH at detection, only 5 remain at order time
+        let placements = exchange.get_placements();
+        assert_eq!(placements[0].symbol, "ETHBTC");
+        assert_eq!(placements[0].qty, 5.0);
+    }
+
+    #[test]
+    fn thinned_book_is_ignored_when_not_sizing_to_it() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let arbitrage_ordering = ordering_with_thinned_book(&exchange, 5.0, false);
+
+        let leg_1 = &arbitrage_profit.get_transaction_result_list()[0];
+        assert_eq!(arbitrage_ordering.get_order_qty(leg_1), leg_1.get_qty_to_execute());
+    }
+
+    #[test]
+    fn duplicate_client_order_id_fetches_status_instead_of_placing() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let leg_1 = &arbitrage_profit.get_transaction_result_list()[0];
+        exchange.push_filled(leg_1.get_exchange_code(), 1, leg_1.get_qty_to_execute(), leg_1.get_price());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_client_order_window(Some(Duration::from_secs(60)));
+
+        assert!(arbitrage_ordering.execute_transaction(leg_1).is_ok());
+        let duplicate = arbitrage_ordering.execute_transaction(leg_1);
+
+        assert!(matches!(duplicate, Err(OrderingError::AlreadySubmitted(Some(ref status))) if status == "FILLED"));
+        assert_eq!(exchange.get_placements().len(), 1);
+        let order_statuses = exchange.get_calls_of("order_status");
+        assert_eq!(order_statuses.len(), 1);
+        assert_eq!(order_statuses[0].order_id, 1);
+    }
+
+    #[test]
+    fn client_order_id_outside_the_window_is_placed_again() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let leg_1 = &arbitrage_profit.get_transaction_result_list()[0];
+        exchange.push_filled(leg_1.get_exchange_code(), 1, leg_1.get_qty_to_execute(), leg_1.get_price());
+        exchange.push_filled(leg_1.get_exchange_code(), 2, leg_1.get_qty_to_execute(), leg_1.get_price());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        arbitrage_ordering.set_client_order_window(Some(Duration::from_secs(60)));
+
+        assert!(arbitrage_ordering.execute_transaction(leg_1).is_ok());
+        clock.advance(chrono::Duration::seconds(61));
+
+        assert!(arbitrage_ordering.execute_transaction(leg_1).is_ok());
+        assert_eq!(exchange.get_placements().len(), 2);
+    }
+
+    #[test]
+    fn binance_message_error_converts_to_an_exchange_error() {
+        let ordering_error = OrderingError::from(Error::from_kind(BinanceLibErrorKind::Msg("connection reset".to_string())));
+
+        assert!(matches!(ordering_error, OrderingError::ExchangeError(ref msg) if msg == "connection reset"));
+    }
+
+    #[test]
+    fn rejected_order_keeps_the_binance_code_and_message() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_order(Err(OrderingError::BinanceError(-2010, "insufficient balance".to_string())));
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+
+        let err = arbitrage_ordering.execute_transaction(&arbitrage_profit.get_transaction_result_list()[0]).unwrap_err();
+
+        assert!(matches!(err, OrderingError::BinanceError(-2010, ref msg) if msg == "insufficient balance"));
+        assert_eq!(err.to_string(), "binance error. error code: -2010, msg: insufficient balance");
+        let _: &dyn error::Error = &err;
+    }
+
+    // place the first leg of the triangle after the scripted placement errors, retrying up to the attempts
+    fn place_after_errors(exchange: &Arc<MockExchange>, errors: Vec<OrderingError>, retry_attempts: u32) -> Result<Transaction, OrderingError> {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let leg_1 = &arbitrage_profit.get_transaction_result_list()[0];
+        for err in errors {
+            exchange.push_order(Err(err));
+        }
+        exchange.push_filled(leg_1.get_exchange_code(), 1, leg_1.get_qty_to_execute(), leg_1.get_price());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_retry(retry_attempts, Duration::from_millis(1));
+        return arbitrage_ordering.execute_transaction(leg_1);
+    }
+
+    #[test]
+    fn transient_error_is_retried_until_success() {
+        let exchange = Arc::new(MockExchange::new());
+
+        let result = place_after_errors(&exchange, vec![OrderingError::BinanceError(-1003, "too many requests".to_string())], 3);
+
+        assert_eq!(result.unwrap().status, "FILLED");
+        assert_eq!(exchange.get_placements().len(), 2);
+    }
+
+    #[test]
+    fn permanent_error_fails_immediately() {
+        let exchange = Arc::new(MockExchange::new());
+
+        let result = place_after_errors(&exchange, vec![OrderingError::BinanceError(-2010, "insufficient balance".to_string())], 3);
+
+        assert!(matches!(result, Err(OrderingError::BinanceError(-2010, _))));
+        assert_eq!(exchange.get_placements().len(), 1);
+    }
+
+    #[test]
+    fn transient_errors_beyond_the_attempts_fail() {
+        let exchange = Arc::new(MockExchange::new());
+        let errors = vec![OrderingError::BinanceError(-1021, "timestamp outside recv window".to_string()),
+                          OrderingError::ExchangeError("503 Service Unavailable".to_string())];
+
+        let result = place_after_errors(&exchange, errors, 1);
+
+        assert!(result.unwrap_err().is_transient());
+        assert_eq!(exchange.get_placements().len(), 2);
+    }
+
+    // profit of the triangle with a time in force per leg
+    fn triangle_profit_with_time_in_force(time_in_forces: &[&str]) -> ArbitrageProfit {
+        let mut transactions = arbitrage_testing::triangle();
+        for (transaction, time_in_force) in transactions.iter_mut().zip(time_in_forces.iter()) {
+            transaction.insert("time_in_force".to_string(), time_in_force.to_string());
+        }
+        let mut arbitrage = Arbitrage::from_transaction_list(&transactions).unwrap();
+        return arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+    }
+
+    #[test]
+    fn each_time_in_force_dispatches_to_its_order_method() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = triangle_profit_with_time_in_force(&["IOC", "GTC", "FOK"]);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let methods: Vec<String> = exchange.get_placements().iter().map(|call| call.method.clone()).collect();
+        assert_eq!(methods, vec!["limit_buy_ioc", "limit_sell", "limit_buy_fok"]);
+    }
+
+    #[test]
+    fn time_in_force_defaults_to_fok() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+
+        arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        let mut methods: Vec<String> = exchange.get_placements().iter().map(|call| call.method.clone()).collect();
+        methods.sort();
+        assert_eq!(methods, vec!["limit_buy_fok", "limit_buy_fok", "limit_sell_fok"]);
+    }
+
+    #[test]
+    fn missing_step_size_skips_the_clean_up_sell() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_balance(Ok(arbitrage_testing::balance("ETH", 5.0)));
+        exchange.push_balance(Ok(arbitrage_testing::balance("USDT", 0.0)));
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let eth_btc = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        arbitrage_ordering.quote_cache.write().unwrap().insert(eth_btc.instrument.clone(), eth_btc.build());
+
+        arbitrage_ordering.clean_balances(&arbitrage_testing::triangle_profit(1.0));
+
+        assert!(exchange.get_calls_of("market_sell").is_empty());
+    }
+
+    #[test]
+    fn qty_is_not_normalized_without_a_step_size() {
+        let exchange = Arc::new(MockExchange::new());
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let eth_btc = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        arbitrage_ordering.quote_cache.write().unwrap().insert(eth_btc.instrument.clone(), eth_btc.build());
+
+        assert_eq!(arbitrage_ordering.normalize_qty(&"BINANCE_ETH_BTC".to_string(), 3.0), None);
+        assert_eq!(arbitrage_ordering.normalize_qty(&"BINANCE_BNB_BTC".to_string(), 3.0), None);
+    }
+
+    // run the triangle sequentially after scripting the first leg placement and status, returning the placements
+    fn run_sequential_with_first_leg(exchange: &Arc<MockExchange>, status: &str, order_status: Result<Order, OrderingError>) -> Vec<arbitrage_testing::MockCall> {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first_qty = arbitrage_profit.get_transaction_result_list()[0].get_qty_to_execute();
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, status, first_qty, 0.0, 0.05)));
+        exchange.push_order_status(order_status);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        return exchange.get_placements().into_iter().filter(|call| call.method.starts_with("limit_")).collect();
+    }
+
+    #[test]
+    fn cancelled_new_order_stops_the_chain() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_cancel(Ok(arbitrage_testing::order_canceled("ETHBTC", 1)));
+
+        let placements = run_sequential_with_first_leg(&exchange, "NEW", Ok(arbitrage_testing::order("ETHBTC", 1, "NEW", 20.0, 0.0, 0.05)));
+
+        assert_eq!(placements.len(), 1);
+        assert_eq!(exchange.get_calls_of("cancel_order").len(), 1);
+    }
+
+    #[test]
+    fn killed_fok_stops_the_chain() {
+        let exchange = Arc::new(MockExchange::new());
+
+        let placements = run_sequential_with_first_leg(&exchange, "EXPIRED", Ok(arbitrage_testing::order("ETHBTC", 1, "EXPIRED", 20.0, 0.0, 0.05)));
+
+        assert_eq!(placements.len(), 1);
+        assert!(exchange.get_calls_of("cancel_order").is_empty());
+    }
+
+    #[test]
+    fn order_status_error_stops_the_chain() {
+        let exchange = Arc::new(MockExchange::new());
+
+        let placements = run_sequential_with_first_leg(&exchange, "FILLED", Err(OrderingError::Timeout));
+
+        assert_eq!(placements.len(), 1);
+    }
+
+    #[test]
+    fn slow_fill_is_polled_until_filled_and_not_cancelled() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let first = &arbitrage_profit.get_transaction_result_list()[0];
+        let first_qty = first.get_qty_to_execute();
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETHBTC", 1, "NEW", first_qty, 0.0, 0.05)));
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "NEW", first_qty, 0.0, 0.05)));
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "NEW", first_qty, 0.0, 0.05)));
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "FILLED", first_qty, first_qty, 0.05)));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_order_status_poll_interval(Duration::from_millis(5));
+        arbitrage_ordering.set_order_status_max_wait(Duration::from_millis(1000));
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        assert_eq!(exchange.get_calls_of("order_status").len(), 3);
+        assert!(exchange.get_calls_of("cancel_order").is_empty());
+        assert_eq!(exchange.get_placements()[1].symbol, "ETHUSDT");
+    }
+
+    #[test]
+    fn malformed_sleep_between_transactions_defaults_to_ten_micros() {
+        assert_eq!(parse_sleep_between_transactions(Some("abc".to_string())), Duration::from_micros(10));
+        assert_eq!(parse_sleep_between_transactions(None), Duration::from_micros(10));
+        assert_eq!(parse_sleep_between_transactions(Some("250".to_string())), Duration::from_micros(250));
+    }
+
+    #[test]
+    fn sleep_between_transactions_paces_the_parallel_legs() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+        arbitrage_ordering.set_sleep_between_transactions(Duration::from_millis(20));
+
+        let start_date = Instant::now();
+        arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        assert!(start_date.elapsed() >= Duration::from_millis(60));
+    }
+
+    // triangle profit with eth sold at the bid, a higher bid is more profitable
+    fn profit_with_eth_bid(bid_price: f32) -> ArbitrageProfit {
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        quotes[1] = QuoteFixture::new("BINANCE_ETH_USDT", bid_price, 1000.0, bid_price + 1.0, 1000.0);
+        return arbitrage_testing::profit_of(&mut arbitrage_testing::triangle_arbitrage(), &quotes, 1.0).unwrap();
+    }
+
+    #[test]
+    fn full_pending_queue_drops_the_least_profitable() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_max_pending_profits(2);
+
+        arbitrage_ordering.queue_pending_profit(profit_with_eth_bid(2100.0));
+        arbitrage_ordering.queue_pending_profit(profit_with_eth_bid(2000.0));
+        arbitrage_ordering.queue_pending_profit(profit_with_eth_bid(2200.0));
+
+        let kept: Vec<f64> = arbitrage_ordering.pending_profits.iter().map(|p| p.get_transaction_result_list()[1].get_price()).collect();
+        assert_eq!(kept, vec![2100.0, 2200.0]);
+        assert_eq!(arbitrage_ordering.get_dropped_profits(), 1);
+    }
+
+    #[test]
+    fn batch_beyond_the_pending_queue_drops_the_least_profitable() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, arbitrage_profit_sender) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_max_concurrent_executions(Some(3));
+        arbitrage_ordering.set_max_pending_profits(2);
+        for bid_price in [2100.0, 2000.0, 2200.0] {
+            arbitrage_profit_sender.send(profit_with_eth_bid(bid_price)).unwrap();
+        }
+
+        let next = arbitrage_ordering.next_arbitrage_profit().unwrap();
+
+        assert_eq!(next.get_transaction_result_list()[1].get_price(), 2200.0);
+        assert_eq!(arbitrage_ordering.pending_profits.len(), 1);
+        assert_eq!(arbitrage_ordering.get_dropped_profits(), 1);
+    }
+
+    // profit of the triangle on kraken
+    fn kraken_triangle_profit() -> ArbitrageProfit {
+        let transactions: Vec<HashMap<String, String>> = arbitrage_testing::triangle().into_iter()
+            .map(|leg| leg.into_iter().map(|(key, value)| (key, value.replace("BINANCE_", "KRAKEN_"))).collect())
+            .collect();
+        let mut quotes = arbitrage_testing::triangle_quotes();
+        for quote in &mut quotes {
+            quote.instrument = quote.instrument.replace("BINANCE_", "KRAKEN_");
+        }
+        return arbitrage_testing::profit_of(&mut Arbitrage::from_transaction_list(&transactions).unwrap(), &quotes, 1.0).unwrap();
+    }
+
+    #[test]
+    fn exchange_prefix_is_stripped_from_assets_and_instruments() {
+        let arbitrage_profit = kraken_triangle_profit();
+        let exchange = Arc::new(MockExchange::with_name("KRAKEN"));
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 2.0)])));
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.update_balances();
+        assert_eq!(arbitrage_ordering.get_balance(&"KRAKEN_BTC".to_string()), Some(2.0));
+        assert_eq!(arbitrage_profit.get_start_asset("KRAKEN_"), "BTC");
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let symbols: Vec<String> = exchange.get_placements().iter().map(|call| call.symbol.clone()).collect();
+        assert_eq!(symbols, vec!["ETHBTC", "ETHUSDT", "BTCUSDT"]);
+    }
+
+    // execution of the first triangle leg at create_at, buying 20 ETH for 1 BTC
+    fn eth_btc_execution(create_at: DateTime<Utc>) -> ExecutionRecord {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let transaction_result = &arbitrage_profit.get_transaction_result_list()[0];
+        let transaction = arbitrage_testing::transaction("ETHBTC", 1, "FILLED", 20.0, 20.0, 0.05);
+        return ExecutionRecord::new(&arbitrage_profit, transaction_result, &transaction, false, None, create_at);
+    }
+
+    #[test]
+    fn balances_are_reconstructed_from_the_snapshot_and_an_execution() {
+        let mut balances = HashMap::new();
+        balances.insert("BTC".to_string(), 1.5);
+        let create_at = Utc::now();
+        let balance_snapshot = BalanceSnapshot { balances: balances, create_at: create_at };
+
+        let balances = reconstruct_balances(&balance_snapshot, &vec![eth_btc_execution(create_at)], |symbol| {
+            if symbol == "ETHBTC" { Some(("ETH".to_string(), "BTC".to_string())) } else { None }
+        });
+
+        assert!((balances["BTC"] - 0.5).abs() < 1e-9);
+        assert!((balances["ETH"] - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unavailable_account_estimates_balances_from_the_clock_dated_snapshot() {
+        let exchange = Arc::new(MockExchange::new());
+        let mut arbitrage_ordering = ordering_with_instruments(&exchange, &["BINANCE_ETH_BTC"]);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        let profit_sink = Arc::new(MemoryProfitSink::default());
+        arbitrage_ordering.set_profit_sink(Some(Arc::clone(&profit_sink) as Arc<dyn ProfitSink>));
+
+        // snapshot, then a recorded execution
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 1.5)])));
+        arbitrage_ordering.update_balances();
+        assert_eq!(profit_sink.get_last_balance_snapshot().unwrap().create_at, Utc.ymd(2026, 1, 1).and_hms(12, 0, 0));
+        profit_sink.add_execution(&eth_btc_execution(clock.now()));
+
+        // account unavailable
+        exchange.push_account(Err(OrderingError::ExchangeError("account unavailable".to_string())));
+        arbitrage_ordering.update_balances();
+
+        assert!(arbitrage_ordering.is_balances_estimated());
+        assert!((arbitrage_ordering.get_balance(&"BTC".to_string()).unwrap() - 0.5).abs() < 1e-9);
+        assert!((arbitrage_ordering.get_balance(&"ETH".to_string()).unwrap() - 20.0).abs() < 1e-9);
+    }
+
+    // placed price of the first triangle leg, buying ETH at 0.05, with a 10bps tolerance and the book ask moved
+    fn placed_price_with_ask(ask_price: f32) -> f64 {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let transaction = &arbitrage_profit.get_transaction_result_list()[0];
+        exchange.push_filled("ETHBTC", 1, transaction.get_qty_to_execute(), transaction.get_price());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        arbitrage_ordering.set_price_tolerance_bps(10.0);
+        arbitrage_ordering.quote_cache.write().unwrap().insert("BINANCE_ETH_BTC".to_string(), arbitrage_testing::market_bbo("BINANCE_ETH_BTC", 0.0499, 1000.0, ask_price, 1000.0));
+
+        arbitrage_ordering.execute_transaction(transaction).unwrap();
+
+        return exchange.get_calls_of("limit_buy_fok")[0].price;
+    }
+
+    #[test]
+    fn leg_within_tolerance_of_the_moved_book_is_repriced() {
+        assert!((placed_price_with_ask(0.05004) - 0.05004).abs() < 1e-6);
+    }
+
+    #[test]
+    fn leg_beyond_tolerance_of_the_moved_book_keeps_its_price() {
+        assert!((placed_price_with_ask(0.0501) - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cleanup_order_follows_the_configured_priority() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let mut cleanup_priority: HashMap<String, f64> = HashMap::new();
+        cleanup_priority.insert("USDT".to_string(), 0.1);
+        cleanup_priority.insert("ETH".to_string(), 0.8);
+        arbitrage_ordering.set_cleanup_priority(cleanup_priority);
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+
+        arbitrage_ordering.clean_balances(&arbitrage_profit);
+
+        assert_eq!(arbitrage_ordering.get_cleanup_order(&arbitrage_profit), vec!["ETH", "USDT", "BTC"]);
+        let assets: Vec<String> = exchange.get_calls_of("get_balance").iter().map(|call| call.symbol.clone()).collect();
+        assert_eq!(assets, vec!["ETH", "USDT"]);
+    }
+
+    #[test]
+    fn cleanup_without_priority_keeps_the_cycle_order() {
+        let exchange = Arc::new(MockExchange::new());
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        assert_eq!(arbitrage_ordering.get_cleanup_order(&arbitrage_testing::triangle_profit(1.0)), vec!["BTC", "ETH", "USDT"]);
+    }
+
+    #[test]
+    fn non_binance_cleanup_sells_on_the_exchange_symbol_and_increment() {
+        let exchange = Arc::new(MockExchange::with_name("KRAKEN"));
+        exchange.set_symbol_separator("/");
+        exchange.set_qty_increment("KRAKEN_ETH_BTC", 0.01);
+        exchange.set_qty_increment("KRAKEN_USDT_BTC", 1.0);
+        exchange.push_balance(Ok(arbitrage_testing::balance("ETH", 1.23456)));
+        exchange.push_balance(Ok(arbitrage_testing::balance("USDT", 12.5)));
+        exchange.push_order(Ok(arbitrage_testing::transaction("ETH/BTC", 1, "FILLED", 1.23, 1.23, 0.05)));
+        exchange.push_order(Ok(arbitrage_testing::transaction("USDT/BTC", 2, "FILLED", 12.0, 12.0, 0.00005)));
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        arbitrage_ordering.clean_balances(&kraken_triangle_profit());
+
+        let sells: Vec<(String, f64)> = exchange.get_calls_of("market_sell").iter().map(|call| (call.symbol.clone(), call.qty)).collect();
+        assert_eq!(sells, vec![("ETH/BTC".to_string(), 1.23), ("USDT/BTC".to_string(), 12.0)]);
+    }
+
+    #[test]
+    fn background_refresh_updates_the_shared_balances() {
+        let exchange = Arc::new(MockExchange::new());
+        exchange.push_account(Ok(arbitrage_testing::account(&[("BTC", 2.0)])));
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        arbitrage_ordering.set_shutdown(Arc::clone(&shutdown));
+        arbitrage_ordering.set_balance_refresh_interval(Some(Duration::from_millis(1)));
+        let balances = arbitrage_ordering.get_balances();
+
+        let balance_refresh_handle = arbitrage_ordering.start_balance_refresh().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while balances.read().unwrap().get("BTC").is_none() & (Instant::now() < deadline) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        shutdown.store(true, atomic::Ordering::SeqCst);
+        balance_refresh_handle.join().unwrap();
+
+        assert_eq!(balances.read().unwrap().get("BTC"), Some(&2.0));
+        assert_eq!(arbitrage_ordering.get_balance(&"BTC".to_string()), Some(2.0));
+    }
+
+    #[test]
+    fn no_background_refresh_without_an_interval() {
+        let exchange = Arc::new(MockExchange::new());
+        let (arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+
+        assert!(arbitrage_ordering.start_balance_refresh().is_none());
+    }
+
+    #[test]
+    fn parallel_cycle_records_every_execution() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+        let profit_sink = Arc::new(MemoryProfitSink::default());
+        arbitrage_ordering.set_profit_sink(Some(Arc::clone(&profit_sink) as Arc<dyn ProfitSink>));
+
+        arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        let mut order_ids: Vec<u64> = profit_sink.executions.lock().unwrap().iter().map(|execution| execution.order_id).collect();
+        order_ids.sort();
+        assert_eq!(order_ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn market_last_leg_dispatches_a_market_order_in_parallel_mode() {
+        let exchange = Arc::new(MockExchange::new());
+        let mut transactions = arbitrage_testing::triangle();
+        transactions[2].insert("order_type".to_string(), "MARKET".to_string());
+        let mut arbitrage = Arbitrage::from_transaction_list(&transactions).unwrap();
+        let arbitrage_profit = arbitrage_testing::profit_of(&mut arbitrage, &arbitrage_testing::triangle_quotes(), 1.0).unwrap();
+        push_parallel_legs(&exchange, &arbitrage_profit, None);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+
+        let results = arbitrage_ordering.execute_parallel(&arbitrage_profit);
+
+        assert!(results.iter().all(|result| result.is_ok()));
+        let market_buys = exchange.get_calls_of("market_buy");
+        assert_eq!(market_buys.len(), 1);
+        assert_eq!(market_buys[0].symbol, "BTCUSDT");
+        assert!(exchange.get_calls_of("limit_buy_fok").iter().all(|call| call.symbol != "BTCUSDT"));
+    }
+
+    // push parallel leg answers, eth btc still resting on the exchange
+    fn push_legs_with_resting_eth_btc(exchange: &Arc<MockExchange>, arbitrage_profit: &ArbitrageProfit, eth_btc_order_id: u64) {
+        let legs = arbitrage_profit.get_transaction_result_list();
+        exchange.push_order_for("ETHBTC", Ok(arbitrage_testing::transaction("ETHBTC", eth_btc_order_id, "NEW", legs[0].get_qty_to_execute(), 0.0, 0.05)));
+        exchange.push_order_for("ETHUSDT", Ok(arbitrage_testing::transaction("ETHUSDT", 2, "FILLED", legs[1].get_qty_to_execute(), legs[1].get_qty_to_execute(), 2000.0)));
+        exchange.push_order_for("BTCUSDT", Ok(arbitrage_testing::transaction("BTCUSDT", 3, "FILLED", legs[2].get_qty_to_execute(), legs[2].get_qty_to_execute(), 38000.0)));
+    }
+
+    #[test]
+    fn overlapping_cycles_share_the_in_flight_limit_until_the_order_resolves() {
+        let exchange = Arc::new(MockExchange::new());
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::PARALLEL);
+        arbitrage_ordering.set_max_in_flight_per_instrument(Some(1), Duration::from_millis(20));
+        let eth_btc_placements = |exchange: &Arc<MockExchange>| exchange.get_placements().iter().filter(|call| call.symbol == "ETHBTC").count();
+
+        // first cycle leaves its eth btc order resting
+        let first_profit = arbitrage_testing::triangle_profit(1.0);
+        push_legs_with_resting_eth_btc(&exchange, &first_profit, 1);
+        assert!(arbitrage_ordering.execute_parallel(&first_profit).iter().all(|result| result.is_ok()));
+
+        // second cycle, the first order is still open
+        let second_profit = arbitrage_testing::triangle_profit(1.0);
+        push_legs_with_resting_eth_btc(&exchange, &second_profit, 4);
+        let results = arbitrage_ordering.execute_parallel(&second_profit);
+        assert!(matches!(results[0], Err(OrderingError::InFlightLimit)));
+        assert_eq!(eth_btc_placements(&exchange), 1);
+
+        // first order filled, the third cycle gets the slot
+        exchange.push_order_status(Ok(arbitrage_testing::order("ETHBTC", 1, "FILLED", 1.0, 1.0, 0.05)));
+        let third_profit = arbitrage_testing::triangle_profit(1.0);
+        let legs = third_profit.get_transaction_result_list();
+        exchange.push_order_for("ETHUSDT", Ok(arbitrage_testing::transaction("ETHUSDT", 5, "FILLED", legs[1].get_qty_to_execute(), legs[1].get_qty_to_execute(), 2000.0)));
+        exchange.push_order_for("BTCUSDT", Ok(arbitrage_testing::transaction("BTCUSDT", 6, "FILLED", legs[2].get_qty_to_execute(), legs[2].get_qty_to_execute(), 38000.0)));
+        let results = arbitrage_ordering.execute_parallel(&third_profit);
+        assert_eq!(results[0].as_ref().unwrap().order_id, 4);
+        assert_eq!(eth_btc_placements(&exchange), 2);
+    }
+
+    #[test]
+    fn executions_are_dated_by_the_ordering_clock() {
+        let exchange = Arc::new(MockExchange::new());
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        push_filled_legs(&exchange, &arbitrage_profit);
+        let (mut arbitrage_ordering, _) = arbitrage_testing::mock_ordering(&exchange, ExecutionMode::SEQUENTIAL);
+        let clock = Arc::new(MockClock::new(Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+        arbitrage_ordering.set_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        let profit_sink = Arc::new(MemoryProfitSink::default());
+        arbitrage_ordering.set_profit_sink(Some(Arc::clone(&profit_sink) as Arc<dyn ProfitSink>));
+
+        arbitrage_ordering.execute_sequential(&arbitrage_profit);
+
+        let executions = profit_sink.executions.lock().unwrap().clone();
+        assert_eq!(executions.len(), 3);
+        assert!(executions.iter().all(|execution| execution.create_at == Utc.ymd(2026, 1, 1).and_hms(12, 0, 0)));
+    }
+}
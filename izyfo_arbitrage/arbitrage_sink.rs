@@ -0,0 +1,729 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+
+use binance::model::Transaction;
+use chrono::{DateTime, Utc};
+use log::{info, trace, warn};
+use rusqlite;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::izyfo_arbitrage::arbitrage::ArbitrageProfit;
+use crate::izyfo_arbitrage::arbitrage_database::ArbitrageDatabase;
+use crate::izyfo_arbitrage::arbitrage_transaction::ArbitrageTransactionResult;
+
+// Execution Record
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExecutionRecord {
+    pub name: String,
+    pub uuid: Uuid,
+    pub symbol: String,
+    pub operation: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub transact_time: u64,
+    pub price: f64,
+    pub qty: f64,
+    #[serde(default)]
+    pub fill_price: Option<f64>,
+    pub raw_response: Option<String>,
+    pub run_id: Option<Uuid>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl ExecutionRecord {
+    // create record from the placed order of a leg at create_at, optionally keeping the serialized order response
+    pub fn new(arbitrage_profit: &ArbitrageProfit, transaction_result: &ArbitrageTransactionResult, transaction: &Transaction, raw_response: bool, run_id: Option<Uuid>, create_at: DateTime<Utc>) -> ExecutionRecord {
+        let raw_response = if raw_response {
+            match serde_json::to_string(transaction) {
+                Ok(raw) => Some(raw),
+                Err(err) => {
+                    warn!("arbitrage_sink - failed to serialize order response. error: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // average fill price, none if nothing was executed
+        let fill_price = if (transaction.executed_qty > 0.0) & (transaction.cummulative_quote_qty > 0.0) {
+            Some(transaction.cummulative_quote_qty / transaction.executed_qty)
+        } else {
+            None
+        };
+
+        ExecutionRecord {
+            name: arbitrage_profit.get_name().clone(),
+            uuid: arbitrage_profit.get_uuid(),
+            symbol: transaction.symbol.clone(),
+            operation: transaction_result.get_operation().clone(),
+            order_id: transaction.order_id,
+            client_order_id: transaction.client_order_id.clone(),
+            transact_time: transaction.transact_time,
+            price: transaction_result.get_price(),
+            qty: transaction_result.get_qty_to_execute(),
+            fill_price: fill_price,
+            raw_response: raw_response,
+            run_id: run_id,
+            create_at: create_at,
+        }
+    }
+
+    // return order response parsed back from the raw response
+    pub fn get_raw_transaction(&self) -> Option<Transaction> {
+        let raw = self.raw_response.as_ref()?;
+        return serde_json::from_str::<Transaction>(raw).ok();
+    }
+}
+
+// Run Record, the effective configuration of a run, referenced by its executions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    pub run_id: Uuid,
+    pub exchange: String,
+    pub start_assets: Vec<String>,
+    pub symbols_count: usize,
+    pub leg_count: usize,
+    pub qty_in: f64,
+    pub profit_threshold: f64,
+    pub ordering: bool,
+    pub mode: String,
+    pub create_at: DateTime<Utc>,
+}
+
+// Cycle State, enabled or suspended by an operator
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CycleState {
+    pub name: String,
+    pub suspended: bool,
+    pub create_at: DateTime<Utc>,
+}
+
+// Balance Snapshot, free balances by asset as last reported by the exchange
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceSnapshot {
+    pub balances: HashMap<String, f64>,
+    pub create_at: DateTime<Utc>,
+}
+
+// Latency Record, detection latency percentiles of the profits of a time bucket
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LatencyRecord {
+    pub bucket_start: DateTime<Utc>,
+    pub bucket_secs: i64,
+    pub count: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+}
+
+impl LatencyRecord {
+    // create record from the latencies of a bucket, none if empty
+    pub fn new(bucket_start: DateTime<Utc>, bucket_secs: i64, latencies_ms: &Vec<i64>) -> Option<LatencyRecord> {
+        let mut sorted = latencies_ms.clone();
+        sorted.sort();
+        Some(LatencyRecord {
+            bucket_start: bucket_start,
+            bucket_secs: bucket_secs,
+            count: sorted.len(),
+            p50_ms: percentile(&sorted, 50.0)?,
+            p90_ms: percentile(&sorted, 90.0)?,
+            p99_ms: percentile(&sorted, 99.0)?,
+        })
+    }
+}
+
+// return nearest-rank percentile of sorted values, none if empty
+pub fn percentile(sorted: &Vec<i64>, pct: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    return Some(sorted[rank.max(1).min(sorted.len()) - 1] as f64);
+}
+
+// Profit Sink
+pub trait ProfitSink: Send + Sync {
+    fn add_profit(&self, arbitrage_profit: &ArbitrageProfit);
+    fn add_execution(&self, execution: &ExecutionRecord);
+    fn add_run(&self, run: &RunRecord);
+    fn set_cycle_state(&self, cycle_state: &CycleState);
+    fn get_suspended_cycles(&self) -> Vec<String>;
+    fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot);
+    fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot>;
+    fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord>;
+    fn add_latency(&self, latency: &LatencyRecord);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ProfitSinkConfig {
+    POSTGRES(String),
+    SQLITE(String),
+    JSONL(String),
+}
+
+impl ProfitSinkConfig {
+    // parse "kind:address", e.g. "jsonl:/var/log/profits.jsonl"
+    pub fn from_str(value: &str) -> Result<ProfitSinkConfig, String> {
+        let mut parts = value.splitn(2, ':');
+        let kind = parts.next().unwrap_or("").trim().to_lowercase();
+        let address = parts.next().unwrap_or("").trim().to_string();
+        match kind.as_str() {
+            "postgres" => Ok(ProfitSinkConfig::POSTGRES(address)),
+            "sqlite" => Ok(ProfitSinkConfig::SQLITE(address)),
+            "jsonl" => Ok(ProfitSinkConfig::JSONL(address)),
+            _ => Err(format!("unknown profit sink: '{}'", value))
+        }
+    }
+
+    // create sink
+    pub fn create(&self) -> Result<Arc<dyn ProfitSink>, String> {
+        match self {
+            ProfitSinkConfig::POSTGRES(address) => {
+                let arbitrage_database = ArbitrageDatabase::new(address.clone());
+                arbitrage_database.connect()?;
+                Ok(Arc::new(arbitrage_database))
+            }
+            ProfitSinkConfig::SQLITE(path) => Ok(Arc::new(SqliteProfitSink::new(path)?)),
+            ProfitSinkConfig::JSONL(path) => Ok(Arc::new(JsonlProfitSink::new(path)?)),
+        }
+    }
+}
+
+// Profit Record, one line of the jsonl sink
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ProfitRecord {
+    PROFIT(ArbitrageProfit),
+    EXECUTION(ExecutionRecord),
+    CYCLE(CycleState),
+    RUN(RunRecord),
+    BALANCE(BalanceSnapshot),
+    LATENCY(LatencyRecord),
+}
+
+// Jsonl Profit Sink
+pub struct JsonlProfitSink {
+    path: String,
+    file: Mutex<File>,
+}
+
+impl JsonlProfitSink {
+    // open file in append mode
+    pub fn new(path: &String) -> Result<JsonlProfitSink, String> {
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|err| format!("failed to open profit file '{}'. error: {}", path, err))?;
+        info!("arbitrage_sink - jsonl sink opened. path:{}", path);
+        Ok(JsonlProfitSink {
+            path: path.clone(),
+            file: Mutex::new(file),
+        })
+    }
+
+    // write record
+    fn write(&self, record: &ProfitRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!("arbitrage_sink - failed to serialize record. error: {}", err);
+                return;
+            }
+        };
+        let mut file = self.file.lock().unwrap();
+        match writeln!(file, "{}", line) {
+            Ok(_) => trace!("arbitrage_sink - record written."),
+            Err(err) => warn!("arbitrage_sink - failed to write record. path:{}, error: {}", self.path, err)
+        }
+    }
+
+    // read records back, malformed lines are skipped
+    pub fn read(&self) -> Result<Vec<ProfitRecord>, String> {
+        let file = File::open(&self.path).map_err(|err| format!("failed to open profit file '{}'. error: {}", self.path, err))?;
+        let mut records: Vec<ProfitRecord> = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|err| format!("failed to read profit file '{}'. error: {}", self.path, err))?;
+            match serde_json::from_str::<ProfitRecord>(&line) {
+                Ok(record) => records.push(record),
+                Err(err) => warn!("arbitrage_sink - skipping malformed record. error: {}", err)
+            }
+        }
+        Ok(records)
+    }
+
+    // read profits back
+    pub fn read_profits(&self) -> Result<Vec<ArbitrageProfit>, String> {
+        let profits = self.read()?.into_iter().filter_map(|record| match record {
+            ProfitRecord::PROFIT(p) => Some(p),
+            _ => None
+        }).collect();
+        Ok(profits)
+    }
+}
+
+impl ProfitSink for JsonlProfitSink {
+    fn add_profit(&self, arbitrage_profit: &ArbitrageProfit) {
+        self.write(&ProfitRecord::PROFIT(arbitrage_profit.clone()));
+    }
+
+    fn add_execution(&self, execution: &ExecutionRecord) {
+        self.write(&ProfitRecord::EXECUTION(execution.clone()));
+    }
+
+    fn add_run(&self, run: &RunRecord) {
+        self.write(&ProfitRecord::RUN(run.clone()));
+    }
+
+    fn set_cycle_state(&self, cycle_state: &CycleState) {
+        self.write(&ProfitRecord::CYCLE(cycle_state.clone()));
+    }
+
+    // replay cycle states, the last record of a cycle wins
+    fn get_suspended_cycles(&self) -> Vec<String> {
+        let records = match self.read() {
+            Ok(records) => records,
+            Err(err) => {
+                warn!("arbitrage_sink - failed to read cycle states. error: {}", err);
+                return Vec::new();
+            }
+        };
+        let mut suspended_cycles: Vec<String> = Vec::new();
+        for record in records {
+            if let ProfitRecord::CYCLE(cycle_state) = record {
+                suspended_cycles.retain(|name| name != &cycle_state.name);
+                if cycle_state.suspended {
+                    suspended_cycles.push(cycle_state.name);
+                }
+            }
+        }
+        suspended_cycles
+    }
+
+    fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot) {
+        self.write(&ProfitRecord::BALANCE(balance_snapshot.clone()));
+    }
+
+    fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot> {
+        let records = match self.read() {
+            Ok(records) => records,
+            Err(err) => {
+                warn!("arbitrage_sink - failed to read balance snapshots. error: {}", err);
+                return None;
+            }
+        };
+        records.into_iter().filter_map(|record| match record {
+            ProfitRecord::BALANCE(b) => Some(b),
+            _ => None
+        }).last()
+    }
+
+    fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord> {
+        let records = match self.read() {
+            Ok(records) => records,
+            Err(err) => {
+                warn!("arbitrage_sink - failed to read executions. error: {}", err);
+                return Vec::new();
+            }
+        };
+        records.into_iter().filter_map(|record| match record {
+            ProfitRecord::EXECUTION(e) if e.create_at >= since => Some(e),
+            _ => None
+        }).collect()
+    }
+
+    fn add_latency(&self, latency: &LatencyRecord) {
+        self.write(&ProfitRecord::LATENCY(latency.clone()));
+    }
+}
+
+// parse rfc3339 date stored as text
+fn parse_date(value: &String) -> DateTime<Utc> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(date) => date.with_timezone(&Utc),
+        Err(err) => {
+            warn!("arbitrage_sink - malformed date, epoch used. date: {}, error: {}", value, err);
+            DateTime::<Utc>::from(std::time::UNIX_EPOCH)
+        }
+    }
+}
+
+// Sqlite Profit Sink
+pub struct SqliteProfitSink {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteProfitSink {
+    // open database and create tables
+    pub fn new(path: &String) -> Result<SqliteProfitSink, String> {
+        let connection = rusqlite::Connection::open(path)
+            .map_err(|err| format!("failed to open sqlite database '{}'. error: {}", path, err))?;
+        connection.execute_batch("create table if not exists triangle_arbitrage_binance (
+                    id integer primary key autoincrement,
+                    name text,
+                    uuid text,
+                    date text,
+                    profit real,
+                    latency_ms integer);
+                create table if not exists triangle_arbitrage_binance_executions (
+                    id integer primary key autoincrement,
+                    name text,
+                    uuid text,
+                    symbol text,
+                    operation text,
+                    order_id integer,
+                    client_order_id text,
+                    transact_time integer,
+                    price real,
+                    qty real,
+                    fill_price real,
+                    raw_response text,
+                    run_id text,
+                    date text);
+                create table if not exists triangle_arbitrage_binance_runs (
+                    run_id text primary key,
+                    exchange text,
+                    start_assets text,
+                    symbols_count integer,
+                    leg_count integer,
+                    qty_in real,
+                    profit_threshold real,
+                    ordering integer,
+                    mode text,
+                    date text);
+                create table if not exists triangle_arbitrage_binance_cycles (
+                    name text primary key,
+                    suspended integer,
+                    date text);
+                create table if not exists triangle_arbitrage_binance_balances (
+                    id integer primary key autoincrement,
+                    balances text,
+                    date text);
+                create table if not exists triangle_arbitrage_binance_latency (
+                    id integer primary key autoincrement,
+                    bucket_start text,
+                    bucket_secs integer,
+                    count integer,
+                    p50_ms real,
+                    p90_ms real,
+                    p99_ms real);")
+            .map_err(|err| format!("failed to create sqlite tables. error: {}", err))?;
+        info!("arbitrage_sink - sqlite sink opened. path:{}", path);
+        Ok(SqliteProfitSink {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl ProfitSink for SqliteProfitSink {
+    fn add_profit(&self, arbitrage_profit: &ArbitrageProfit) {
+        let connection = self.connection.lock().unwrap();
+        let profit = arbitrage_profit.get_profit();
+        match connection.execute("insert into triangle_arbitrage_binance (name, uuid, date, profit, latency_ms) values (?1, ?2, ?3, ?4, ?5)",
+                                 rusqlite::params![arbitrage_profit.get_name(), arbitrage_profit.get_uuid().to_string(), arbitrage_profit.get_create_at().to_rfc3339(),
+                                                   profit, arbitrage_profit.get_latency_ms()]) {
+            Ok(result) => trace!("{}", format!("profit added rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to add profit error={}", err))
+        }
+    }
+
+    fn add_execution(&self, execution: &ExecutionRecord) {
+        let connection = self.connection.lock().unwrap();
+        match connection.execute("insert into triangle_arbitrage_binance_executions (name, uuid, symbol, operation, order_id, client_order_id, transact_time, price, qty, fill_price, raw_response, run_id, date) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                                 rusqlite::params![execution.name, execution.uuid.to_string(), execution.symbol, execution.operation, execution.order_id as i64,
+                                                   execution.client_order_id, execution.transact_time as i64, execution.price, execution.qty, execution.fill_price, execution.raw_response,
+                                                   execution.run_id.map(|run_id| run_id.to_string()), execution.create_at.to_rfc3339()]) {
+            Ok(result) => trace!("{}", format!("execution added rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to add execution error={}", err))
+        }
+    }
+
+    fn add_run(&self, run: &RunRecord) {
+        let connection = self.connection.lock().unwrap();
+        match connection.execute("insert into triangle_arbitrage_binance_runs (run_id, exchange, start_assets, symbols_count, leg_count, qty_in, profit_threshold, ordering, mode, date) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                                 rusqlite::params![run.run_id.to_string(), run.exchange, run.start_assets.join(","), run.symbols_count as i64, run.leg_count as i64,
+                                                   run.qty_in, run.profit_threshold, run.ordering, run.mode, run.create_at.to_rfc3339()]) {
+            Ok(result) => trace!("{}", format!("run added rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to add run error={}", err))
+        }
+    }
+
+    fn set_cycle_state(&self, cycle_state: &CycleState) {
+        let connection = self.connection.lock().unwrap();
+        match connection.execute("insert or replace into triangle_arbitrage_binance_cycles (name, suspended, date) values (?1, ?2, ?3)",
+                                 rusqlite::params![cycle_state.name, cycle_state.suspended, cycle_state.create_at.to_rfc3339()]) {
+            Ok(result) => trace!("{}", format!("cycle state set rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to set cycle state error={}", err))
+        }
+    }
+
+    fn get_suspended_cycles(&self) -> Vec<String> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = match connection.prepare("select name from triangle_arbitrage_binance_cycles where suspended = 1") {
+            Ok(statement) => statement,
+            Err(err) => {
+                warn!("{}", format!("failed to query cycle states error={}", err));
+                return Vec::new();
+            }
+        };
+        let suspended_cycles = match statement.query_map(rusqlite::params![], |row| row.get::<_, String>(0)) {
+            Ok(rows) => rows.filter_map(|row| row.ok()).collect(),
+            Err(err) => {
+                warn!("{}", format!("failed to query cycle states error={}", err));
+                Vec::new()
+            }
+        };
+        suspended_cycles
+    }
+
+    fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot) {
+        let balances = match serde_json::to_string(&balance_snapshot.balances) {
+            Ok(balances) => balances,
+            Err(err) => {
+                warn!("{}", format!("failed to serialize balances error={}", err));
+                return;
+            }
+        };
+        let connection = self.connection.lock().unwrap();
+        match connection.execute("insert into triangle_arbitrage_binance_balances (balances, date) values (?1, ?2)",
+                                 rusqlite::params![balances, balance_snapshot.create_at.to_rfc3339()]) {
+            Ok(result) => trace!("{}", format!("balance snapshot added rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to add balance snapshot error={}", err))
+        }
+    }
+
+    fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot> {
+        let connection = self.connection.lock().unwrap();
+        let row = connection.query_row("select balances, date from triangle_arbitrage_binance_balances order by id desc limit 1", rusqlite::params![],
+                                       |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)));
+        match row {
+            Ok((balances, date)) => match serde_json::from_str::<HashMap<String, f64>>(&balances) {
+                Ok(balances) => Some(BalanceSnapshot { balances: balances, create_at: parse_date(&date) }),
+                Err(err) => {
+                    warn!("{}", format!("failed to parse balance snapshot error={}", err));
+                    None
+                }
+            },
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(err) => {
+                warn!("{}", format!("failed to query balance snapshot error={}", err));
+                None
+            }
+        }
+    }
+
+    fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = match connection.prepare("select name, uuid, symbol, operation, order_id, client_order_id, transact_time, price, qty, fill_price, raw_response, run_id, date
+                                                      from triangle_arbitrage_binance_executions where date >= ?1 order by id") {
+            Ok(statement) => statement,
+            Err(err) => {
+                warn!("{}", format!("failed to query executions error={}", err));
+                return Vec::new();
+            }
+        };
+        let executions = statement.query_map(rusqlite::params![since.to_rfc3339()], |row| {
+            Ok(ExecutionRecord {
+                name: row.get(0)?,
+                uuid: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or(Uuid::nil()),
+                symbol: row.get(2)?,
+                operation: row.get(3)?,
+                order_id: row.get::<_, i64>(4)? as u64,
+                client_order_id: row.get(5)?,
+                transact_time: row.get::<_, i64>(6)? as u64,
+                price: row.get(7)?,
+                qty: row.get(8)?,
+                fill_price: row.get(9)?,
+                raw_response: row.get(10)?,
+                run_id: row.get::<_, Option<String>>(11)?.and_then(|run_id| Uuid::parse_str(&run_id).ok()),
+                create_at: parse_date(&row.get::<_, String>(12)?),
+            })
+        });
+        let executions = match executions {
+            Ok(rows) => rows.filter_map(|row| row.ok()).collect(),
+            Err(err) => {
+                warn!("{}", format!("failed to query executions error={}", err));
+                Vec::new()
+            }
+        };
+        executions
+    }
+
+    fn add_latency(&self, latency: &LatencyRecord) {
+        let connection = self.connection.lock().unwrap();
+        match connection.execute("insert into triangle_arbitrage_binance_latency (bucket_start, bucket_secs, count, p50_ms, p90_ms, p99_ms) values (?1, ?2, ?3, ?4, ?5, ?6)",
+                                 rusqlite::params![latency.bucket_start.to_rfc3339(), latency.bucket_secs, latency.count as i64, latency.p50_ms, latency.p90_ms, latency.p99_ms]) {
+            Ok(result) => trace!("{}", format!("latency added rows={}", result)),
+            Err(err) => warn!("{}", format!("failed to add latency error={}", err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::izyfo_arbitrage::arbitrage_testing;
+
+    // return path of a new temporary file
+    fn temp_path(extension: &str) -> String {
+        return std::env::temp_dir().join(format!("izyfo-{}.{}", Uuid::new_v4(), extension)).to_string_lossy().to_string();
+    }
+
+    #[test]
+    fn persisted_date_equals_create_at() {
+        let profit_sink = SqliteProfitSink::new(&":memory:".to_string()).unwrap();
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+
+        profit_sink.add_profit(&arbitrage_profit);
+
+        let connection = profit_sink.connection.lock().unwrap();
+        let date: String = connection.query_row("select date from triangle_arbitrage_binance where uuid = ?1",
+                                                rusqlite::params![arbitrage_profit.get_uuid().to_string()], |row| row.get(0)).unwrap();
+        assert_eq!(parse_date(&date), arbitrage_profit.get_create_at());
+    }
+
+    #[test]
+    fn jsonl_profit_keeps_create_at() {
+        let path = temp_path("jsonl");
+        let profit_sink = JsonlProfitSink::new(&path).unwrap();
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+
+        profit_sink.add_profit(&arbitrage_profit);
+
+        let profits = profit_sink.read_profits().unwrap();
+        assert_eq!(profits.len(), 1);
+        assert_eq!(profits[0].get_create_at(), arbitrage_profit.get_create_at());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn jsonl_sink_reads_back_written_profits() {
+        let path = temp_path("jsonl");
+        let profit_sink = JsonlProfitSink::new(&path).unwrap();
+        let arbitrage_profits = vec![arbitrage_testing::triangle_profit(1.0), arbitrage_testing::triangle_profit(0.5)];
+
+        for arbitrage_profit in &arbitrage_profits {
+            profit_sink.add_profit(arbitrage_profit);
+        }
+
+        let profits = profit_sink.read_profits().unwrap();
+        assert_eq!(profits.len(), 2);
+        for (profit, arbitrage_profit) in profits.iter().zip(arbitrage_profits.iter()) {
+            assert_eq!(profit.get_uuid(), arbitrage_profit.get_uuid());
+            assert_eq!(profit.get_name(), arbitrage_profit.get_name());
+            assert_eq!(profit.get_profit(), arbitrage_profit.get_profit());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn jsonl_sink_skips_malformed_lines() {
+        let path = temp_path("jsonl");
+        std::fs::write(&path, "not a record\n").unwrap();
+        let profit_sink = JsonlProfitSink::new(&path).unwrap();
+
+        profit_sink.add_profit(&arbitrage_testing::triangle_profit(1.0));
+
+        assert_eq!(profit_sink.read_profits().unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn profit_sink_is_selected_by_config() {
+        assert!(matches!(ProfitSinkConfig::from_str("jsonl:/tmp/profits.jsonl"), Ok(ProfitSinkConfig::JSONL(path)) if path == "/tmp/profits.jsonl"));
+        assert!(matches!(ProfitSinkConfig::from_str("sqlite::memory:"), Ok(ProfitSinkConfig::SQLITE(path)) if path == ":memory:"));
+        assert!(matches!(ProfitSinkConfig::from_str("POSTGRES:host=localhost"), Ok(ProfitSinkConfig::POSTGRES(_))));
+        assert!(ProfitSinkConfig::from_str("redis:localhost").is_err());
+        assert!(ProfitSinkConfig::from_str("sqlite::memory:").unwrap().create().is_ok());
+    }
+
+    #[test]
+    fn raw_response_round_trips() {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let transaction = arbitrage_testing::transaction("ETHBTC", 7, "FILLED", 20.0, 20.0, 0.05);
+
+        let execution = ExecutionRecord::new(&arbitrage_profit, &arbitrage_profit.get_transaction_result_list()[0], &transaction, true, None, Utc::now());
+
+        let raw_transaction = execution.get_raw_transaction().unwrap();
+        assert_eq!(raw_transaction.symbol, transaction.symbol);
+        assert_eq!(raw_transaction.order_id, transaction.order_id);
+        assert_eq!(raw_transaction.executed_qty, transaction.executed_qty);
+        assert_eq!(raw_transaction.status, transaction.status);
+    }
+
+    #[test]
+    fn raw_response_is_not_kept_by_default() {
+        let arbitrage_profit = arbitrage_testing::triangle_profit(1.0);
+        let transaction = arbitrage_testing::transaction("ETHBTC", 7, "FILLED", 20.0, 20.0, 0.05);
+
+        let execution = ExecutionRecord::new(&arbitrage_profit, &arbitrage_profit.get_transaction_result_list()[0], &transaction, false, None, Utc::now());
+
+        assert!(execution.raw_response.is_none());
+        assert!(execution.get_raw_transaction().is_none());
+    }
+
+    // cycle state at now
+    fn cycle_state(name: &str, suspended: bool) -> CycleState {
+        return CycleState {
+            name: name.to_string(),
+            suspended: suspended,
+            create_at: Utc::now(),
+        };
+    }
+
+    #[test]
+    fn suspended_cycle_stays_suspended_across_a_sqlite_reload() {
+        let path = temp_path("sqlite");
+        {
+            let profit_sink = SqliteProfitSink::new(&path).unwrap();
+            profit_sink.set_cycle_state(&cycle_state("BTC-ETH:ETH-USDT:USDT-BTC", true));
+            profit_sink.set_cycle_state(&cycle_state("BTC-USDT:USDT-ETH:ETH-BTC", true));
+            profit_sink.set_cycle_state(&cycle_state("BTC-USDT:USDT-ETH:ETH-BTC", false));
+        }
+
+        let profit_sink = SqliteProfitSink::new(&path).unwrap();
+
+        assert_eq!(profit_sink.get_suspended_cycles(), vec!["BTC-ETH:ETH-USDT:USDT-BTC".to_string()]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn suspended_cycle_stays_suspended_across_a_jsonl_reload() {
+        let path = temp_path("jsonl");
+        {
+            let profit_sink = JsonlProfitSink::new(&path).unwrap();
+            profit_sink.set_cycle_state(&cycle_state("BTC-ETH:ETH-USDT:USDT-BTC", true));
+            profit_sink.set_cycle_state(&cycle_state("BTC-USDT:USDT-ETH:ETH-BTC", true));
+            profit_sink.set_cycle_state(&cycle_state("BTC-USDT:USDT-ETH:ETH-BTC", false));
+        }
+
+        let profit_sink = JsonlProfitSink::new(&path).unwrap();
+
+        assert_eq!(profit_sink.get_suspended_cycles(), vec!["BTC-ETH:ETH-USDT:USDT-BTC".to_string()]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn latency_percentiles_of_a_bucket_are_persisted() {
+        let profit_sink = SqliteProfitSink::new(&":memory:".to_string()).unwrap();
+        let latencies_ms: Vec<i64> = (1..=100).rev().collect();
+        let bucket_start = Utc.timestamp(1_600_000_020, 0);
+
+        profit_sink.add_latency(&LatencyRecord::new(bucket_start, 60, &latencies_ms).unwrap());
+
+        let connection = profit_sink.connection.lock().unwrap();
+        let (date, count, p50_ms, p90_ms, p99_ms): (String, i64, f64, f64, f64) = connection
+            .query_row("select bucket_start, count, p50_ms, p90_ms, p99_ms from triangle_arbitrage_binance_latency", rusqlite::params![],
+                       |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))).unwrap();
+        assert_eq!(parse_date(&date), bucket_start);
+        assert_eq!(count, 100);
+        assert_eq!((p50_ms, p90_ms, p99_ms), (50.0, 90.0, 99.0));
+    }
+
+    #[test]
+    fn empty_bucket_has_no_latency_record() {
+        assert!(LatencyRecord::new(Utc::now(), 60, &vec![]).is_none());
+        assert_eq!(percentile(&vec![7], 99.0), Some(7.0));
+    }
+}
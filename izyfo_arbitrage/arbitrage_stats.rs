@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{self, AtomicBool};
+use std::thread;
+use std::thread::JoinHandle;
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::izyfo_arbitrage::arbitrage_ordering::SHUTDOWN_POLL_INTERVAL;
+
+// stats path
+pub const STATS_PATH: &str = "/stats";
+
+// Arbitrage Stats, session totals served on the stats path
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArbitrageStats {
+    pub realized_pnl: HashMap<String, f64>,
+}
+
+// Stats Server, serves the session stats over http until shutdown
+pub struct StatsServer {
+    server: Server,
+}
+
+impl StatsServer {
+    // bind stats server, port 0 picks a free port
+    pub fn bind(address: &str) -> Result<StatsServer, String> {
+        let server = Server::http(address).map_err(|e| format!("failed to bind stats server '{}': {}", address, e))?;
+        return Ok(StatsServer {
+            server: server,
+        });
+    }
+
+    // return bound address
+    pub fn get_address(&self) -> String {
+        return self.server.server_addr().to_string();
+    }
+
+    // serve requests on a thread until shutdown
+    pub fn start(self, realized_pnl: Arc<Mutex<HashMap<String, f64>>>, shutdown: Arc<AtomicBool>) -> JoinHandle<()> {
+        return thread::spawn(move || {
+            info!("arbitrage_stats - stats server started. address: {}", self.get_address());
+            while !shutdown.load(atomic::Ordering::SeqCst) {
+                match self.server.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+                    Ok(Some(request)) => {
+                        let stats = ArbitrageStats {
+                            realized_pnl: realized_pnl.lock().unwrap().clone(),
+                        };
+                        respond(request, &stats);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        error!("arbitrage_stats - stats server error. error: {}", err);
+                        break;
+                    }
+                }
+            }
+            info!("arbitrage_stats - stats server stopped.");
+        });
+    }
+}
+
+// answer stats as json, anything else is not found
+fn respond(request: Request, stats: &ArbitrageStats) {
+    let result = if (request.method() == &Method::Get) & (request.url() == STATS_PATH) {
+        let body = serde_json::to_string(stats).unwrap_or_default();
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        request.respond(Response::from_string(body).with_header(content_type))
+    } else {
+        request.respond(Response::from_string("not found").with_status_code(404))
+    };
+    if let Err(err) = result {
+        error!("arbitrage_stats - stats response failed. error: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    // send a get request, returning the raw response
+    fn get(address: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(format!("GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path).as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        return response;
+    }
+
+    #[test]
+    fn stats_serve_the_realized_pnl() {
+        let realized_pnl: Arc<Mutex<HashMap<String, f64>>> = Arc::new(Mutex::new(HashMap::new()));
+        realized_pnl.lock().unwrap().insert("BTC".to_string(), 0.25);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stats_server = StatsServer::bind("127.0.0.1:0").unwrap();
+        let address = stats_server.get_address();
+        let stats_handle = stats_server.start(Arc::clone(&realized_pnl), Arc::clone(&shutdown));
+
+        let response = get(&address, STATS_PATH);
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        let stats: ArbitrageStats = serde_json::from_str(body).unwrap();
+
+        assert!(response.starts_with("HTTP/1.0 200") | response.starts_with("HTTP/1.1 200"));
+        assert_eq!(stats.realized_pnl.get("BTC"), Some(&0.25));
+        assert!(get(&address, "/unknown").contains(" 404 "));
+        shutdown.store(true, atomic::Ordering::SeqCst);
+        stats_handle.join().unwrap();
+    }
+}
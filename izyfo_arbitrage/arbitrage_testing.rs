@@ -0,0 +1,546 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{self, AtomicBool};
+use std::thread;
+use std::time::Duration;
+
+use binance::model::{AccountInformation, Balance, Order, OrderCanceled, Transaction};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::izyfo_arbitrage::arbitrage::{Arbitrage, ArbitrageProfit};
+use crate::izyfo_arbitrage::arbitrage_exchange::Exchange;
+use crate::izyfo_arbitrage::arbitrage_ordering::{ArbitrageOrdering, OrderingError};
+use crate::izyfo_arbitrage::arbitrage_sink::{BalanceSnapshot, CycleState, ExecutionRecord, LatencyRecord, ProfitSink, RunRecord};
+use crate::izyfo_arbitrage::arbitrage_executor::{ArbitrageExecutor, ExecutionMode};
+use crate::izyfo_events::exchange::market_bbo::MarketBBO;
+
+// Quote Fixture, market bbo fields as delivered by the feed, filters disabled by default
+#[derive(Debug, Clone)]
+pub struct QuoteFixture {
+    pub instrument: String,
+    pub bid_price: f32,
+    pub bid_qty: f32,
+    pub ask_price: f32,
+    pub ask_qty: f32,
+    pub min_price: f32,
+    pub max_price: f32,
+    pub min_qty: f32,
+    pub max_qty: f32,
+    pub min_notional: f32,
+    pub step_size: f32,
+    pub tick_size: f32,
+    pub marketdata_timestamp: f64,
+    pub created_timestamp_ms: i64,
+}
+
+impl QuoteFixture {
+    // new quote fixture
+    pub fn new(instrument: &str, bid_price: f32, bid_qty: f32, ask_price: f32, ask_qty: f32) -> QuoteFixture {
+        return QuoteFixture {
+            instrument: instrument.to_string(),
+            bid_price: bid_price,
+            bid_qty: bid_qty,
+            ask_price: ask_price,
+            ask_qty: ask_qty,
+            min_price: 0.0,
+            max_price: 0.0,
+            min_qty: 0.0,
+            max_qty: 1000000000.0,
+            min_notional: 0.0,
+            step_size: 0.0,
+            tick_size: 0.0,
+            marketdata_timestamp: 1.0,
+            created_timestamp_ms: 1000,
+        };
+    }
+
+    // build market bbo
+    pub fn build(&self) -> MarketBBO {
+        return serde_json::from_value(json!({
+            "instrument": self.instrument,
+            "feed": self.instrument,
+            "bid_price": self.bid_price,
+            "bid_qty": self.bid_qty,
+            "ask_price": self.ask_price,
+            "ask_qty": self.ask_qty,
+            "min_price": self.min_price,
+            "max_price": self.max_price,
+            "min_qty": self.min_qty,
+            "max_qty": self.max_qty,
+            "min_notional": self.min_notional,
+            "step_size": self.step_size,
+            "tick_size": self.tick_size,
+            "marketdata_timestamp": self.marketdata_timestamp,
+            "created_timestamp_ms": self.created_timestamp_ms,
+        })).unwrap();
+    }
+}
+
+// return market bbo without filters
+pub fn market_bbo(instrument: &str, bid_price: f32, bid_qty: f32, ask_price: f32, ask_qty: f32) -> MarketBBO {
+    return QuoteFixture::new(instrument, bid_price, bid_qty, ask_price, ask_qty).build();
+}
+
+// return transaction of a leg, as built by the executor initialize
+pub fn leg(source: &str, target: &str, operation: &str, base: &str, quote: &str) -> HashMap<String, String> {
+    let mut transaction: HashMap<String, String> = HashMap::new();
+    transaction.insert("source".to_string(), format!("BINANCE_{}", source));
+    transaction.insert("target".to_string(), format!("BINANCE_{}", target));
+    transaction.insert("operation".to_string(), operation.to_string());
+    transaction.insert("instrument".to_string(), format!("BINANCE_{}_{}", base, quote));
+    transaction.insert("exchange_code".to_string(), format!("{}{}", base, quote));
+    return transaction;
+}
+
+// return triangle BTC -> ETH -> USDT -> BTC
+pub fn triangle() -> Vec<HashMap<String, String>> {
+    return vec![
+        leg("BTC", "ETH", "BUY", "ETH", "BTC"),
+        leg("ETH", "USDT", "SELL", "ETH", "USDT"),
+        leg("USDT", "BTC", "BUY", "BTC", "USDT"),
+    ];
+}
+
+// return quotes of the triangle, 1 BTC buys 20 ETH, sold for 40000 USDT, buying back about 1.0526 BTC before fees
+pub fn triangle_quotes() -> Vec<QuoteFixture> {
+    return vec![
+        QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0),
+        QuoteFixture::new("BINANCE_ETH_USDT", 2000.0, 1000.0, 2001.0, 1000.0),
+        QuoteFixture::new("BINANCE_BTC_USDT", 37990.0, 1000.0, 38000.0, 1000.0),
+    ];
+}
+
+// return arbitrage of the triangle
+pub fn triangle_arbitrage() -> Arbitrage {
+    return Arbitrage::from_transaction_list(&triangle()).unwrap();
+}
+
+// return profit of the arbitrage evaluated on the quotes, unscaled
+pub fn profit_of(arbitrage: &mut Arbitrage, quotes: &Vec<QuoteFixture>, qty_in: f64) -> Option<ArbitrageProfit> {
+    let market_bbo_list: Vec<MarketBBO> = quotes.iter().map(|q| q.build()).collect();
+    for market_bbo in &market_bbo_list {
+        arbitrage.update(market_bbo);
+    }
+    return arbitrage.execute(market_bbo_list.last().unwrap(), qty_in, false);
+}
+
+// return profit of the triangle on its quotes
+pub fn triangle_profit(qty_in: f64) -> ArbitrageProfit {
+    return profit_of(&mut triangle_arbitrage(), &triangle_quotes(), qty_in).unwrap();
+}
+
+// return order placement response
+pub fn transaction(symbol: &str, order_id: u64, status: &str, orig_qty: f64, executed_qty: f64, price: f64) -> Transaction {
+    return serde_json::from_value(json!({
+        "symbol": symbol,
+        "orderId": order_id,
+        "orderListId": -1,
+        "clientOrderId": format!("client-{}", order_id),
+        "transactTime": 1000,
+        "price": price,
+        "origQty": orig_qty,
+        "executedQty": executed_qty,
+        "cummulativeQuoteQty": executed_qty * price,
+        "stopPrice": "0.0",
+        "status": status,
+        "timeInForce": "FOK",
+        "type": "LIMIT",
+        "side": "BUY",
+        "fills": [],
+    })).unwrap();
+}
+
+// return order status response, a partial fill is an executed qty below orig qty
+pub fn order(symbol: &str, order_id: u64, status: &str, orig_qty: f64, executed_qty: f64, price: f64) -> Order {
+    return serde_json::from_value(json!({
+        "symbol": symbol,
+        "orderId": order_id,
+        "orderListId": -1,
+        "clientOrderId": format!("client-{}", order_id),
+        "price": price,
+        "origQty": orig_qty,
+        "executedQty": executed_qty,
+        "cummulativeQuoteQty": "0.0",
+        "status": status,
+        "timeInForce": "FOK",
+        "type": "LIMIT",
+        "side": "BUY",
+        "stopPrice": "0.0",
+        "icebergQty": "0.0",
+        "time": 1000,
+        "updateTime": 1000,
+        "isWorking": true,
+        "origQuoteOrderQty": "0.0",
+    })).unwrap();
+}
+
+// return cancel response
+pub fn order_canceled(symbol: &str, order_id: u64) -> OrderCanceled {
+    return serde_json::from_value(json!({
+        "symbol": symbol,
+        "origClientOrderId": format!("client-{}", order_id),
+        "orderId": order_id,
+        "clientOrderId": format!("cancel-{}", order_id),
+    })).unwrap();
+}
+
+// return free balance of an asset
+pub fn balance(asset: &str, free: f64) -> Balance {
+    return serde_json::from_value(json!({
+        "asset": asset,
+        "free": free.to_string(),
+        "locked": "0.0",
+    })).unwrap();
+}
+
+// return account with free balances
+pub fn account(balances: &[(&str, f64)]) -> AccountInformation {
+    let balances: Vec<Balance> = balances.iter().map(|(asset, free)| balance(asset, *free)).collect();
+    return serde_json::from_value(json!({
+        "makerCommission": 10,
+        "takerCommission": 10,
+        "buyerCommission": 0,
+        "sellerCommission": 0,
+        "canTrade": true,
+        "canWithdraw": true,
+        "canDeposit": true,
+        "balances": serde_json::to_value(balances).unwrap(),
+    })).unwrap();
+}
+
+// Mock Call, an exchange call recorded by the mock exchange
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCall {
+    pub method: String,
+    pub symbol: String,
+    pub qty: f64,
+    pub price: f64,
+    pub order_id: u64,
+}
+
+// Mock Exchange, records every call and returns scripted responses in order, an unscripted call fails
+pub struct MockExchange {
+    name: String,
+    calls: Mutex<Vec<MockCall>>,
+    orders: Mutex<VecDeque<Result<Transaction, OrderingError>>>,
+    symbol_orders: Mutex<HashMap<String, VecDeque<Result<Transaction, OrderingError>>>>,
+    order_delays: Mutex<HashMap<String, Duration>>,
+    order_statuses: Mutex<VecDeque<Result<Order, OrderingError>>>,
+    cancels: Mutex<VecDeque<Result<OrderCanceled, OrderingError>>>,
+    accounts: Mutex<VecDeque<Result<AccountInformation, OrderingError>>>,
+    balances: Mutex<VecDeque<Result<Balance, OrderingError>>>,
+    account_delay: Mutex<Duration>,
+    symbol_separator: Mutex<String>,
+    qty_increments: Mutex<HashMap<String, f64>>,
+}
+
+impl MockExchange {
+    // new mock exchange
+    pub fn new() -> MockExchange {
+        return MockExchange::with_name("BINANCE");
+    }
+
+    // new mock exchange with a name, instrument ids must carry its prefix
+    pub fn with_name(name: &str) -> MockExchange {
+        return MockExchange {
+            name: name.to_string(),
+            calls: Mutex::new(Vec::new()),
+            orders: Mutex::new(VecDeque::new()),
+            symbol_orders: Mutex::new(HashMap::new()),
+            order_delays: Mutex::new(HashMap::new()),
+            order_statuses: Mutex::new(VecDeque::new()),
+            cancels: Mutex::new(VecDeque::new()),
+            accounts: Mutex::new(VecDeque::new()),
+            balances: Mutex::new(VecDeque::new()),
+            account_delay: Mutex::new(Duration::from_secs(0)),
+            symbol_separator: Mutex::new(String::new()),
+            qty_increments: Mutex::new(HashMap::new()),
+        };
+    }
+
+    // script response of the next order placement, limit or market
+    pub fn push_order(&self, order: Result<Transaction, OrderingError>) {
+        self.orders.lock().unwrap().push_back(order);
+    }
+
+    // script response of the next order placement on a symbol, taken before the shared queue
+    pub fn push_order_for(&self, symbol: &str, order: Result<Transaction, OrderingError>) {
+        self.symbol_orders.lock().unwrap().entry(symbol.to_string()).or_insert_with(VecDeque::new).push_back(order);
+    }
+
+    // delay every order placement response on a symbol, a slow leg
+    pub fn set_order_delay(&self, symbol: &str, delay: Duration) {
+        self.order_delays.lock().unwrap().insert(symbol.to_string(), delay);
+    }
+
+    // script response of the next order status
+    pub fn push_order_status(&self, order_status: Result<Order, OrderingError>) {
+        self.order_statuses.lock().unwrap().push_back(order_status);
+    }
+
+    // script response of the next cancel
+    pub fn push_cancel(&self, cancel: Result<OrderCanceled, OrderingError>) {
+        self.cancels.lock().unwrap().push_back(cancel);
+    }
+
+    // script response of the next account request
+    pub fn push_account(&self, account: Result<AccountInformation, OrderingError>) {
+        self.accounts.lock().unwrap().push_back(account);
+    }
+
+    // delay every account response, a hanging account endpoint
+    pub fn set_account_delay(&self, delay: Duration) {
+        *self.account_delay.lock().unwrap() = delay;
+    }
+
+    // set separator between base and quote of the exchange symbols, e.g. "/" for "ETH/BTC"
+    pub fn set_symbol_separator(&self, symbol_separator: &str) {
+        *self.symbol_separator.lock().unwrap() = symbol_separator.to_string();
+    }
+
+    // set minimum order increment of an instrument, taken over the referencedata step size
+    pub fn set_qty_increment(&self, instrument_id: &str, qty_increment: f64) {
+        self.qty_increments.lock().unwrap().insert(instrument_id.to_string(), qty_increment);
+    }
+
+    // script response of the next balance request
+    pub fn push_balance(&self, balance: Result<Balance, OrderingError>) {
+        self.balances.lock().unwrap().push_back(balance);
+    }
+
+    // script a filled leg, placement then status
+    pub fn push_filled(&self, symbol: &str, order_id: u64, qty: f64, price: f64) {
+        self.push_order(Ok(transaction(symbol, order_id, "FILLED", qty, qty, price)));
+        self.push_order_status(Ok(order(symbol, order_id, "FILLED", qty, qty, price)));
+    }
+
+    // return recorded calls, in call order
+    pub fn get_calls(&self) -> Vec<MockCall> {
+        return self.calls.lock().unwrap().clone();
+    }
+
+    // return recorded calls of a method
+    pub fn get_calls_of(&self, method: &str) -> Vec<MockCall> {
+        return self.get_calls().into_iter().filter(|call| call.method == method).collect();
+    }
+
+    // return recorded order placements, limit or market
+    pub fn get_placements(&self) -> Vec<MockCall> {
+        return self.get_calls().into_iter().filter(|call| call.method.starts_with("limit_") | call.method.starts_with("market_")).collect();
+    }
+
+    // record call
+    fn record(&self, method: &str, symbol: &String, qty: f64, price: f64, order_id: u64) {
+        self.calls.lock().unwrap().push(MockCall {
+            method: method.to_string(),
+            symbol: symbol.clone(),
+            qty: qty,
+            price: price,
+            order_id: order_id,
+        });
+    }
+
+    // next scripted order
+    fn next_order(&self, method: &str, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        self.record(method, &symbol, qty, price, 0);
+        let delay = self.order_delays.lock().unwrap().get(&symbol).cloned();
+        if let Some(delay) = delay {
+            thread::sleep(delay);
+        }
+        if let Some(order) = self.symbol_orders.lock().unwrap().get_mut(&symbol).and_then(|orders| orders.pop_front()) {
+            return order;
+        }
+        return next_scripted(&self.orders, method);
+    }
+}
+
+// pop the next scripted response
+fn next_scripted<T>(queue: &Mutex<VecDeque<Result<T, OrderingError>>>, method: &str) -> Result<T, OrderingError> {
+    return match queue.lock().unwrap().pop_front() {
+        Some(response) => response,
+        None => Err(OrderingError::ExchangeError(format!("mock exchange, no scripted response for {}", method)))
+    };
+}
+
+impl Exchange for MockExchange {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_symbol(&self, base: &str, quote: &str) -> String {
+        return format!("{}{}{}", base, self.symbol_separator.lock().unwrap(), quote);
+    }
+
+    fn get_qty_increment(&self, instrument_id: &str) -> Option<f64> {
+        return self.qty_increments.lock().unwrap().get(instrument_id).cloned();
+    }
+
+    fn limit_buy_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_buy_fok", symbol, qty, price);
+    }
+
+    fn limit_sell_fok(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_sell_fok", symbol, qty, price);
+    }
+
+    fn limit_buy_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_buy_ioc", symbol, qty, price);
+    }
+
+    fn limit_sell_ioc(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_sell_ioc", symbol, qty, price);
+    }
+
+    fn limit_buy(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_buy", symbol, qty, price);
+    }
+
+    fn limit_sell(&self, symbol: String, qty: f64, price: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("limit_sell", symbol, qty, price);
+    }
+
+    fn market_buy(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("market_buy", symbol, qty, 0.0);
+    }
+
+    fn market_sell(&self, symbol: String, qty: f64) -> Result<Transaction, OrderingError> {
+        return self.next_order("market_sell", symbol, qty, 0.0);
+    }
+
+    fn order_status(&self, symbol: String, order_id: u64) -> Result<Order, OrderingError> {
+        self.record("order_status", &symbol, 0.0, 0.0, order_id);
+        return next_scripted(&self.order_statuses, "order_status");
+    }
+
+    fn cancel_order(&self, symbol: String, order_id: u64) -> Result<OrderCanceled, OrderingError> {
+        self.record("cancel_order", &symbol, 0.0, 0.0, order_id);
+        return next_scripted(&self.cancels, "cancel_order");
+    }
+
+    fn get_account(&self) -> Result<AccountInformation, OrderingError> {
+        self.record("get_account", &String::new(), 0.0, 0.0, 0);
+        let account_delay = *self.account_delay.lock().unwrap();
+        thread::sleep(account_delay);
+        return next_scripted(&self.accounts, "get_account");
+    }
+
+    fn get_balance(&self, asset: String) -> Result<Balance, OrderingError> {
+        self.record("get_balance", &asset, 0.0, 0.0, 0);
+        return next_scripted(&self.balances, "get_balance");
+    }
+}
+
+// Memory Profit Sink, keeps every record in memory, profit writes can be slowed down like a loaded database
+#[derive(Default)]
+pub struct MemoryProfitSink {
+    pub profit_delay: Mutex<Duration>,
+    pub profits: Mutex<Vec<ArbitrageProfit>>,
+    pub executions: Mutex<Vec<ExecutionRecord>>,
+    pub runs: Mutex<Vec<RunRecord>>,
+    pub cycle_states: Mutex<Vec<CycleState>>,
+    pub balance_snapshots: Mutex<Vec<BalanceSnapshot>>,
+    pub latencies: Mutex<Vec<LatencyRecord>>,
+}
+
+impl ProfitSink for MemoryProfitSink {
+    fn add_profit(&self, arbitrage_profit: &ArbitrageProfit) {
+        let profit_delay = *self.profit_delay.lock().unwrap();
+        thread::sleep(profit_delay);
+        self.profits.lock().unwrap().push(arbitrage_profit.clone());
+    }
+
+    fn add_execution(&self, execution: &ExecutionRecord) {
+        self.executions.lock().unwrap().push(execution.clone());
+    }
+
+    fn add_run(&self, run: &RunRecord) {
+        self.runs.lock().unwrap().push(run.clone());
+    }
+
+    fn set_cycle_state(&self, cycle_state: &CycleState) {
+        self.cycle_states.lock().unwrap().push(cycle_state.clone());
+    }
+
+    // the last state of a cycle wins
+    fn get_suspended_cycles(&self) -> Vec<String> {
+        let mut suspended_cycles: Vec<String> = Vec::new();
+        for cycle_state in self.cycle_states.lock().unwrap().iter() {
+            suspended_cycles.retain(|name| name != &cycle_state.name);
+            if cycle_state.suspended {
+                suspended_cycles.push(cycle_state.name.clone());
+            }
+        }
+        return suspended_cycles;
+    }
+
+    fn add_balance_snapshot(&self, balance_snapshot: &BalanceSnapshot) {
+        self.balance_snapshots.lock().unwrap().push(balance_snapshot.clone());
+    }
+
+    fn get_last_balance_snapshot(&self) -> Option<BalanceSnapshot> {
+        return self.balance_snapshots.lock().unwrap().last().cloned();
+    }
+
+    fn get_executions_since(&self, since: DateTime<Utc>) -> Vec<ExecutionRecord> {
+        return self.executions.lock().unwrap().iter().filter(|e| e.create_at >= since).cloned().collect();
+    }
+
+    fn add_latency(&self, latency: &LatencyRecord) {
+        self.latencies.lock().unwrap().push(latency.clone());
+    }
+}
+
+// return ordering on the mock exchange, polling without waiting, with the sender of its profit channel
+pub fn mock_ordering(exchange: &Arc<MockExchange>, mode: ExecutionMode) -> (ArbitrageOrdering, crossbeam_channel::Sender<ArbitrageProfit>) {
+    let (arbitrage_profit_sender, arbitrage_profit_receiver) = crossbeam_channel::unbounded();
+    return (mock_ordering_on(exchange, mode, arbitrage_profit_receiver), arbitrage_profit_sender);
+}
+
+// return ordering on the mock exchange reading profits from the receiver
+fn mock_ordering_on(exchange: &Arc<MockExchange>, mode: ExecutionMode, arbitrage_profit_receiver: crossbeam_channel::Receiver<ArbitrageProfit>) -> ArbitrageOrdering {
+    let mut arbitrage_ordering = ArbitrageOrdering::with_exchange(arbitrage_profit_receiver, mode, Arc::clone(exchange) as Arc<dyn Exchange>, None);
+    arbitrage_ordering.set_order_status_poll_interval(std::time::Duration::from_millis(0));
+    arbitrage_ordering.set_order_status_max_wait(std::time::Duration::from_millis(0));
+    arbitrage_ordering.set_retry(0, std::time::Duration::from_millis(0));
+    arbitrage_ordering.set_sleep_between_transactions(std::time::Duration::from_millis(0));
+    return arbitrage_ordering;
+}
+
+// run the ordering loop until every profit was taken, then shut it down
+pub fn run_ordering(mut arbitrage_ordering: ArbitrageOrdering, arbitrage_profit_sender: &crossbeam_channel::Sender<ArbitrageProfit>, profits: Vec<ArbitrageProfit>) -> ArbitrageOrdering {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    arbitrage_ordering.set_shutdown(Arc::clone(&shutdown));
+    for arbitrage_profit in profits {
+        arbitrage_profit_sender.send(arbitrage_profit).unwrap();
+    }
+    let ordering_handle = thread::spawn(move || {
+        arbitrage_ordering.start();
+        arbitrage_ordering
+    });
+    while !arbitrage_profit_sender.is_empty() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    shutdown.store(true, atomic::Ordering::SeqCst);
+    return ordering_handle.join().unwrap();
+}
+
+// script every leg of the profit as filled
+pub fn push_filled_legs(exchange: &MockExchange, arbitrage_profit: &ArbitrageProfit) {
+    for (index, transaction) in arbitrage_profit.get_transaction_result_list().iter().enumerate() {
+        exchange.push_filled(transaction.get_exchange_code(), index as u64 + 1, transaction.get_qty_to_execute(), transaction.get_price());
+    }
+}
+
+// return executor on the mock exchange and a memory sink, profits sent to ordering arrive on the returned receiver instead
+pub fn mock_executor(exchange: &Arc<MockExchange>, start_assets: &[&str], symbol_list: &[&str], qty_in: f64, profit_threshold: f64, ordering: bool)
+                     -> (ArbitrageExecutor, Arc<MemoryProfitSink>, crossbeam_channel::Receiver<ArbitrageProfit>) {
+    let arbitrage_ordering = mock_ordering_on(exchange, ExecutionMode::SEQUENTIAL, crossbeam_channel::never());
+    let (arbitrage_profit_sender, arbitrage_profit_receiver) = crossbeam_channel::unbounded();
+    let profit_sink = Arc::new(MemoryProfitSink::default());
+    let start_assets: Vec<String> = start_assets.iter().map(|a| a.to_string()).collect();
+    let symbol_list: Vec<String> = symbol_list.iter().map(|s| s.to_string()).collect();
+    let arbitrage_executor = ArbitrageExecutor::with_ordering("BINANCE".to_string(), &start_assets, &symbol_list, qty_in, profit_threshold, ordering, 64,
+                                                              Arc::clone(&profit_sink) as Arc<dyn ProfitSink>, arbitrage_ordering, arbitrage_profit_sender, 3);
+    return (arbitrage_executor, profit_sink, arbitrage_profit_receiver);
+}
@@ -0,0 +1,47 @@
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry::trace::{Span, TraceContextExt, Tracer};
+
+use crate::izyfo_arbitrage::arbitrage::ArbitrageProfit;
+use crate::izyfo_arbitrage::arbitrage_transaction::ArbitrageTransactionResult;
+
+// tracer name
+pub const TRACER_NAME: &str = "izyfo_arbitrage";
+
+// span names
+pub const TICK_INGESTION: &str = "tick_ingestion";
+pub const CYCLE_EVALUATION: &str = "cycle_evaluation";
+pub const PROFIT_EMISSION: &str = "profit_emission";
+pub const CYCLE_EXECUTION: &str = "cycle_execution";
+pub const ORDER_LEG: &str = "order_leg";
+
+// start span as a child of the parent context, spans are no-op until a tracer provider is installed
+pub fn start_span(name: &'static str, parent: &Context, attributes: Vec<KeyValue>) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let mut span = tracer.start_with_context(name, parent);
+    for attribute in attributes {
+        span.set_attribute(attribute);
+    }
+    return parent.with_span(span);
+}
+
+// end span of the context
+pub fn end_span(context: &Context) {
+    context.span().end();
+}
+
+// cycle attributes, shared by every span of a cycle so spans from different threads correlate
+pub fn cycle_attributes(arbitrage_profit: &ArbitrageProfit) -> Vec<KeyValue> {
+    return vec![
+        KeyValue::new("cycle", arbitrage_profit.get_name().clone()),
+        KeyValue::new("uuid", arbitrage_profit.get_uuid().to_string()),
+    ];
+}
+
+// leg attributes
+pub fn leg_attributes(arbitrage_profit: &ArbitrageProfit, transaction: &ArbitrageTransactionResult) -> Vec<KeyValue> {
+    let mut attributes = cycle_attributes(arbitrage_profit);
+    attributes.push(KeyValue::new("leg_uuid", transaction.get_uuid().to_string()));
+    attributes.push(KeyValue::new("symbol", transaction.get_exchange_code().clone()));
+    attributes.push(KeyValue::new("side", transaction.get_operation().clone()));
+    return attributes;
+}
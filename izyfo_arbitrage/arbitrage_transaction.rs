@@ -1,11 +1,291 @@
 use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
 use std::ptr::null;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 
 use serde::{Deserialize, Serialize};
 
+use crate::izyfo_arbitrage::arbitrage_exchange::strip_prefix;
 use crate::izyfo_events::exchange::market_bbo::MarketBBO;
-use crate::izyfo_utils::math;
+use crate::izyfo_utils::math::{self, RoundingStrategy};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PriceSource {
+    BBO,
+    LAST_TRADE,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    InvalidAskPrice(f64),
+    InvalidBidPrice(f64),
+    InvalidAskQty(f64),
+    InvalidBidQty(f64),
+    InvalidLastPrice(f64),
+    PriceDeviation(f64),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::InvalidAskPrice(v) => write!(f, "invalid ask price: '{}'", v),
+            ValidationError::InvalidBidPrice(v) => write!(f, "invalid bid price: '{}'", v),
+            ValidationError::InvalidAskQty(v) => write!(f, "invalid ask qty: '{}'", v),
+            ValidationError::InvalidBidQty(v) => write!(f, "invalid bid qty: '{}'", v),
+            ValidationError::InvalidLastPrice(v) => write!(f, "invalid last price: '{}'", v),
+            ValidationError::PriceDeviation(v) => write!(f, "price deviation from reference mid: '{}'", v),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+// moving average weight of the latest mid in the reference mid
+pub const REFERENCE_MID_ALPHA: f64 = 0.1;
+
+// fee multiplier when fees are paid in BNB
+pub const BNB_DISCOUNT_MULTIPLIER: Decimal = Decimal::from_parts(75, 0, 0, false, 2);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum FeeKind {
+    PERCENT,
+    ABSOLUTE,
+    BNB_DISCOUNT,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Fee {
+    pub rate: Decimal,
+    pub kind: FeeKind,
+}
+
+impl Fee {
+    // return fee deducted from qty, computed in decimal so the rate is not rounded
+    pub fn get_fee(&self, qty: f64) -> f64 {
+        let qty = Decimal::from_f64(qty).unwrap_or_default();
+        let fee = match self.kind {
+            FeeKind::PERCENT => qty * self.rate,
+            FeeKind::ABSOLUTE => self.rate,
+            FeeKind::BNB_DISCOUNT => qty * self.rate * BNB_DISCOUNT_MULTIPLIER,
+        };
+        return fee.to_f64().unwrap_or_default();
+    }
+}
+
+// Liquidity of an order, a GTC limit order resting off the touch makes liquidity, crossing, FOK, IOC and market orders take it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Liquidity {
+    MAKER,
+    TAKER,
+}
+
+impl fmt::Display for Liquidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Liquidity::MAKER => write!(f, "MAKER"),
+            Liquidity::TAKER => write!(f, "TAKER"),
+        }
+    }
+}
+
+impl Liquidity {
+    // parse liquidity
+    pub fn from_str(value: &str) -> Result<Liquidity, String> {
+        match value {
+            "MAKER" => Ok(Liquidity::MAKER),
+            "TAKER" => Ok(Liquidity::TAKER),
+            _ => Err(format!("invalid liquidity '{}'", value)),
+        }
+    }
+
+    // return liquidity of an order type and time in force, an order crossing the book takes liquidity
+    pub fn of(order_type: &OrderType, time_in_force: &TimeInForce, crosses: bool) -> Liquidity {
+        if (*order_type == OrderType::LIMIT) & (*time_in_force == TimeInForce::GTC) & !crosses {
+            return Liquidity::MAKER;
+        }
+        return Liquidity::TAKER;
+    }
+}
+
+// Fee Schedule, fee rate by instrument and liquidity (e.g. a VIP tier), overriding the default trade fee
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FeeSchedule {
+    rates: HashMap<String, f64>,
+}
+
+impl FeeSchedule {
+    // return key, an instrument without liquidity applies to maker and taker orders
+    fn get_key(instrument: &String, liquidity: Option<&Liquidity>) -> String {
+        match liquidity {
+            Some(liquidity) => format!("{}:{}", instrument, liquidity),
+            None => instrument.clone(),
+        }
+    }
+
+    // set fee rate
+    pub fn set_rate(&mut self, instrument: &String, liquidity: Option<Liquidity>, rate: f64) {
+        self.rates.insert(FeeSchedule::get_key(instrument, liquidity.as_ref()), rate);
+    }
+
+    // return fee rate of the instrument and liquidity, falling back to the instrument rate
+    pub fn get_rate(&self, instrument: &String, liquidity: &Liquidity) -> Option<f64> {
+        if let Some(rate) = self.rates.get(&FeeSchedule::get_key(instrument, Some(liquidity))) {
+            return Some(*rate);
+        }
+        return self.rates.get(instrument).cloned();
+    }
+
+    // parse "instrument[:liquidity]=rate" file, e.g. "BINANCE_ADA_USDT:TAKER=0.00075"
+    pub fn from_file(path: &str) -> Result<FeeSchedule, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("failed to read fee schedule '{}': {}", path, e))?;
+        let mut fee_schedule = FeeSchedule::default();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() | line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("invalid fee schedule line: '{}'", line))
+            };
+            let rate = value.parse::<f64>().map_err(|e| format!("invalid fee rate '{}': {}", value, e))?;
+            let (instrument, liquidity) = match key.find(':') {
+                Some(i) => (key[..i].to_string(), Some(Liquidity::from_str(&key[i + 1..])?)),
+                None => (key.to_string(), None)
+            };
+            fee_schedule.set_rate(&instrument, liquidity, rate);
+        }
+        Ok(fee_schedule)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum OrderType {
+    LIMIT,
+    MARKET,
+}
+
+impl fmt::Display for OrderType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderType::LIMIT => write!(f, "LIMIT"),
+            OrderType::MARKET => write!(f, "MARKET"),
+        }
+    }
+}
+
+impl OrderType {
+    // parse order type, default to limit
+    pub fn from_str(value: &str) -> OrderType {
+        match value {
+            "MARKET" => OrderType::MARKET,
+            _ => OrderType::LIMIT,
+        }
+    }
+}
+
+// Time In Force of limit orders. FOK and IOC never rest on the book, so the sequential
+// cancel-if-NEW step never applies to them, an IOC may however come back EXPIRED partially filled.
+// A GTC order still NEW once the order status max wait elapses is cancelled by the sequential
+// execution, making it good-till-max-wait; a partially filled GTC is left resting.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TimeInForce {
+    FOK,
+    IOC,
+    GTC,
+}
+
+impl fmt::Display for TimeInForce {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeInForce::FOK => write!(f, "FOK"),
+            TimeInForce::IOC => write!(f, "IOC"),
+            TimeInForce::GTC => write!(f, "GTC"),
+        }
+    }
+}
+
+impl TimeInForce {
+    // parse time in force, default to fill-or-kill
+    pub fn from_str(value: &str) -> TimeInForce {
+        match value {
+            "IOC" => TimeInForce::IOC,
+            "GTC" => TimeInForce::GTC,
+            _ => TimeInForce::FOK,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum OrderingConstraint {
+    MARKET_QTY,
+    MIN_QTY,
+    MAX_QTY,
+    PRICE_BOUNDS,
+    MIN_NOTIONAL,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArbitrageTransactionConfig {
+    pub clamp_price: bool,
+    pub price_source: PriceSource,
+    pub rounding_strategy: RoundingStrategy,
+    pub trade_fee: Fee,
+    pub maker_fee: f64,
+    pub maker_ratio: HashMap<String, f64>,
+    pub simulation: bool,
+    pub check_units: bool,
+    pub referencedata_sizes: HashMap<String, (f64, f64)>,
+    pub fee_free_windows: HashMap<String, (f64, f64)>,
+    pub max_price_deviation: f64,
+    pub fee_schedule: FeeSchedule,
+}
+
+impl Default for ArbitrageTransactionConfig {
+    fn default() -> ArbitrageTransactionConfig {
+        ArbitrageTransactionConfig {
+            clamp_price: false,
+            price_source: PriceSource::BBO,
+            rounding_strategy: RoundingStrategy::FLOOR,
+            trade_fee: Fee {
+                rate: Decimal::new(1, 3),
+                kind: FeeKind::PERCENT,
+            },
+            maker_fee: 0.0009,
+            maker_ratio: HashMap::new(),
+            simulation: false,
+            check_units: false,
+            referencedata_sizes: HashMap::new(),
+            fee_free_windows: HashMap::new(),
+            max_price_deviation: 0.0,
+            fee_schedule: FeeSchedule::default(),
+        }
+    }
+}
+
+impl ArbitrageTransactionConfig {
+    // mark instrument fee-free between start and end, e.g. during an exchange promotion
+    pub fn set_fee_free_window(&mut self, instrument: String, start: DateTime<Utc>, end: DateTime<Utc>) {
+        let start_timestamp = start.timestamp_millis() as f64 / 1000_f64;
+        let end_timestamp = end.timestamp_millis() as f64 / 1000_f64;
+        self.fee_free_windows.insert(instrument, (start_timestamp, end_timestamp));
+    }
+
+    // check instrument is fee-free at the tick timestamp, end excluded
+    pub fn is_fee_free(&self, instrument: &String, timestamp: f64) -> bool {
+        match self.fee_free_windows.get(instrument) {
+            Some((start, end)) => (timestamp >= *start) & (timestamp < *end),
+            None => false
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ArbitrageTransaction {
@@ -15,19 +295,25 @@ pub struct ArbitrageTransaction {
     operation: String,
     instrument: String,
     exchange_code: String,
-    ask_price: f32,
-    bid_price: f32,
-    min_price: f32,
-    max_price: f32,
-    ask_qty: f32,
-    bid_qty: f32,
-    min_qty: f32,
-    max_qty: f32,
-    step_size: f32,
-    tick_size: f32,
+    ask_price: f64,
+    bid_price: f64,
+    last_price: f64,
+    min_price: f64,
+    max_price: f64,
+    ask_qty: f64,
+    bid_qty: f64,
+    min_qty: f64,
+    max_qty: f64,
+    min_notional: f64,
+    step_size: f64,
+    tick_size: f64,
     tick_timestamp: f64,
-    trade_fee: (f32, String),
+    order_type: OrderType,
+    time_in_force: TimeInForce,
+    reference_mid: f64,
+    price_deviation: f64,
     ready: bool,
+    config: ArbitrageTransactionConfig,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -38,31 +324,42 @@ pub struct ArbitrageTransactionResult {
     operation: String,
     instrument: String,
     exchange_code: String,
-    qty_in: f32,
-    qty_out: f32,
-    qty_out_r: f32,
-    qty_to_execute: f32,
-    price: f32,
-    fee: f32,
-    step_size: f32,
-    tick_size: f32,
-    min_price: f32,
-    max_price: f32,
-    min_qty: f32,
-    max_qty: f32,
+    qty_in: f64,
+    qty_out: f64,
+    qty_out_r: f64,
+    qty_to_execute: f64,
+    price: f64,
+    fee: f64,
+    step_size: f64,
+    tick_size: f64,
+    min_price: f64,
+    max_price: f64,
+    min_qty: f64,
+    max_qty: f64,
+    min_notional: f64,
     tick_timestamp: f64,
-    market_qty: f32,
+    market_qty: f64,
+    spread: f64,
+    bid_price: f64,
+    ask_price: f64,
+    order_type: OrderType,
+    time_in_force: TimeInForce,
     uuid: Uuid,
 }
 
 impl ArbitrageTransactionResult {
+    // return name
+    pub fn get_name(&self) -> &String {
+        return &self.name;
+    }
+
     // return transaction result
-    pub fn get_qty_in(&self) -> f32 {
+    pub fn get_qty_in(&self) -> f64 {
         return self.qty_in;
     }
 
     // return transaction result
-    pub fn get_qty_out(&self) -> f32 {
+    pub fn get_qty_out(&self) -> f64 {
         return self.qty_out;
     }
 
@@ -76,30 +373,162 @@ impl ArbitrageTransactionResult {
         return &self.operation;
     }
 
-    // return instrument symbol
-    pub fn get_instrument_symbol(&self) -> String {
-        return self.instrument.replace("BINANCE_", "");
+    // return instrument
+    pub fn get_instrument(&self) -> &String {
+        return &self.instrument;
+    }
+
+    // return instrument symbol, without the exchange prefix
+    pub fn get_instrument_symbol(&self, prefix: &str) -> String {
+        return strip_prefix(&self.instrument, prefix);
+    }
+
+    // return exchange prefix of the instrument id EXCHANGE_BASE_QUOTE
+    fn get_instrument_prefix(&self) -> String {
+        return match self.instrument.find('_') {
+            Some(index) => self.instrument[..index + 1].to_string(),
+            None => String::new()
+        };
     }
 
-    pub fn get_price(&self) -> f32 {
+    pub fn get_price(&self) -> f64 {
         return self.price;
     }
 
+    // return fee, in the output asset
+    pub fn get_fee(&self) -> f64 {
+        return self.fee;
+    }
+
+    // return spread at detection
+    pub fn get_spread(&self) -> f64 {
+        return self.spread;
+    }
+
+    // return effective spread crossed by a fill, twice the signed distance from the detection mid, none without a quote
+    pub fn get_effective_spread(&self, fill_price: f64) -> Option<f64> {
+        if (self.bid_price <= 0.0) | (self.ask_price <= 0.0) {
+            return None;
+        }
+        let mid = (self.bid_price + self.ask_price) / 2.0;
+        if self.operation == "BUY" {
+            return Some(2.0 * (fill_price - mid));
+        } else if self.operation == "SELL" {
+            return Some(2.0 * (mid - fill_price));
+        }
+        return None;
+    }
+
+    // return qty out when unwound at market with the price moved against by one spread
+    pub fn get_unwind_qty_out(&self) -> f64 {
+        let qty_out_gross = self.qty_out + self.fee;
+        let fee_rate = if qty_out_gross > 0.0 { self.fee / qty_out_gross } else { 0.0 };
+
+        if self.operation == "BUY" {
+            let price = self.price + self.spread;
+            if price <= 0.0 {
+                return 0.0;
+            }
+            return self.qty_in / price * (1.0 - fee_rate);
+        } else if self.operation == "SELL" {
+            let price = (self.price - self.spread).max(0.0);
+            return self.qty_in * price * (1.0 - fee_rate);
+        }
+        return self.qty_out;
+    }
+
     pub fn get_exchange_code(&self) -> &String {
         return &self.exchange_code;
     }
 
-    pub fn get_qty_to_execute(&self) -> f32 {
+    pub fn get_qty_to_execute(&self) -> f64 {
         return self.qty_to_execute;
     }
 
-    pub fn get_market_qty(&self) -> f32 {
+    pub fn get_market_qty(&self) -> f64 {
         return self.market_qty;
     }
 
+    // return step size
+    pub fn get_step_size(&self) -> f64 {
+        return self.step_size;
+    }
+
+    // return min qty
+    pub fn get_min_qty(&self) -> f64 {
+        return self.min_qty;
+    }
+
+    // return min notional, price * qty, 0 when the exchange has none
+    pub fn get_min_notional(&self) -> f64 {
+        return self.min_notional;
+    }
+
+    // return result scaled to the filled ratio of a previous leg, qty to execute floored to the step
+    pub fn scale(&self, ratio: f64) -> ArbitrageTransactionResult {
+        let mut result = self.clone();
+        let qty_to_execute = self.qty_to_execute * ratio;
+        result.qty_in = self.qty_in * ratio;
+        result.qty_to_execute = math::round_to_step(qty_to_execute, self.step_size, &RoundingStrategy::FLOOR);
+        result.qty_out = self.qty_out * ratio;
+        result.fee = self.fee * ratio;
+        return result;
+    }
+
     // check transaction is valid for ordering
     pub fn is_valid_ordering(&self) -> bool {
-        return (self.qty_to_execute <= self.market_qty) & (self.min_qty <= self.qty_to_execute) & (self.qty_to_execute <= self.max_qty);
+        return self.get_ordering_failures().is_empty();
+    }
+
+    // return ordering constraints the transaction fails
+    pub fn get_ordering_failures(&self) -> Vec<OrderingConstraint> {
+        let mut failures: Vec<OrderingConstraint> = Vec::new();
+        if self.qty_to_execute > self.market_qty {
+            failures.push(OrderingConstraint::MARKET_QTY);
+        }
+        if self.qty_to_execute < self.min_qty {
+            failures.push(OrderingConstraint::MIN_QTY);
+        }
+        if self.qty_to_execute > self.max_qty {
+            failures.push(OrderingConstraint::MAX_QTY);
+        }
+        if !self.is_valid_price() {
+            failures.push(OrderingConstraint::PRICE_BOUNDS);
+        }
+        if (self.min_notional > 0.0) & (self.price * self.qty_to_execute < self.min_notional) {
+            failures.push(OrderingConstraint::MIN_NOTIONAL);
+        }
+        return failures;
+    }
+
+    // check price within filter bounds, a zero bound is disabled
+    pub fn is_valid_price(&self) -> bool {
+        return ((self.min_price <= 0.0) | (self.min_price <= self.price)) & ((self.max_price <= 0.0) | (self.price <= self.max_price));
+    }
+
+    // check qty units, qty_to_execute is always in base asset
+    // BUY: source is quote, qty_to_execute * price spends at most qty_in (one step of rounding allowed)
+    // SELL: source is base, qty_to_execute is at most qty_in and qty_out is in quote
+    pub fn is_valid_units(&self) -> bool {
+        let prefix = self.get_instrument_prefix();
+        let symbol = self.get_instrument_symbol(&prefix);
+        let source = strip_prefix(&self.source, &prefix);
+        let target = strip_prefix(&self.target, &prefix);
+        let epsilon = 1e-4 * self.qty_in.abs().max(1.0);
+
+        if self.operation == "BUY" {
+            let base_ok = symbol.starts_with(&target) & symbol.ends_with(&source);
+            let qty_ok = self.qty_to_execute * self.price <= self.qty_in + self.step_size * self.price + epsilon;
+            let out_ok = (self.qty_out + self.fee - self.qty_to_execute).abs() <= 1e-4 * self.qty_to_execute.abs().max(1.0);
+            return base_ok & qty_ok & out_ok;
+        } else if self.operation == "SELL" {
+            let base_ok = symbol.starts_with(&source) & symbol.ends_with(&target);
+            let qty_ok = self.qty_to_execute <= self.qty_in + self.step_size + epsilon;
+            let qty_out_gross = self.qty_to_execute * self.price;
+            let out_ok = (self.qty_out + self.fee - qty_out_gross).abs() <= 1e-4 * qty_out_gross.abs().max(1.0);
+            return base_ok & qty_ok & out_ok;
+        }
+        return false;
     }
 
     // return source
@@ -116,6 +545,16 @@ impl ArbitrageTransactionResult {
     pub fn get_uuid(&self) -> Uuid {
         return self.uuid;
     }
+
+    // return order type
+    pub fn get_order_type(&self) -> &OrderType {
+        return &self.order_type;
+    }
+
+    // return time in force
+    pub fn get_time_in_force(&self) -> &TimeInForce {
+        return &self.time_in_force;
+    }
 }
 
 impl ArbitrageTransaction {
@@ -129,62 +568,124 @@ impl ArbitrageTransaction {
             instrument: instrument,
             bid_price: 0.0,
             ask_price: 0.0,
+            last_price: 0.0,
             min_price: 0.0,
             max_price: 0.0,
             bid_qty: 0.0,
             ask_qty: 0.0,
             min_qty: 0.0,
             max_qty: 0.0,
+            min_notional: 0.0,
             step_size: 0.0,
             tick_size: 0.0,
-            trade_fee: (0.001, "%".to_string()),
+            order_type: OrderType::LIMIT,
+            time_in_force: TimeInForce::FOK,
+            reference_mid: 0.0,
+            price_deviation: 0.0,
             ready: false,
+            config: ArbitrageTransactionConfig::default(),
             tick_timestamp: 0.0,
             exchange_code: exchange_code,
         }
     }
 
+    // set order type
+    pub fn set_order_type(&mut self, order_type: OrderType) {
+        self.order_type = order_type;
+    }
+
+    // set time in force
+    pub fn set_time_in_force(&mut self, time_in_force: TimeInForce) {
+        self.time_in_force = time_in_force;
+    }
+
+    // set config
+    pub fn set_config(&mut self, config: ArbitrageTransactionConfig) {
+        self.config = config;
+    }
+
     // update
     pub fn update(&mut self, tick: &MarketBBO) {
-        self.ask_price = tick.get_ask_price();
-        self.bid_price = tick.get_bid_price();
-        self.min_price = tick.get_min_price();
-        self.max_price = tick.get_max_price();
-
-        self.ask_qty = tick.get_ask_qty();
-        self.bid_qty = tick.get_bid_qty();
-        self.min_qty = tick.get_min_qty();
-        self.max_qty = tick.get_max_qty();
-
-        self.step_size = tick.get_step_size();
-        self.tick_size = tick.get_tick_size();
+        self.ask_price = to_f64(tick.get_ask_price());
+        self.bid_price = to_f64(tick.get_bid_price());
+        self.min_price = to_f64(tick.get_min_price());
+        self.max_price = to_f64(tick.get_max_price());
+
+        self.ask_qty = to_f64(tick.get_ask_qty());
+        self.bid_qty = to_f64(tick.get_bid_qty());
+        self.min_qty = to_f64(tick.get_min_qty());
+        self.max_qty = to_f64(tick.get_max_qty());
+        self.min_notional = to_f64(tick.get_min_notional());
+
+        self.step_size = to_f64(tick.get_step_size());
+        self.tick_size = to_f64(tick.get_tick_size());
+
+        // zero step or tick means unknown, use referencedata
+        if (self.step_size == 0.0) | (self.tick_size == 0.0) {
+            if let Some((step_size, tick_size)) = self.config.referencedata_sizes.get(&self.instrument) {
+                if self.step_size == 0.0 {
+                    self.step_size = *step_size;
+                }
+                if self.tick_size == 0.0 {
+                    self.tick_size = *tick_size;
+                }
+            }
+        }
         self.tick_timestamp = tick.get_marketdata_timestamp();
+        self.update_reference_mid();
         self.ready = true;
     }
 
+    // measure the mid deviation from the reference mid, then fold the mid into the reference
+    fn update_reference_mid(&mut self) {
+        if (self.ask_price <= 0.0) | (self.bid_price <= 0.0) {
+            return;
+        }
+        let mid = (self.ask_price + self.bid_price) / 2.0;
+        if self.reference_mid > 0.0 {
+            self.price_deviation = (mid - self.reference_mid).abs() / self.reference_mid;
+            self.reference_mid += REFERENCE_MID_ALPHA * (mid - self.reference_mid);
+        } else {
+            self.price_deviation = 0.0;
+            self.reference_mid = mid;
+        }
+    }
+
+    // update last trade price
+    pub fn update_last_price(&mut self, last_price: f64) {
+        self.last_price = last_price;
+    }
+
     // update
-    pub fn is_valid(&self) -> Result<bool, String> {
-        if self.ask_price <= 0.0 {
-            Err(format!("invalid ask price: '{}'", self.ask_price))
+    pub fn is_valid(&self) -> Result<bool, ValidationError> {
+        if (self.config.price_source == PriceSource::LAST_TRADE) & (self.last_price <= 0.0) {
+            Err(ValidationError::InvalidLastPrice(self.last_price))
+        } else if self.ask_price <= 0.0 {
+            Err(ValidationError::InvalidAskPrice(self.ask_price))
         } else if self.bid_price <= 0.0 {
-            Err(format!("invalid bid price: '{}'", self.bid_price))
+            Err(ValidationError::InvalidBidPrice(self.bid_price))
         } else if self.ask_qty <= 0.0 {
-            Err(format!("invalid bid price: '{}'", self.ask_qty))
+            Err(ValidationError::InvalidAskQty(self.ask_qty))
         } else if self.bid_qty <= 0.0 {
-            Err(format!("invalid bid price: '{}'", self.bid_qty))
+            Err(ValidationError::InvalidBidQty(self.bid_qty))
+        } else if (self.config.max_price_deviation > 0.0) & (self.price_deviation > self.config.max_price_deviation) {
+            Err(ValidationError::PriceDeviation(self.price_deviation))
         } else {
             Ok(true)
         }
     }
 
     // execute transaction
-    pub fn execute(&self, qty_in: f32) -> ArbitrageTransactionResult {
-        let mut qty_out: f32;
+    pub fn execute(&self, qty_in: f64) -> ArbitrageTransactionResult {
+        let mut qty_out: f64;
 
 
         if self.operation == "BUY" {
             // get price
-            let mut price = self.ask_price;
+            let mut price = match self.config.price_source {
+                PriceSource::BBO => self.ask_price,
+                PriceSource::LAST_TRADE => self.last_price,
+            };
             price = self.normalize_price(price);
 
             // calculate qty
@@ -192,10 +693,7 @@ impl ArbitrageTransaction {
             qty_to_execute = self.normalize_qty(qty_to_execute);
 
             // calculate fee
-            let mut fee: f32 = 0.0;
-            if self.trade_fee.1 == "%" {
-                fee = qty_to_execute * self.trade_fee.0;
-            }
+            let fee: f64 = self.get_trade_fee(price).get_fee(qty_to_execute);
 
             // remove fee
             qty_out = qty_to_execute - fee;
@@ -220,8 +718,14 @@ impl ArbitrageTransaction {
                 max_price: self.max_price.clone(),
                 min_qty: self.min_qty.clone(),
                 max_qty: self.max_qty.clone(),
+                min_notional: self.min_notional,
                 exchange_code: self.exchange_code.clone(),
                 market_qty: self.ask_qty,
+                spread: self.ask_price - self.bid_price,
+                bid_price: self.bid_price,
+                ask_price: self.ask_price,
+                order_type: self.order_type.clone(),
+                time_in_force: self.time_in_force.clone(),
                 uuid: Uuid::new_v4(),
             }
         } else if self.operation == "SELL" {
@@ -229,17 +733,17 @@ impl ArbitrageTransaction {
             let normalize_qty = self.normalize_qty(qty_in);
 
             // price
-            let mut price = self.bid_price;
+            let mut price = match self.config.price_source {
+                PriceSource::BBO => self.bid_price,
+                PriceSource::LAST_TRADE => self.last_price,
+            };
             price = self.normalize_price(price);
 
             // round out
             qty_out = normalize_qty * price;
 
             // calculate fee
-            let mut fee: f32 = 0.0;
-            if self.trade_fee.1 == "%" {
-                fee = qty_out * self.trade_fee.0;
-            }
+            let fee: f64 = self.get_trade_fee(price).get_fee(qty_out);
             // remove fee
             qty_out = qty_out - fee;
 
@@ -263,8 +767,14 @@ impl ArbitrageTransaction {
                 max_price: self.max_price.clone(),
                 min_qty: self.min_qty.clone(),
                 max_qty: self.max_qty.clone(),
+                min_notional: self.min_notional,
                 exchange_code: self.exchange_code.clone(),
                 market_qty: self.bid_qty,
+                spread: self.ask_price - self.bid_price,
+                bid_price: self.bid_price,
+                ask_price: self.ask_price,
+                order_type: self.order_type.clone(),
+                time_in_force: self.time_in_force.clone(),
                 uuid: Uuid::new_v4(),
             }
         } else {
@@ -287,9 +797,15 @@ impl ArbitrageTransaction {
                 max_price: self.max_price.clone(),
                 min_qty: self.min_qty.clone(),
                 max_qty: self.max_qty.clone(),
+                min_notional: self.min_notional,
                 qty_out_r: 0.0,
                 exchange_code: self.exchange_code.clone(),
                 market_qty: self.ask_qty,
+                spread: self.ask_price - self.bid_price,
+                bid_price: self.bid_price,
+                ask_price: self.ask_price,
+                order_type: self.order_type.clone(),
+                time_in_force: self.time_in_force.clone(),
                 uuid: Uuid::new_v4(),
             }
         }
@@ -311,31 +827,514 @@ impl ArbitrageTransaction {
         return &self.source;
     }
 
-    fn normalize_qty(&self, qty: f32) -> f32 {
-        //check step size
-        if !self.step_size.is_nan() {
-            if self.step_size == 1.0 {
-                return qty.trunc();
-            } else {
-                let round_count: usize = self.step_size.to_string().len() - 2;
-                return math::round_down(qty, round_count);
+    pub fn get_target(&self) -> &String {
+        return &self.target;
+    }
+
+    pub fn get_tick_timestamp(&self) -> f64 {
+        return self.tick_timestamp;
+    }
+
+    // check units enforcement enabled
+    pub fn is_check_units(&self) -> bool {
+        return self.config.check_units;
+    }
+
+    // return true when an order at price crosses the book, a buy at or above the ask, a sell at or below the bid
+    fn crosses_book(&self, price: f64) -> bool {
+        if self.operation == "BUY" {
+            return price >= self.ask_price;
+        }
+        return price <= self.bid_price;
+    }
+
+    // return fee rate at the order price from the fee schedule, else the taker fee, a simulation blends maker and taker fees by the assumed maker ratio of the instrument
+    fn get_fee_rate(&self, price: f64) -> Decimal {
+        if self.config.is_fee_free(&self.instrument, self.tick_timestamp) {
+            return Decimal::ZERO;
+        }
+        let liquidity = Liquidity::of(&self.order_type, &self.time_in_force, self.crosses_book(price));
+        if let Some(rate) = self.config.fee_schedule.get_rate(&self.instrument, &liquidity) {
+            return Decimal::from_f64(rate).unwrap_or_default();
+        }
+        let taker_fee = self.config.trade_fee.rate;
+
+        // the fill mix is a backtest assumption, never applied to live fees
+        if !self.config.simulation {
+            return taker_fee;
+        }
+        let maker_ratio = Decimal::from_f64(self.config.maker_ratio.get(&self.instrument).cloned().unwrap_or(0.0)).unwrap_or_default();
+        let maker_fee = Decimal::from_f64(self.config.maker_fee).unwrap_or_default();
+        return maker_ratio * maker_fee + (Decimal::ONE - maker_ratio) * taker_fee;
+    }
+
+    // return trade fee of the configured kind at the fee rate of this transaction at price
+    fn get_trade_fee(&self, price: f64) -> Fee {
+        return Fee {
+            rate: self.get_fee_rate(price),
+            kind: self.config.trade_fee.kind.clone(),
+        };
+    }
+
+    // return qty rounding strategy, conservative rounds qty down
+    fn get_qty_rounding_strategy(&self) -> RoundingStrategy {
+        match self.config.rounding_strategy {
+            RoundingStrategy::CONSERVATIVE => RoundingStrategy::FLOOR,
+            ref rounding_strategy => rounding_strategy.clone(),
+        }
+    }
+
+    // return price rounding strategy, conservative rounds buy price up and sell price down
+    fn get_price_rounding_strategy(&self) -> RoundingStrategy {
+        match self.config.rounding_strategy {
+            RoundingStrategy::CONSERVATIVE => {
+                if self.operation == "BUY" {
+                    RoundingStrategy::CEIL
+                } else {
+                    RoundingStrategy::FLOOR
+                }
             }
-        } else {
-            return qty;
+            ref rounding_strategy => rounding_strategy.clone(),
         }
     }
 
-    fn normalize_price(&self, price: f32) -> f32 {
-        //check step size
-        if !self.tick_size.is_nan() {
-            if self.tick_size == 1.0 {
-                return price.trunc();
-            } else {
-                let round_count: usize = self.tick_size.to_string().len() - 2;
-                return math::round_down(price, round_count);
+    fn normalize_qty(&self, qty: f64) -> f64 {
+        return math::round_to_step(qty, self.step_size, &self.get_qty_rounding_strategy());
+    }
+
+    fn normalize_price(&self, price: f64) -> f64 {
+        let mut price = price;
+
+        // clamp into price filter bounds, bounds are on the tick grid
+        if self.config.clamp_price {
+            if (self.min_price > 0.0) & (price < self.min_price) {
+                price = self.min_price;
+            } else if (self.max_price > 0.0) & (price > self.max_price) {
+                price = self.max_price;
             }
-        } else {
-            return price;
         }
+
+        return math::round_to_step(price, self.tick_size, &self.get_price_rounding_strategy());
+    }
+}
+
+// widen market data value to f64 through its shortest decimal form, so 0.001 stays 0.001
+pub fn to_f64(value: f32) -> f64 {
+    return value.to_string().parse::<f64>().unwrap_or(value as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::izyfo_arbitrage::arbitrage_testing::{self, QuoteFixture};
+
+    // ETH bought with BTC on ETHBTC
+    fn eth_btc_buy(config: ArbitrageTransactionConfig, quote: &QuoteFixture) -> ArbitrageTransaction {
+        let mut arbitrage_transaction = ArbitrageTransaction::new("BINANCE_BTC".to_string(), "BINANCE_ETH".to_string(), "BUY".to_string(),
+                                                                  "BINANCE_ETH_BTC".to_string(), "ETHBTC".to_string());
+        arbitrage_transaction.set_config(config);
+        arbitrage_transaction.update(&quote.build());
+        return arbitrage_transaction;
+    }
+
+    // ETHBTC quote with the ask above the price filter
+    fn above_max_price_quote() -> QuoteFixture {
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        quote.min_price = 0.01;
+        quote.max_price = 0.049;
+        return quote;
+    }
+
+    #[test]
+    fn price_above_max_price_is_rejected() {
+        let arbitrage_transaction = eth_btc_buy(ArbitrageTransactionConfig::default(), &above_max_price_quote());
+
+        let result = arbitrage_transaction.execute(1.0);
+
+        assert_eq!(result.get_price(), 0.05);
+        assert!(!result.is_valid_price());
+        assert!(!result.is_valid_ordering());
+    }
+
+    #[test]
+    fn price_above_max_price_is_clamped() {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.clamp_price = true;
+        let arbitrage_transaction = eth_btc_buy(config, &above_max_price_quote());
+
+        let result = arbitrage_transaction.execute(1.0);
+
+        assert_eq!(result.get_price(), 0.049);
+        assert!(result.is_valid_price());
+    }
+
+    #[test]
+    fn price_within_bounds_is_unchanged() {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.clamp_price = true;
+        let arbitrage_transaction = eth_btc_buy(config, &arbitrage_testing::triangle_quotes()[0]);
+
+        let result = arbitrage_transaction.execute(1.0);
+
+        assert_eq!(result.get_price(), 0.05);
+        assert!(result.is_valid_price());
+    }
+
+    #[test]
+    fn each_invalid_field_yields_its_variant() {
+        let mut zero_ask_price = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        zero_ask_price.ask_price = 0.0;
+        let mut zero_bid_price = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        zero_bid_price.bid_price = 0.0;
+        let mut zero_ask_qty = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        zero_ask_qty.ask_qty = 0.0;
+        let mut zero_bid_qty = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        zero_bid_qty.bid_qty = 0.0;
+
+        let config = ArbitrageTransactionConfig::default();
+        assert_eq!(eth_btc_buy(config.clone(), &zero_ask_price).is_valid(), Err(ValidationError::InvalidAskPrice(0.0)));
+        assert_eq!(eth_btc_buy(config.clone(), &zero_bid_price).is_valid(), Err(ValidationError::InvalidBidPrice(0.0)));
+        assert_eq!(eth_btc_buy(config.clone(), &zero_ask_qty).is_valid(), Err(ValidationError::InvalidAskQty(0.0)));
+        assert_eq!(eth_btc_buy(config.clone(), &zero_bid_qty).is_valid(), Err(ValidationError::InvalidBidQty(0.0)));
+        assert_eq!(eth_btc_buy(config, &arbitrage_testing::triangle_quotes()[0]).is_valid(), Ok(true));
+    }
+
+    #[test]
+    fn missing_last_trade_yields_invalid_last_price() {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.price_source = PriceSource::LAST_TRADE;
+
+        let arbitrage_transaction = eth_btc_buy(config, &arbitrage_testing::triangle_quotes()[0]);
+
+        assert_eq!(arbitrage_transaction.is_valid(), Err(ValidationError::InvalidLastPrice(0.0)));
+    }
+
+    #[test]
+    fn validation_messages_name_the_failing_field() {
+        assert_eq!(ValidationError::InvalidAskQty(0.0).to_string(), "invalid ask qty: '0'");
+        assert_eq!(ValidationError::InvalidBidQty(0.0).to_string(), "invalid bid qty: '0'");
+    }
+
+    // ETH bought on a quote with the step or tick size set, under a rounding strategy
+    fn rounded_buy(rounding_strategy: RoundingStrategy, ask_price: f32, step_size: f32, tick_size: f32) -> ArbitrageTransactionResult {
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, ask_price, 1000.0);
+        quote.step_size = step_size;
+        quote.tick_size = tick_size;
+        let mut config = ArbitrageTransactionConfig::default();
+        config.rounding_strategy = rounding_strategy;
+        return eth_btc_buy(config, &quote).execute(1.0);
+    }
+
+    #[test]
+    fn qty_is_rounded_with_each_strategy() {
+        // 1 BTC buys 16.666.. ETH at 0.06
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.06, 0.01, 0.0).get_qty_to_execute(), 16.66);
+        assert_eq!(rounded_buy(RoundingStrategy::CEIL, 0.06, 0.01, 0.0).get_qty_to_execute(), 16.67);
+        assert_eq!(rounded_buy(RoundingStrategy::NEAREST, 0.06, 0.01, 0.0).get_qty_to_execute(), 16.67);
+        assert_eq!(rounded_buy(RoundingStrategy::CONSERVATIVE, 0.06, 0.01, 0.0).get_qty_to_execute(), 16.66);
+    }
+
+    #[test]
+    fn price_is_rounded_with_each_strategy() {
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.0606, 0.0, 0.001).get_price(), 0.06);
+        assert_eq!(rounded_buy(RoundingStrategy::CEIL, 0.0606, 0.0, 0.001).get_price(), 0.061);
+        assert_eq!(rounded_buy(RoundingStrategy::NEAREST, 0.0606, 0.0, 0.001).get_price(), 0.061);
+        // a conservative buy never pays less than the ask
+        assert_eq!(rounded_buy(RoundingStrategy::CONSERVATIVE, 0.0606, 0.0, 0.001).get_price(), 0.061);
+    }
+
+    #[test]
+    fn exact_step_multiple_is_kept_by_every_strategy() {
+        for rounding_strategy in vec![RoundingStrategy::FLOOR, RoundingStrategy::CEIL, RoundingStrategy::NEAREST, RoundingStrategy::CONSERVATIVE] {
+            assert_eq!(rounded_buy(rounding_strategy, 0.05, 0.1, 0.0).get_qty_to_execute(), 20.0);
+        }
+    }
+
+    // fee rate of the ETHBTC buy with an assumed maker ratio
+    fn fee_with_maker_ratio(maker_ratio: f64, simulation: bool) -> Decimal {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.maker_ratio.insert("BINANCE_ETH_BTC".to_string(), maker_ratio);
+        config.simulation = simulation;
+        return eth_btc_buy(config, &QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0)).get_fee_rate(0.05);
+    }
+
+    #[test]
+    fn simulated_maker_fills_cost_less_than_taker_fills() {
+        let maker_fee = fee_with_maker_ratio(1.0, true);
+        let taker_fee = fee_with_maker_ratio(0.0, true);
+
+        assert_eq!(maker_fee, Decimal::new(9, 4));
+        assert_eq!(taker_fee, Decimal::new(1, 3));
+        assert!(maker_fee < taker_fee);
+    }
+
+    #[test]
+    fn maker_ratio_leaves_live_fees_unchanged() {
+        assert_eq!(fee_with_maker_ratio(1.0, false), Decimal::new(1, 3));
+    }
+
+    // ETH bought for 1 BTC with the trade fee
+    fn buy_with_fee(rate: Decimal, kind: FeeKind) -> ArbitrageTransactionResult {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.trade_fee = Fee {
+            rate: rate,
+            kind: kind,
+        };
+        return eth_btc_buy(config, &QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0)).execute(1.0);
+    }
+
+    #[test]
+    fn percent_fee_deducts_the_rate_of_the_qty() {
+        let result = buy_with_fee(Decimal::new(1, 3), FeeKind::PERCENT);
+
+        assert!((result.get_fee() - result.get_qty_to_execute() * 0.001).abs() < 1e-12);
+        assert!((result.get_qty_out() - (result.get_qty_to_execute() - result.get_fee())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn absolute_fee_deducts_the_rate() {
+        let result = buy_with_fee(Decimal::new(5, 1), FeeKind::ABSOLUTE);
+
+        assert_eq!(result.get_fee(), 0.5);
+        assert!((result.get_qty_out() - (result.get_qty_to_execute() - 0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bnb_discount_fee_deducts_the_discounted_rate_of_the_qty() {
+        let result = buy_with_fee(Decimal::new(1, 3), FeeKind::BNB_DISCOUNT);
+
+        assert!((result.get_fee() - result.get_qty_to_execute() * 0.00075).abs() < 1e-12);
+        assert!((result.get_qty_out() - (result.get_qty_to_execute() - result.get_fee())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fee_is_exact_in_decimal() {
+        let fee = Fee {
+            rate: Decimal::new(1, 3),
+            kind: FeeKind::PERCENT,
+        };
+
+        assert_eq!(fee.get_fee(20.0), 0.02);
+    }
+
+    // leg on an instrument, updated with its quote
+    fn quoted_leg(source: &str, target: &str, operation: &str, quote: &QuoteFixture) -> ArbitrageTransaction {
+        let exchange_code = quote.instrument.replace("BINANCE_", "").replace('_', "");
+        let mut arbitrage_transaction = ArbitrageTransaction::new(format!("BINANCE_{}", source), format!("BINANCE_{}", target), operation.to_string(),
+                                                                  quote.instrument.clone(), exchange_code);
+        arbitrage_transaction.update(&quote.build());
+        return arbitrage_transaction;
+    }
+
+    #[test]
+    fn buy_qty_to_execute_is_in_the_base_asset() {
+        let quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+
+        let result = quoted_leg("BTC", "ETH", "BUY", &quote).execute(1.0);
+
+        // 1 BTC spent buys about 20 ETH
+        assert!((result.get_qty_to_execute() - 1.0 / to_f64(0.05)).abs() < 1e-9);
+        assert!((result.get_qty_out() - (result.get_qty_to_execute() - result.get_fee())).abs() < 1e-12);
+        assert!(result.is_valid_units());
+    }
+
+    #[test]
+    fn sell_qty_to_execute_is_in_the_base_asset() {
+        let quote = QuoteFixture::new("BINANCE_ETH_USDT", 2000.0, 1000.0, 2001.0, 1000.0);
+
+        let result = quoted_leg("ETH", "USDT", "SELL", &quote).execute(2.0);
+
+        // 2 ETH sold, proceeds in USDT
+        assert_eq!(result.get_qty_to_execute(), 2.0);
+        assert!((result.get_qty_out() + result.get_fee() - 4000.0).abs() < 1e-9);
+        assert!(result.is_valid_units());
+    }
+
+    #[test]
+    fn leg_with_inverted_units_is_invalid() {
+        let quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+
+        // selling BTC on ETH_BTC would spend the quote asset as base
+        let result = quoted_leg("BTC", "ETH", "SELL", &quote).execute(1.0);
+
+        assert!(!result.is_valid_units());
+    }
+
+    #[test]
+    fn zero_step_falls_back_to_the_referencedata_step() {
+        let quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.06, 1000.0);
+        let mut config = ArbitrageTransactionConfig::default();
+        config.referencedata_sizes.insert("BINANCE_ETH_BTC".to_string(), (0.01, 0.0001));
+
+        let result = eth_btc_buy(config, &quote).execute(1.0);
+
+        // 1 BTC buys 16.666.. ETH at 0.06, floored to the referencedata step
+        assert_eq!(result.get_qty_to_execute(), 16.66);
+    }
+
+    #[test]
+    fn quoted_step_is_kept_over_the_referencedata_step() {
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.06, 1000.0);
+        quote.step_size = 0.1;
+        let mut config = ArbitrageTransactionConfig::default();
+        config.referencedata_sizes.insert("BINANCE_ETH_BTC".to_string(), (0.01, 0.0001));
+
+        let result = eth_btc_buy(config, &quote).execute(1.0);
+
+        assert_eq!(result.get_qty_to_execute(), 16.6);
+    }
+
+    #[test]
+    fn small_priced_buy_keeps_its_precision() {
+        let quote = QuoteFixture::new("BINANCE_SHIB_USDT", 0.00000811, 1000000000.0, 0.00000812, 1000000000.0);
+
+        let result = quoted_leg("USDT", "SHIB", "BUY", &quote).execute(1000.0);
+
+        // 123152709.36.. SHIB, beyond the 7 significant digits of f32
+        assert!((result.get_qty_to_execute() - 1000.0 / 0.00000812).abs() < 1e-3);
+        assert_eq!(result.get_price(), 0.00000812);
+    }
+
+    #[test]
+    fn eight_decimal_qty_is_kept() {
+        let quote = QuoteFixture::new("BINANCE_BTC_USDT", 30000.0, 1000.0, 30001.0, 1000.0);
+
+        let result = quoted_leg("BTC", "USDT", "SELL", &quote).execute(0.12345678);
+
+        assert_eq!(result.get_qty_to_execute(), 0.12345678);
+    }
+
+    #[test]
+    fn effective_spread_of_a_buy_fill_above_the_detection_mid() {
+        let quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+
+        let result = quoted_leg("BTC", "ETH", "BUY", &quote).execute(1.0);
+
+        // detection mid 0.04995, filled one tick above the ask
+        assert!((result.get_effective_spread(0.0501).unwrap() - 0.0003).abs() < 1e-9);
+        assert!((result.get_effective_spread(0.05).unwrap() - result.get_spread()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn effective_spread_of_a_sell_fill_below_the_detection_mid() {
+        let quote = QuoteFixture::new("BINANCE_ETH_USDT", 2000.0, 1000.0, 2001.0, 1000.0);
+
+        let result = quoted_leg("ETH", "USDT", "SELL", &quote).execute(2.0);
+
+        // detection mid 2000.5, filled one below the bid
+        assert!((result.get_effective_spread(1999.0).unwrap() - 3.0).abs() < 1e-9);
+    }
+
+    // fee rate of the ETHBTC buy quoted at the timestamp, fee-free from 10s to 20s
+    fn fee_in_promo_window(marketdata_timestamp: f64) -> Decimal {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.set_fee_free_window("BINANCE_ETH_BTC".to_string(), Utc.timestamp(10, 0), Utc.timestamp(20, 0));
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        quote.marketdata_timestamp = marketdata_timestamp;
+        return eth_btc_buy(config, &quote).get_fee_rate(0.05);
+    }
+
+    #[test]
+    fn promo_symbol_is_fee_free_within_the_window() {
+        assert_eq!(fee_in_promo_window(10.0), Decimal::ZERO);
+        assert_eq!(fee_in_promo_window(15.0), Decimal::ZERO);
+    }
+
+    #[test]
+    fn promo_symbol_pays_the_normal_fee_outside_the_window() {
+        assert_eq!(fee_in_promo_window(5.0), Decimal::new(1, 3));
+        assert_eq!(fee_in_promo_window(20.0), Decimal::new(1, 3));
+    }
+
+    // fee rate of an ETHBTC buy at price with the order type and time in force, on a 0.02% maker and 0.04% taker schedule, the ask is 0.05
+    fn scheduled_fee_rate(order_type: OrderType, time_in_force: TimeInForce, price: f64) -> Decimal {
+        let mut config = ArbitrageTransactionConfig::default();
+        config.fee_schedule.set_rate(&"BINANCE_ETH_BTC".to_string(), Some(Liquidity::MAKER), 0.0002);
+        config.fee_schedule.set_rate(&"BINANCE_ETH_BTC".to_string(), Some(Liquidity::TAKER), 0.0004);
+        let mut arbitrage_transaction = eth_btc_buy(config, &QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0));
+        arbitrage_transaction.set_order_type(order_type);
+        arbitrage_transaction.set_time_in_force(time_in_force);
+        return arbitrage_transaction.get_fee_rate(price);
+    }
+
+    #[test]
+    fn gtc_limit_order_below_the_ask_pays_the_maker_fee() {
+        assert_eq!(scheduled_fee_rate(OrderType::LIMIT, TimeInForce::GTC, 0.0499), Decimal::new(2, 4));
+    }
+
+    #[test]
+    fn gtc_limit_order_crossing_the_ask_pays_the_taker_fee() {
+        assert_eq!(scheduled_fee_rate(OrderType::LIMIT, TimeInForce::GTC, 0.05), Decimal::new(4, 4));
+    }
+
+    #[test]
+    fn fok_ioc_and_market_orders_pay_the_taker_fee() {
+        assert_eq!(scheduled_fee_rate(OrderType::LIMIT, TimeInForce::FOK, 0.0499), Decimal::new(4, 4));
+        assert_eq!(scheduled_fee_rate(OrderType::LIMIT, TimeInForce::IOC, 0.0499), Decimal::new(4, 4));
+        assert_eq!(scheduled_fee_rate(OrderType::MARKET, TimeInForce::GTC, 0.0499), Decimal::new(4, 4));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fee_schedule_file_is_keyed_by_instrument_and_liquidity() {
+        let path = std::env::temp_dir().join(format!("izyfo-fee-schedule-{}.conf", Uuid::new_v4())).to_string_lossy().to_string();
+        fs::write(&path, "# vip 1\nBINANCE_ETH_BTC:MAKER=0.0002\nBINANCE_ETH_BTC:TAKER=0.0004\nBINANCE_BTC_USDT=0.00075\n").unwrap();
+
+        let fee_schedule = FeeSchedule::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(fee_schedule.get_rate(&"BINANCE_ETH_BTC".to_string(), &Liquidity::MAKER), Some(0.0002));
+        assert_eq!(fee_schedule.get_rate(&"BINANCE_ETH_BTC".to_string(), &Liquidity::TAKER), Some(0.0004));
+        assert_eq!(fee_schedule.get_rate(&"BINANCE_BTC_USDT".to_string(), &Liquidity::MAKER), Some(0.00075));
+        assert_eq!(fee_schedule.get_rate(&"BINANCE_ETH_USDT".to_string(), &Liquidity::TAKER), None);
+    }
+
+    #[test]
+    fn fee_schedule_file_with_an_unknown_liquidity_is_rejected() {
+        let path = std::env::temp_dir().join(format!("izyfo-fee-schedule-{}.conf", Uuid::new_v4())).to_string_lossy().to_string();
+        fs::write(&path, "BINANCE_ETH_BTC:MAKR=0.0002\n").unwrap();
+
+        let fee_schedule = FeeSchedule::from_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(fee_schedule.unwrap_err(), "invalid liquidity 'MAKR'");
+    }
+
+    // ETHBTC buy of 1 BTC, a 1 BTC notional, against the min notional
+    fn eth_btc_buy_with_min_notional(min_notional: f32) -> ArbitrageTransactionResult {
+        let mut quote = QuoteFixture::new("BINANCE_ETH_BTC", 0.0499, 1000.0, 0.05, 1000.0);
+        quote.min_notional = min_notional;
+        return eth_btc_buy(ArbitrageTransactionConfig::default(), &quote).execute(1.0);
+    }
+
+    #[test]
+    fn leg_below_min_notional_is_not_valid_for_ordering() {
+        let result = eth_btc_buy_with_min_notional(2.0);
+
+        assert_eq!(result.get_min_notional(), 2.0);
+        assert_eq!(result.get_ordering_failures(), vec![OrderingConstraint::MIN_NOTIONAL]);
+        assert!(!result.is_valid_ordering());
+    }
+
+    #[test]
+    fn leg_above_min_notional_is_valid_for_ordering() {
+        assert!(eth_btc_buy_with_min_notional(0.5).is_valid_ordering());
+    }
+
+    #[test]
+    fn qty_is_floored_to_a_multiple_of_the_step_size() {
+        // 1 BTC buys 16.666.. ETH at 0.06
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.06, 0.001, 0.0).get_qty_to_execute(), 16.666);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.06, 0.1, 0.0).get_qty_to_execute(), 16.6);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.06, 1.0, 0.0).get_qty_to_execute(), 16.0);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 0.06, 10.0, 0.0).get_qty_to_execute(), 10.0);
+    }
+
+    #[test]
+    fn price_is_floored_to_a_multiple_of_the_tick_size() {
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 12.3456, 0.0, 0.001).get_price(), 12.345);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 12.3456, 0.0, 0.1).get_price(), 12.3);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 12.3456, 0.0, 1.0).get_price(), 12.0);
+        assert_eq!(rounded_buy(RoundingStrategy::FLOOR, 12.3456, 0.0, 10.0).get_price(), 10.0);
+    }
+}
@@ -2,4 +2,11 @@ pub mod arbitrage_executor;
 pub mod arbitrage;
 pub mod arbitrage_transaction;
 pub mod arbitrage_database;
-pub mod arbitrage_ordering;
\ No newline at end of file
+pub mod arbitrage_ordering;
+pub mod arbitrage_clock;
+pub mod arbitrage_sink;
+pub mod arbitrage_tracing;
+pub mod arbitrage_exchange;
+pub mod arbitrage_stats;
+#[cfg(test)]
+pub mod arbitrage_testing;
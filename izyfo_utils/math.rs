@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+// rounding of a qty or price, conservative rounds against the profit of the leg
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RoundingStrategy {
+    FLOOR,
+    CEIL,
+    NEAREST,
+    CONSERVATIVE,
+}
+
+// round value down to decimals
+pub fn round_down(value: f32, round_count: usize) -> f32 {
+    let factor = 10_f32.powi(round_count as i32);
+    return (value * factor).floor() / factor;
+}
+
+// tolerance, in steps, absorbing float error so 0.3 / 0.1 still counts 3 steps
+const STEP_EPSILON: f64 = 1e-9;
+
+// round value to a multiple of step following the rounding strategy, a zero or nan step leaves the value unchanged
+// single step rounding of the crate, used by the transaction and ordering qty normalization
+pub fn round_to_step(value: f64, step: f64, rounding_strategy: &RoundingStrategy) -> f64 {
+    if step.is_nan() | (step <= 0.0) {
+        return value;
+    }
+    let steps = value / step;
+    let steps = match rounding_strategy {
+        RoundingStrategy::FLOOR | RoundingStrategy::CONSERVATIVE => (steps + STEP_EPSILON).floor(),
+        RoundingStrategy::CEIL => (steps - STEP_EPSILON).ceil(),
+        RoundingStrategy::NEAREST => steps.round(),
+    };
+
+    // drop the float noise of the product, e.g. 3 * 0.1
+    return round_with_strategy(steps * step, step_decimals(step), &RoundingStrategy::NEAREST);
+}
+
+// return decimals of step, e.g. 3 for 0.001 and 0 for 10
+fn step_decimals(step: f64) -> usize {
+    let mut decimals: usize = 0;
+    let mut scaled = step;
+    while (decimals < 16) & ((scaled - scaled.round()).abs() > STEP_EPSILON * scaled.max(1.0)) {
+        decimals += 1;
+        scaled = step * 10_f64.powi(decimals as i32);
+    }
+    return decimals;
+}
+
+// round value to decimals following the rounding strategy
+pub fn round_with_strategy(value: f64, round_count: usize, rounding_strategy: &RoundingStrategy) -> f64 {
+    let factor = 10_f64.powi(round_count as i32);
+    match rounding_strategy {
+        RoundingStrategy::FLOOR | RoundingStrategy::CONSERVATIVE => {
+            if round_count == 0 {
+                value.trunc()
+            } else {
+                (value * factor).floor() / factor
+            }
+        }
+        RoundingStrategy::CEIL => (value * factor).ceil() / factor,
+        RoundingStrategy::NEAREST => (value * factor).round() / factor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_error_of_an_exact_multiple_keeps_the_step() {
+        assert_eq!(round_to_step(0.3, 0.1, &RoundingStrategy::FLOOR), 0.3);
+        assert_eq!(round_to_step(0.3, 0.1, &RoundingStrategy::CEIL), 0.3);
+    }
+
+    #[test]
+    fn zero_or_nan_step_leaves_the_value_unchanged() {
+        assert_eq!(round_to_step(1.23456, 0.0, &RoundingStrategy::FLOOR), 1.23456);
+        assert_eq!(round_to_step(1.23456, f64::NAN, &RoundingStrategy::FLOOR), 1.23456);
+    }
+
+    #[test]
+    fn value_is_rounded_to_decimals_with_each_strategy() {
+        assert_eq!(round_with_strategy(1.2345, 2, &RoundingStrategy::FLOOR), 1.23);
+        assert_eq!(round_with_strategy(1.2345, 2, &RoundingStrategy::CEIL), 1.24);
+        assert_eq!(round_with_strategy(1.2355, 2, &RoundingStrategy::NEAREST), 1.24);
+        assert_eq!(step_decimals(0.001), 3);
+        assert_eq!(step_decimals(10.0), 0);
+    }
+
+    #[test]
+    fn value_is_rounded_down_to_decimals() {
+        assert_eq!(round_down(1.2389, 2), 1.23);
+        assert_eq!(round_down(7.9, 0), 7.0);
+    }
+}